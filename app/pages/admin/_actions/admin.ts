@@ -9,11 +9,13 @@ export type AdminLinkListItem = {
   created_at: string;
   expires_at: string | null;
   enabled: boolean;
+  expired: boolean;
   disabled_at: string | null;
   last_access_at: string | null;
   creator_ip: string | null;
   creator_user_agent: string | null;
   creator_request_id: string | null;
+  creator_metadata_expired: boolean;
 };
 
 export type AdminLinkListResponse = {
@@ -31,6 +33,7 @@ export type AdminAccessLogItem = {
 
 export type AdminAccessLogResponse = {
   items: AdminAccessLogItem[];
+  next_page_state: string | null;
 };
 
 function getApiEndpoint(): string {
@@ -49,6 +52,11 @@ async function backendFetch(
   const url = new URL(path, base).toString();
 
   const outgoing = new Headers(init?.headers);
+  const adminToken = process.env.ADMIN_API_TOKEN;
+  if (adminToken && !outgoing.has("authorization")) {
+    outgoing.set("authorization", `Bearer ${adminToken}`);
+  }
+
   const incoming = await nextHeaders();
   const ua = incoming.get("user-agent");
   if (ua && !outgoing.has("user-agent")) {
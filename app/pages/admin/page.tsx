@@ -56,6 +56,11 @@ export default async function AdminPage(props: {
                 <tbody>
                   {links.map((l) => {
                     const isEnabled = l.enabled;
+                    const status = !isEnabled
+                      ? "disabled"
+                      : l.expired
+                        ? "expired"
+                        : "enabled";
                     const detailHref = `/pages/admin/${encodeURIComponent(l.id)}`;
                     return (
                       <tr
@@ -97,12 +102,18 @@ export default async function AdminPage(props: {
                         </td>
                         <td className="px-3 py-2">
                           <Link href={detailHref} className="block">
-                            <div className="text-muted-foreground text-xs">
-                              <div>{l.creator_ip || "-"}</div>
-                              <div className="break-all">
-                                {l.creator_user_agent || "-"}
+                            {l.creator_metadata_expired ? (
+                              <div className="text-muted-foreground text-xs">
+                                Expired (retention)
                               </div>
-                            </div>
+                            ) : (
+                              <div className="text-muted-foreground text-xs">
+                                <div>{l.creator_ip || "-"}</div>
+                                <div className="break-all">
+                                  {l.creator_user_agent || "-"}
+                                </div>
+                              </div>
+                            )}
                           </Link>
                         </td>
                         <td className="px-3 py-2">
@@ -113,7 +124,7 @@ export default async function AdminPage(props: {
                                 : "bg-secondary text-foreground border-border inline-flex rounded-full border px-2 py-0.5 text-xs"
                             }
                           >
-                            {isEnabled ? "enabled" : "disabled"}
+                            {status}
                           </span>
                         </td>
                         <td className="px-3 py-2">
@@ -0,0 +1,27 @@
+use envconfig::Envconfig;
+use strum::EnumString;
+use valuable::Valuable;
+
+/// What happens to an access event when the queue is full.
+#[derive(EnumString, Debug, Valuable, Clone, Copy, PartialEq, Eq)]
+#[strum(ascii_case_insensitive)]
+pub enum OverflowPolicy {
+    /// Drop the event and count it, so redirects never wait on storage.
+    Drop,
+    /// Wait for room in the queue, applying backpressure to redirects.
+    Block,
+}
+
+#[derive(Envconfig, Debug, Valuable, Clone)]
+pub struct Config {
+    /// When false, access events are written inline by the redirect handler.
+    #[envconfig(from = "ACCESS_LOG_ASYNC", default = "true")]
+    pub async_enabled: bool,
+
+    #[envconfig(from = "ACCESS_LOG_QUEUE_SIZE", default = "10000")]
+    pub queue_size: usize,
+    #[envconfig(from = "ACCESS_LOG_BATCH_SIZE", default = "100")]
+    pub batch_size: usize,
+    #[envconfig(from = "ACCESS_LOG_OVERFLOW_POLICY", default = "drop")]
+    pub overflow_policy: OverflowPolicy,
+}
@@ -0,0 +1,162 @@
+use std::sync::Mutex;
+
+use tokio::{
+    sync::{
+        mpsc::{self, error::TrySendError},
+        oneshot,
+    },
+    task::JoinHandle,
+};
+
+use crate::{
+    access_log::config::{Config, OverflowPolicy},
+    domain::{models::AccessEvent, repository::ShortenedURLRepository},
+    metrics::registry::METRICS,
+};
+
+/// Queues access events for a background worker that writes them in batches,
+/// keeping storage writes off the redirect path.
+pub struct AccessLogger {
+    tx: mpsc::Sender<AccessEvent>,
+    policy: OverflowPolicy,
+    worker: Mutex<Option<(oneshot::Sender<()>, JoinHandle<()>)>>,
+}
+
+impl AccessLogger {
+    pub fn spawn<T>(repo: T, config: &Config) -> Self
+    where
+        T: ShortenedURLRepository + Send + Sync + 'static,
+    {
+        let (tx, rx) = mpsc::channel(config.queue_size.max(1));
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let handle = tokio::spawn(run(repo, rx, shutdown_rx, config.batch_size.max(1)));
+
+        Self {
+            tx,
+            policy: config.overflow_policy,
+            worker: Mutex::new(Some((shutdown_tx, handle))),
+        }
+    }
+
+    pub async fn record(&self, event: AccessEvent) {
+        let dropped = match self.policy {
+            OverflowPolicy::Drop => match self.tx.try_send(event) {
+                Ok(()) => None,
+                Err(TrySendError::Full(_)) => Some("queue_full"),
+                Err(TrySendError::Closed(_)) => Some("closed"),
+            },
+            OverflowPolicy::Block => self.tx.send(event).await.err().map(|_| "closed"),
+        };
+        if let Some(reason) = dropped {
+            record_dropped(reason, 1);
+        }
+    }
+
+    /// Stops accepting events and waits until everything queued so far is written.
+    pub async fn shutdown(&self) {
+        let worker = self.worker.lock().unwrap_or_else(|e| e.into_inner()).take();
+        let Some((shutdown_tx, handle)) = worker else {
+            return;
+        };
+        let _ = shutdown_tx.send(());
+        if let Err(e) = handle.await {
+            tracing::error!(error = %e, "Access log worker failed");
+        }
+    }
+}
+
+async fn run<T>(
+    repo: T,
+    mut rx: mpsc::Receiver<AccessEvent>,
+    mut shutdown_rx: oneshot::Receiver<()>,
+    batch_size: usize,
+) where
+    T: ShortenedURLRepository,
+{
+    let mut batch = Vec::with_capacity(batch_size);
+    loop {
+        tokio::select! {
+            // Takes whatever is queued, up to `batch_size`, so batches grow
+            // with load without holding events back when traffic is light.
+            received = rx.recv_many(&mut batch, batch_size) => {
+                if received == 0 {
+                    break;
+                }
+                write_batch(&repo, &mut batch).await;
+            }
+            _ = &mut shutdown_rx => {
+                rx.close();
+                while rx.recv_many(&mut batch, batch_size).await > 0 {
+                    write_batch(&repo, &mut batch).await;
+                }
+                tracing::info!("Access log queue flushed");
+                break;
+            }
+        }
+    }
+}
+
+async fn write_batch<T>(repo: &T, batch: &mut Vec<AccessEvent>)
+where
+    T: ShortenedURLRepository,
+{
+    if let Err(e) = repo.log_access_batch(batch).await {
+        tracing::warn!(error = %e, events = batch.len(), "Failed to write access log batch");
+        record_dropped("write_failed", batch.len());
+    }
+    batch.clear();
+}
+
+fn record_dropped(reason: &str, count: usize) {
+    METRICS
+        .access_log_dropped_events
+        .with_label_values(&[reason])
+        .inc_by(count as u64);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use chrono::Utc;
+    use envconfig::Envconfig;
+
+    use super::*;
+    use crate::{domain::models::AccessLogFilter, memory::repository::InMemoryRepository};
+
+    #[tokio::test]
+    async fn test_shutdown_flushes_queued_events() {
+        let repo = Arc::new(InMemoryRepository::new());
+        let config = Config::init_from_hashmap(&HashMap::from([(
+            "ACCESS_LOG_OVERFLOW_POLICY".to_string(),
+            "block".to_string(),
+        )]))
+        .unwrap();
+        let logger = AccessLogger::spawn(Arc::clone(&repo), &config);
+
+        let now = Utc::now();
+        for i in 0..250 {
+            logger
+                .record(AccessEvent {
+                    id: "abcde".to_string(),
+                    ts: now + chrono::Duration::milliseconds(i),
+                    ip: None,
+                    user_agent: None,
+                    request_id: None,
+                    status_code: 308,
+                    update_last_access: true,
+                })
+                .await;
+        }
+        logger.shutdown().await;
+
+        let logs = repo
+            .list_access_logs_page("abcde", &AccessLogFilter::default(), 500, None)
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(logs.len(), 250);
+        let (last_ts, _) = repo.get_last_access("abcde").await.unwrap().unwrap();
+        assert_eq!(last_ts, now + chrono::Duration::milliseconds(249));
+    }
+}
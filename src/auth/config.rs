@@ -0,0 +1,29 @@
+use envconfig::Envconfig;
+use valuable::Valuable;
+
+#[derive(Envconfig, Debug, Valuable, Clone)]
+pub struct Config {
+    #[envconfig(from = "ADMIN_AUTH_ENABLED", default = "true")]
+    pub enabled: bool,
+
+    /// Comma separated list of `<subject>:<role>:<token>`.
+    #[valuable(skip)]
+    #[envconfig(from = "ADMIN_STATIC_TOKENS", default = "")]
+    pub static_tokens: String,
+
+    /// PEM encoded public key or JWKS (JSON) file used to verify admin JWTs.
+    #[envconfig(from = "ADMIN_JWT_KEY_PATH")]
+    pub jwt_key_path: Option<String>,
+
+    #[envconfig(from = "ADMIN_JWT_ALGORITHM", default = "RS256")]
+    pub jwt_algorithm: String,
+
+    #[envconfig(from = "ADMIN_JWT_ISSUER")]
+    pub jwt_issuer: Option<String>,
+
+    #[envconfig(from = "ADMIN_JWT_AUDIENCE")]
+    pub jwt_audience: Option<String>,
+
+    #[envconfig(from = "ADMIN_JWT_ROLE_CLAIM", default = "role")]
+    pub jwt_role_claim: String,
+}
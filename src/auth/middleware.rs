@@ -0,0 +1,106 @@
+use actix_web::{
+    Error, FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError,
+    body::{EitherBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::{Method, header},
+    middleware::Next,
+    web,
+};
+use std::future::{Ready, ready};
+use thiserror::Error;
+
+use crate::auth::authenticator::{AdminAuth, Principal, Role};
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("Missing or invalid credentials")]
+    Unauthorized,
+    #[error("Insufficient role")]
+    Forbidden,
+}
+
+impl ResponseError for AuthError {
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            AuthError::Unauthorized => HttpResponse::Unauthorized()
+                .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+                .body("Unauthorized"),
+            AuthError::Forbidden => HttpResponse::Forbidden().body("Forbidden"),
+        }
+    }
+}
+
+/// Read-only requests need `Role::Viewer`, everything else mutates state and
+/// needs `Role::Operator`.
+fn required_role(method: &Method) -> Role {
+    match *method {
+        Method::GET | Method::HEAD => Role::Viewer,
+        _ => Role::Operator,
+    }
+}
+
+fn bearer_token(req: &ServiceRequest) -> Option<&str> {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| {
+            v.strip_prefix("Bearer ")
+                .or_else(|| v.strip_prefix("bearer "))
+        })
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+pub async fn require_admin<B: MessageBody>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    let reject = |req: ServiceRequest, err: AuthError| {
+        Ok(req
+            .into_response(err.error_response())
+            .map_into_right_body())
+    };
+
+    let Some(auth) = req.app_data::<web::Data<AdminAuth>>().cloned() else {
+        return reject(req, AuthError::Unauthorized);
+    };
+
+    let principal = if auth.enabled() {
+        let Some(principal) = bearer_token(&req).and_then(|token| auth.authenticate(token)) else {
+            return reject(req, AuthError::Unauthorized);
+        };
+
+        if !principal.allows(required_role(req.method())) {
+            tracing::info!(
+                event = "admin_forbidden",
+                subject = principal.subject.as_str(),
+                role = %principal.role,
+                path = req.path()
+            );
+            return reject(req, AuthError::Forbidden);
+        }
+        principal
+    } else {
+        Principal::anonymous()
+    };
+
+    req.extensions_mut().insert(principal);
+
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+impl FromRequest for Principal {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<Principal>()
+                .cloned()
+                .ok_or_else(|| AuthError::Unauthorized.into()),
+        )
+    }
+}
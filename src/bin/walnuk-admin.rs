@@ -0,0 +1,615 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration as StdDuration,
+};
+
+use anyhow::anyhow;
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Duration, Utc};
+use envconfig::Envconfig;
+use serde::Serialize;
+use walnuk::{
+    domain::{
+        id::{
+            self, ID,
+            generator::{self, IdGenerator},
+        },
+        models::{AccessLogFilter, AuditAction, AuditEntry, CreateOutcome},
+        repository::ShortenedURLRepository,
+    },
+    handler::{
+        self,
+        handlers::{
+            AdminAccessLogItem, AdminLinkListItem, AdminLinkListResponse, id_policy,
+            parse_destination, parse_reason, resolve_expires_at, state_snapshot,
+        },
+    },
+    scylla::{self, consistency::CheckReport, db::DB},
+};
+
+const USAGE: &str = "\
+Usage: walnuk-admin [--output table|json] <command>
+
+Commands:
+  check [--dry-run | --repair]
+      Cross-check the ScyllaDB link tables and report orphan, missing and
+      outdated rows. Nothing is written unless --repair is given.
+  create <url> [--id <custom-id>] [--expires-in <seconds> | --expires-at <rfc3339>]
+         [--reason <text>]
+      Create a link. Custom IDs follow the server's CUSTOM_ID_* rules.
+  get <id>
+      Show a link with its state, last access and creator metadata.
+  list [--limit <n>] [--page-state <token>]
+      List links, newest first. Pass the printed token to get the next page.
+  disable <id> [--reason <text>]
+  restore <id> [--reason <text>]
+      Stop or resume redirecting a link. Servers may keep serving a cached
+      redirect for up to REDIRECT_CACHE_TTL_SECONDS.
+  tail <id> [--limit <n>] [--status <code>] [--follow] [--interval <seconds>]
+      Print the latest access log entries, oldest first. With --follow, keep
+      polling for new entries.
+
+Connection settings are read from the same SCYLLA_*, ID_* and CUSTOM_ID_*
+environment variables as the server. Changes are recorded in the admin audit
+log as walnuk-admin:$USER.";
+
+/// Recorded as the creator's user agent for links made with this tool.
+const USER_AGENT: &str = "walnuk-admin";
+
+/// Who changes made with this tool are attributed to in the audit log.
+fn actor() -> String {
+    let user = std::env::var("USER").unwrap_or_default();
+    let user = if user.trim().is_empty() {
+        "unknown"
+    } else {
+        user.trim()
+    };
+    format!("{}:{}", USER_AGENT, user)
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Table,
+    Json,
+}
+
+/// Positional arguments and options of one command.
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<&'static str, String>,
+    flags: HashSet<&'static str>,
+}
+
+impl Args {
+    /// `options` take a value, `flags` do not.
+    fn parse(
+        command: &str,
+        args: &[String],
+        options: &[&'static str],
+        flags: &[&'static str],
+    ) -> Self {
+        let mut parsed = Args {
+            positional: Vec::new(),
+            options: HashMap::new(),
+            flags: HashSet::new(),
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                parsed.positional.push(arg.clone());
+            } else if let Some(&option) = options.iter().find(|o| **o == arg) {
+                let Some(value) = args.next() else {
+                    usage_error(&format!("Missing value for {}", option));
+                };
+                parsed.options.insert(option, value.clone());
+            } else if let Some(&flag) = flags.iter().find(|f| **f == arg) {
+                parsed.flags.insert(flag);
+            } else {
+                usage_error(&format!("Unknown option for {}: {}", command, arg));
+            }
+        }
+        parsed
+    }
+
+    /// The single positional argument, named `name` in errors.
+    fn one(&self, command: &str, name: &str) -> &str {
+        match self.positional.as_slice() {
+            [value] => value,
+            [] => usage_error(&format!("Missing <{}> for {}", name, command)),
+            _ => usage_error(&format!("Too many arguments for {}", command)),
+        }
+    }
+
+    fn none(&self, command: &str) {
+        if !self.positional.is_empty() {
+            usage_error(&format!("Too many arguments for {}", command));
+        }
+    }
+
+    fn value<V: std::str::FromStr>(&self, option: &str) -> Option<V> {
+        self.options.get(option).map(|value| {
+            value.parse().unwrap_or_else(|_| {
+                usage_error(&format!("Invalid value for {}: {}", option, value))
+            })
+        })
+    }
+}
+
+fn print_json<V: Serialize>(value: &V) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths = headers.iter().map(|h| h.len()).collect::<Vec<_>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let print_row = |cells: &mut dyn Iterator<Item = &str>| {
+        let line = cells
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    };
+    print_row(&mut headers.iter().copied());
+    for row in rows {
+        print_row(&mut row.iter().map(String::as_str));
+    }
+}
+
+fn print_fields(fields: Vec<(&str, String)>) {
+    for (label, value) in fields {
+        println!("{:<34}{}", format!("{}:", label), value);
+    }
+}
+
+fn format_time(ts: Option<DateTime<Utc>>) -> String {
+    ts.map_or_else(|| "-".to_string(), |ts| ts.to_rfc3339())
+}
+
+fn format_opt(value: Option<&str>) -> String {
+    value.unwrap_or("-").to_string()
+}
+
+fn print_link(link: &AdminLinkListItem, format: OutputFormat) -> anyhow::Result<()> {
+    if format == OutputFormat::Json {
+        return print_json(link);
+    }
+    let creator_meta = |value: Option<&str>| {
+        if link.creator_metadata_expired {
+            "expired (retention)".to_string()
+        } else {
+            format_opt(value)
+        }
+    };
+    print_fields(vec![
+        ("id", link.id.0.clone()),
+        ("original url", link.original_url.to_string()),
+        ("created at", link.created_at.to_rfc3339()),
+        ("expires at", format_time(link.expires_at)),
+        ("enabled", link.enabled.to_string()),
+        ("expired", link.expired.to_string()),
+        ("disabled at", format_time(link.disabled_at)),
+        ("last access at", format_time(link.last_access_at)),
+        ("creator ip", creator_meta(link.creator_ip.as_deref())),
+        (
+            "creator user agent",
+            creator_meta(link.creator_user_agent.as_deref()),
+        ),
+        (
+            "creator request id",
+            creator_meta(link.creator_request_id.as_deref()),
+        ),
+    ]);
+    Ok(())
+}
+
+fn print_check_report(report: &CheckReport, repair: bool) {
+    let id_seq = match report.id_seq {
+        Some(check) => format!(
+            "{} (highest generated: {}){}",
+            check.current,
+            check
+                .max_generated
+                .map_or_else(|| "none".to_string(), |max| max.to_string()),
+            if check.is_behind() { ", behind" } else { "" }
+        ),
+        None => "not checked (random IDs)".to_string(),
+    };
+    let mut lines = vec![
+        ("links scanned", report.links.scanned.to_string()),
+        (
+            "links missing state",
+            report.links.missing_state.to_string(),
+        ),
+        (
+            "links missing from listing",
+            report.links.missing_listing.to_string(),
+        ),
+        (
+            "links with stale listing",
+            report.links.stale_listing.to_string(),
+        ),
+        ("orphan short_url_state", report.orphan_state.to_string()),
+        (
+            "orphan short_urls_by_created_at",
+            report.orphan_listing.to_string(),
+        ),
+        (
+            "orphan short_url_last_access",
+            report.orphan_last_access.to_string(),
+        ),
+        (
+            "orphan short_url_create_meta",
+            report.orphan_create_meta.to_string(),
+        ),
+        ("id_seq", id_seq),
+    ];
+    if repair {
+        lines.push((
+            "repaired",
+            (report.links.repaired + report.repaired).to_string(),
+        ));
+    }
+    print_fields(lines);
+}
+
+async fn connect() -> anyhow::Result<Arc<DB>> {
+    Ok(Arc::new(
+        DB::new(scylla::config::Config::init_from_env()?).await?,
+    ))
+}
+
+/// Records a change that has already been made, so a failure only warns.
+async fn record_audit(repo: &Arc<DB>, entry: &AuditEntry) {
+    if let Err(e) = repo.record_audit(entry).await {
+        eprintln!(
+            "Failed to record audit entry {}: {:#}",
+            serde_json::to_string(entry).unwrap_or_default(),
+            e
+        );
+    }
+}
+
+/// Looks up a link by ID, failing when it does not exist.
+async fn find_link(repo: &Arc<DB>, id: &str) -> anyhow::Result<AdminLinkListItem> {
+    let url = repo
+        .find_by_id(ID::new(id.to_string()))
+        .await?
+        .ok_or_else(|| anyhow!("Link not found: {}", id))?;
+    AdminLinkListItem::load(repo, url, Utc::now()).await
+}
+
+async fn check(args: &[String]) -> anyhow::Result<i32> {
+    let args = Args::parse("check", args, &[], &["--dry-run", "--repair"]);
+    args.none("check");
+    let repair = scylla::consistency::repair_requested(
+        args.flags.contains("--dry-run"),
+        args.flags.contains("--repair"),
+    )
+    .unwrap_or_else(|message| usage_error(message));
+
+    let db = connect().await?;
+    let report = scylla::consistency::check(&db, repair).await?;
+    print_check_report(&report, repair);
+    Ok(report.exit_code(repair))
+}
+
+async fn create(args: &[String], format: OutputFormat) -> anyhow::Result<i32> {
+    let args = Args::parse(
+        "create",
+        args,
+        &["--id", "--expires-in", "--expires-at", "--reason"],
+        &[],
+    );
+    let url = parse_destination(args.one("create", "url"))?;
+
+    let reason = parse_reason(args.options.get("--reason").map(String::as_str))?;
+    let config = handler::config::Config::init_from_env()?;
+    let custom_id = args.options.get("--id").map(String::as_str);
+    if let Some(custom_id) = custom_id {
+        id_policy(&config).validate(custom_id)?;
+    }
+
+    let now = Utc::now();
+    let expires_at = resolve_expires_at(
+        &config,
+        args.value::<DateTime<Utc>>("--expires-at"),
+        args.value::<i64>("--expires-in"),
+        now,
+    )?;
+
+    let repo = connect().await?;
+    let shortened = match repo.create(url, custom_id, expires_at).await? {
+        CreateOutcome::Created(url) => {
+            let id = url.id.0.as_str();
+            repo.save_create_meta_if_absent(id, url.created_at, None, Some(USER_AGENT), None)
+                .await?;
+            repo.log_create(
+                id,
+                now,
+                None,
+                Some(USER_AGENT),
+                url.original_url.as_str(),
+                None,
+            )
+            .await?;
+
+            let mut entry = AuditEntry::new(&actor(), AuditAction::Create, id, now);
+            entry.reason = reason;
+            entry.after = Some(serde_json::json!({
+                "original_url": url.original_url,
+                "expires_at": url.expires_at,
+            }));
+            record_audit(&repo, &entry).await;
+            url
+        }
+        CreateOutcome::AlreadyExists(url) => {
+            eprintln!("Link {} already points at this URL", url.id.0);
+            url
+        }
+    };
+
+    let link = AdminLinkListItem::load(&repo, shortened, now).await?;
+    print_link(&link, format)?;
+    Ok(0)
+}
+
+async fn get(args: &[String], format: OutputFormat) -> anyhow::Result<i32> {
+    let args = Args::parse("get", args, &[], &[]);
+    let repo = connect().await?;
+    let link = find_link(&repo, args.one("get", "id")).await?;
+    print_link(&link, format)?;
+    Ok(0)
+}
+
+async fn list(args: &[String], format: OutputFormat) -> anyhow::Result<i32> {
+    let args = Args::parse("list", args, &["--limit", "--page-state"], &[]);
+    args.none("list");
+    let limit = args.value::<i32>("--limit").unwrap_or(20).clamp(1, 100);
+    let paging_state = args
+        .options
+        .get("--page-state")
+        .map(|token| URL_SAFE_NO_PAD.decode(token))
+        .transpose()
+        .map_err(|_| anyhow!("Invalid --page-state"))?;
+
+    let repo = connect().await?;
+    let (urls, next_page_state) = repo.list_by_created_at_page(limit, paging_state).await?;
+    let now = Utc::now();
+    let mut items = Vec::with_capacity(urls.len());
+    for url in urls {
+        items.push(AdminLinkListItem::load(&repo, url, now).await?);
+    }
+    let next_page_state = next_page_state.map(|raw| URL_SAFE_NO_PAD.encode(raw));
+
+    match format {
+        OutputFormat::Json => print_json(&AdminLinkListResponse {
+            items,
+            next_page_state,
+        })?,
+        OutputFormat::Table => {
+            let rows = items
+                .iter()
+                .map(|link| {
+                    vec![
+                        link.id.0.clone(),
+                        if link.enabled { "yes" } else { "no" }.to_string(),
+                        if link.expired { "yes" } else { "no" }.to_string(),
+                        link.created_at.to_rfc3339(),
+                        format_time(link.expires_at),
+                        format_time(link.last_access_at),
+                        link.original_url.to_string(),
+                    ]
+                })
+                .collect::<Vec<_>>();
+            print_table(
+                &[
+                    "ID",
+                    "ENABLED",
+                    "EXPIRED",
+                    "CREATED AT",
+                    "EXPIRES AT",
+                    "LAST ACCESS",
+                    "URL",
+                ],
+                &rows,
+            );
+            if let Some(token) = next_page_state {
+                println!("\nNext page: --page-state {}", token);
+            }
+        }
+    }
+    Ok(0)
+}
+
+async fn set_enabled(
+    command: &str,
+    args: &[String],
+    enabled: bool,
+    format: OutputFormat,
+) -> anyhow::Result<i32> {
+    let args = Args::parse(command, args, &["--reason"], &[]);
+    let reason = parse_reason(args.options.get("--reason").map(String::as_str))?;
+    let repo = connect().await?;
+    let id = find_link(&repo, args.one(command, "id")).await?.id.0;
+
+    let before = repo.get_state(&id).await?;
+    let now = Utc::now();
+    repo.set_enabled(&id, enabled, now).await?;
+    let after = repo.get_state(&id).await?;
+
+    let action = if enabled {
+        AuditAction::Restore
+    } else {
+        AuditAction::Disable
+    };
+    let mut entry = AuditEntry::new(&actor(), action, &id, now);
+    entry.reason = reason;
+    entry.before = Some(state_snapshot(before.as_ref()));
+    entry.after = Some(state_snapshot(after.as_ref()));
+    record_audit(&repo, &entry).await;
+
+    let link = find_link(&repo, &id).await?;
+    print_link(&link, format)?;
+    Ok(0)
+}
+
+fn print_access_logs(items: &[AdminAccessLogItem], format: OutputFormat) -> anyhow::Result<()> {
+    match format {
+        // One object per line, so a followed tail can be piped into jq.
+        OutputFormat::Json => {
+            for item in items {
+                println!("{}", serde_json::to_string(item)?);
+            }
+        }
+        OutputFormat::Table => {
+            for item in items {
+                println!(
+                    "{}  {}  {}  {}  {}",
+                    item.ts.to_rfc3339(),
+                    item.status_code,
+                    format_opt(item.ip.as_deref()),
+                    format_opt(item.request_id.as_deref()),
+                    format_opt(item.user_agent.as_deref()),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn tail(args: &[String], format: OutputFormat) -> anyhow::Result<i32> {
+    let args = Args::parse(
+        "tail",
+        args,
+        &["--limit", "--status", "--interval"],
+        &["--follow"],
+    );
+    let limit = args.value::<i32>("--limit").unwrap_or(20).clamp(1, 500);
+    let interval = StdDuration::from_secs(args.value::<u64>("--interval").unwrap_or(2).max(1));
+    let mut filter = AccessLogFilter {
+        status_code: args.value("--status"),
+        ..Default::default()
+    };
+
+    let repo = connect().await?;
+    let id = find_link(&repo, args.one("tail", "id")).await?.id.0;
+    let started_at = Utc::now();
+
+    // Filtered pages can come back short, so keep going until `limit`.
+    let mut rows = Vec::new();
+    let mut paging_state = None;
+    loop {
+        let remaining = limit - rows.len() as i32;
+        let (page, next) = repo
+            .list_access_logs_page(&id, &filter, remaining, paging_state)
+            .await?;
+        rows.extend(page);
+        match next {
+            Some(next) if (rows.len() as i32) < limit => paging_state = Some(next),
+            _ => break,
+        }
+    }
+    let mut items = rows
+        .into_iter()
+        .rev()
+        .map(AdminAccessLogItem::from)
+        .collect::<Vec<_>>();
+    print_access_logs(&items, format)?;
+    if !args.flags.contains("--follow") {
+        return Ok(0);
+    }
+
+    // Timestamps are stored with millisecond precision.
+    let after = |item: &AdminAccessLogItem| item.ts + Duration::milliseconds(1);
+    filter.from = Some(items.last().map_or(started_at, after));
+    loop {
+        tokio::time::sleep(interval).await;
+
+        items.clear();
+        let mut paging_state = None;
+        loop {
+            let (rows, next) = repo
+                .list_access_logs_page(&id, &filter, 500, paging_state)
+                .await?;
+            items.extend(rows.into_iter().map(AdminAccessLogItem::from));
+            match next {
+                Some(next) => paging_state = Some(next),
+                None => break,
+            }
+        }
+        items.reverse();
+        print_access_logs(&items, format)?;
+        if let Some(item) = items.last() {
+            filter.from = Some(after(item));
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
+
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    let mut format = OutputFormat::Table;
+    while let Some(position) = args.iter().position(|arg| arg == "--output") {
+        format = match args.get(position + 1).map(String::as_str) {
+            Some("table") => OutputFormat::Table,
+            Some("json") => OutputFormat::Json,
+            Some(other) => usage_error(&format!("Unknown output format: {}", other)),
+            None => usage_error("Missing value for --output"),
+        };
+        args.drain(position..position + 2);
+    }
+    let Some((command, rest)) = args.split_first() else {
+        usage_error("Missing command");
+    };
+
+    let id_config = match id::config::Config::init_from_env() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to load configuration: {}", err);
+            std::process::exit(1);
+        }
+    };
+    if let Err(err) = IdGenerator::from_config(&id_config).and_then(generator::install) {
+        eprintln!("Failed to configure ID generation: {:?}", err);
+        std::process::exit(1);
+    }
+
+    let result = match command.as_str() {
+        "check" => check(rest).await,
+        "create" => create(rest, format).await,
+        "get" => get(rest, format).await,
+        "list" => list(rest, format).await,
+        "disable" => set_enabled("disable", rest, false, format).await,
+        "restore" => set_enabled("restore", rest, true, format).await,
+        "tail" => tail(rest, format).await,
+        "-h" | "--help" | "help" => {
+            println!("{}", USAGE);
+            Ok(0)
+        }
+        other => usage_error(&format!("Unknown command: {}", other)),
+    };
+
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,17 +1,40 @@
 pub mod logger;
 
-use crate::{config::logger::LoggerConfig, handler, scylla};
+use crate::{
+    access_log, auth, config::logger::LoggerConfig, domain::id, handler, rate_limit, scylla, sqlite,
+};
 use envconfig::Envconfig;
+use strum::EnumString;
 use valuable::Valuable;
 
+#[derive(EnumString, Debug, Valuable, Clone, Copy, PartialEq, Eq)]
+#[strum(ascii_case_insensitive)]
+pub enum StorageBackend {
+    Scylla,
+    Sqlite,
+    Memory,
+}
+
 #[derive(Envconfig, Debug, Valuable)]
 pub struct Config {
+    #[envconfig(from = "STORAGE_BACKEND", default = "scylla")]
+    pub storage_backend: StorageBackend,
     #[envconfig(nested)]
     pub handler: handler::config::Config,
     #[envconfig(nested)]
+    pub id: id::config::Config,
+    #[envconfig(nested)]
     pub scylla: scylla::config::Config,
     #[envconfig(nested)]
+    pub sqlite: sqlite::config::Config,
+    #[envconfig(nested)]
     pub logger: LoggerConfig,
+    #[envconfig(nested)]
+    pub auth: auth::config::Config,
+    #[envconfig(nested)]
+    pub rate_limit: rate_limit::config::Config,
+    #[envconfig(nested)]
+    pub access_log: access_log::config::Config,
 }
 
 pub fn load() -> Result<Config, envconfig::Error> {
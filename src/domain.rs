@@ -1,3 +1,4 @@
+pub mod error;
 pub mod id;
 pub mod models;
 pub mod repository;
@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+/// Failures callers are expected to handle. Anything else a repository
+/// returns is a storage error.
+#[derive(Debug, Error)]
+pub enum RepositoryError {
+    #[error("ID '{0}' is already taken")]
+    IdTaken(String),
+    #[error("ID '{0}' belonged to a deleted link and cannot be reused")]
+    IdDeleted(String),
+}
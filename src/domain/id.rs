@@ -1,8 +1,9 @@
-use std::sync::OnceLock;
+pub mod config;
+pub mod generator;
+pub mod validation;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use sqids::Sqids;
 
 const SIMILAR_CHARS: &[(&str, char)] = &[
     ("C", 'c'),
@@ -70,27 +71,21 @@ fn alphabets() -> Vec<char> {
     chars
 }
 
-static SQIDS: OnceLock<Sqids> = OnceLock::new();
-
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub struct ID(pub String);
 
 impl ID {
     pub fn new(s: String) -> Self {
-        return Self(normalize(&s));
+        Self(normalize(&s))
     }
 
     pub fn generate(seq: i64) -> Result<Self> {
-        let sqids = SQIDS.get_or_init(|| {
-            Sqids::builder()
-                .min_length(5)
-                .alphabet(alphabets())
-                .build()
-                .expect("Failed to build SQIDS")
-        });
-
-        let id_str = sqids.encode(&[seq as u64])?;
-        return Ok(Self(id_str));
+        generator::generator().from_sequence(seq)
+    }
+
+    /// Returns the sequence value `generate` turns into exactly this ID, if any.
+    pub fn generated_seq(&self) -> Option<i64> {
+        generator::generator().decode(self)
     }
 }
 
@@ -107,4 +102,11 @@ mod tests {
         assert_eq!(id1, id2);
         assert_ne!(id1, id3);
     }
+
+    #[test]
+    fn test_generated_seq() {
+        assert_eq!(ID::generate(42).unwrap().generated_seq(), Some(42));
+        assert_eq!(ID("docs".to_string()).generated_seq(), None);
+        assert_eq!(ID("ab".to_string()).generated_seq(), None);
+    }
 }
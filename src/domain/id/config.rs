@@ -0,0 +1,30 @@
+use envconfig::Envconfig;
+use strum::EnumString;
+use valuable::Valuable;
+
+#[derive(EnumString, Debug, Valuable, Clone, Copy, PartialEq, Eq)]
+#[strum(ascii_case_insensitive)]
+pub enum GenerationMode {
+    /// Sqids of the shared sequence over the default alphabet. IDs are
+    /// short but can be enumerated.
+    Sequential,
+    /// Sqids of the shared sequence over an alphabet shuffled with
+    /// `ID_ALPHABET_SECRET`. Hides the order, but a determined observer can
+    /// still learn it from enough samples.
+    Shuffled,
+    /// Random IDs of `ID_RANDOM_LENGTH` characters, retried on collision.
+    Random,
+}
+
+#[derive(Envconfig, Debug, Valuable, Clone)]
+pub struct Config {
+    #[envconfig(from = "ID_GENERATION_MODE", default = "sequential")]
+    pub mode: GenerationMode,
+
+    #[valuable(skip)]
+    #[envconfig(from = "ID_ALPHABET_SECRET")]
+    pub alphabet_secret: Option<String>,
+
+    #[envconfig(from = "ID_RANDOM_LENGTH", default = "8")]
+    pub random_length: usize,
+}
@@ -0,0 +1,193 @@
+use std::sync::OnceLock;
+
+use anyhow::{Result, anyhow, bail};
+use rand::{Rng, SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
+use sqids::Sqids;
+
+use crate::domain::id::{
+    ID, alphabets,
+    config::{Config, GenerationMode},
+};
+
+const SQIDS_MIN_LENGTH: u8 = 5;
+/// Shorter random IDs make collisions, and guessing, too likely.
+const MIN_RANDOM_LENGTH: usize = 6;
+
+/// How new IDs are produced. Lookups never depend on it, so switching modes
+/// keeps every existing ID resolvable.
+pub enum IdGenerator {
+    Sqids(Sqids),
+    Random { alphabet: Vec<char>, length: usize },
+}
+
+static GENERATOR: OnceLock<IdGenerator> = OnceLock::new();
+
+/// Returns the installed generator, or the sequential one if none was installed.
+pub fn generator() -> &'static IdGenerator {
+    GENERATOR.get_or_init(IdGenerator::sequential)
+}
+
+/// Sets the process-wide generator. Has to run before the first ID is generated.
+pub fn install(generator: IdGenerator) -> Result<()> {
+    GENERATOR
+        .set(generator)
+        .map_err(|_| anyhow!("ID generator is already initialized"))
+}
+
+fn build_sqids(alphabet: Vec<char>) -> Result<Sqids> {
+    Ok(Sqids::builder()
+        .min_length(SQIDS_MIN_LENGTH)
+        .alphabet(alphabet)
+        .build()?)
+}
+
+/// Shuffles the alphabet with a stream keyed by the secret, so the same
+/// secret always yields the same alphabet.
+fn shuffled_alphabet(secret: &str) -> Vec<char> {
+    let seed: [u8; 32] = Sha256::new()
+        .chain_update(b"walnuk-id-alphabet:")
+        .chain_update(secret.as_bytes())
+        .finalize()
+        .into();
+    let mut alphabet = alphabets();
+    alphabet.shuffle(&mut ChaCha20Rng::from_seed(seed));
+    alphabet
+}
+
+impl IdGenerator {
+    pub fn sequential() -> Self {
+        IdGenerator::Sqids(build_sqids(alphabets()).expect("Failed to build SQIDS"))
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self> {
+        match config.mode {
+            GenerationMode::Sequential => Ok(Self::sequential()),
+            GenerationMode::Shuffled => {
+                let secret = config
+                    .alphabet_secret
+                    .as_deref()
+                    .filter(|s| !s.is_empty())
+                    .ok_or_else(|| {
+                        anyhow!("ID_GENERATION_MODE=shuffled requires ID_ALPHABET_SECRET")
+                    })?;
+                Ok(IdGenerator::Sqids(build_sqids(shuffled_alphabet(secret))?))
+            }
+            GenerationMode::Random => {
+                if config.random_length < MIN_RANDOM_LENGTH {
+                    bail!("ID_RANDOM_LENGTH must be at least {MIN_RANDOM_LENGTH}");
+                }
+                Ok(IdGenerator::Random {
+                    alphabet: alphabets(),
+                    length: config.random_length,
+                })
+            }
+        }
+    }
+
+    /// Whether IDs are derived from the shared sequence. Otherwise callers
+    /// use `random` and retry when the ID is taken.
+    pub fn uses_sequence(&self) -> bool {
+        matches!(self, IdGenerator::Sqids(_))
+    }
+
+    pub fn from_sequence(&self, seq: i64) -> Result<ID> {
+        match self {
+            IdGenerator::Sqids(sqids) => Ok(ID(sqids.encode(&[seq as u64])?)),
+            IdGenerator::Random { .. } => bail!("Random IDs are not derived from a sequence"),
+        }
+    }
+
+    pub fn random(&self) -> Result<ID> {
+        match self {
+            IdGenerator::Random { alphabet, length } => {
+                let mut rng = rand::rng();
+                Ok(ID((0..*length)
+                    .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+                    .collect()))
+            }
+            IdGenerator::Sqids(_) => bail!("Sequential IDs are derived from the sequence"),
+        }
+    }
+
+    /// Returns the sequence value this generator turns into exactly `id`, if any.
+    pub fn decode(&self, id: &ID) -> Option<i64> {
+        let IdGenerator::Sqids(sqids) = self else {
+            return None;
+        };
+        match sqids.decode(&id.0).as_slice() {
+            [seq] if sqids.encode(&[*seq]).ok()? == id.0 => i64::try_from(*seq).ok(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use envconfig::Envconfig;
+
+    use super::*;
+
+    fn config(vars: &[(&str, &str)]) -> Config {
+        Config::init_from_hashmap(
+            &vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_shuffled_alphabet_depends_on_secret() {
+        let shuffled = |secret| {
+            IdGenerator::from_config(&config(&[
+                ("ID_GENERATION_MODE", "shuffled"),
+                ("ID_ALPHABET_SECRET", secret),
+            ]))
+            .unwrap()
+        };
+        let a = shuffled("secret-a");
+        let b = shuffled("secret-b");
+        let sequential = IdGenerator::sequential();
+
+        let id = a.from_sequence(1).unwrap();
+        assert_eq!(id, shuffled("secret-a").from_sequence(1).unwrap());
+        assert_ne!(id, b.from_sequence(1).unwrap());
+        assert_ne!(id, sequential.from_sequence(1).unwrap());
+        assert_eq!(a.decode(&id), Some(1));
+        // Generated IDs survive the confusable normalization applied on lookup.
+        assert_eq!(ID::new(id.0.clone()), id);
+
+        assert!(IdGenerator::from_config(&config(&[("ID_GENERATION_MODE", "shuffled")])).is_err());
+    }
+
+    #[test]
+    fn test_random_ids() {
+        let generator = IdGenerator::from_config(&config(&[
+            ("ID_GENERATION_MODE", "random"),
+            ("ID_RANDOM_LENGTH", "10"),
+        ]))
+        .unwrap();
+
+        assert!(!generator.uses_sequence());
+        let id = generator.random().unwrap();
+        assert_eq!(id.0.len(), 10);
+        assert_eq!(ID::new(id.0.clone()), id);
+        assert_ne!(id, generator.random().unwrap());
+        assert_eq!(generator.decode(&id), None);
+        assert!(generator.from_sequence(1).is_err());
+        assert!(IdGenerator::sequential().random().is_err());
+
+        assert!(
+            IdGenerator::from_config(&config(&[
+                ("ID_GENERATION_MODE", "random"),
+                ("ID_RANDOM_LENGTH", "4"),
+            ]))
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashSet;
+
+use strum::EnumString;
+use thiserror::Error;
+use valuable::Valuable;
+
+use crate::domain::id::{ID, normalize};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum IdValidationError {
+    #[error("Custom ID must be at least {0} characters long.")]
+    TooShort(usize),
+    #[error("Custom ID must be at most {0} characters long.")]
+    TooLong(usize),
+    #[error("Custom ID contains a character that is not allowed: '{0}'.")]
+    InvalidCharacter(char),
+    #[error("Custom ID '{0}' is reserved.")]
+    Reserved(String),
+    #[error("Custom ID contains a blocked word.")]
+    Blocked,
+    #[error("Custom ID '{0}' has the same format as generated IDs.")]
+    LooksGenerated(String),
+}
+
+/// How to treat custom IDs that `ID::generate` could also produce.
+#[derive(EnumString, Debug, Valuable, Clone, Copy, PartialEq, Eq)]
+#[strum(ascii_case_insensitive)]
+pub enum GeneratedIdPolicy {
+    /// Refuse them.
+    Reject,
+    /// Accept them; the generator skips sequence values whose ID is taken.
+    Reserve,
+}
+
+/// Rules a user-chosen ID has to satisfy before it is stored.
+///
+/// Reserved and blocked words are compared after lowercasing and confusable
+/// normalization, so `He1p` is caught by `help` just like `ID::new` would
+/// resolve it.
+#[derive(Debug, Clone)]
+pub struct IdPolicy {
+    min_length: usize,
+    max_length: usize,
+    allowed_chars: HashSet<char>,
+    reserved: HashSet<String>,
+    blocklist: Vec<String>,
+    generated_ids: GeneratedIdPolicy,
+}
+
+fn canonical(word: &str) -> String {
+    normalize(&word.to_ascii_lowercase())
+}
+
+fn canonical_words<'a>(words: impl IntoIterator<Item = &'a str>) -> impl Iterator<Item = String> {
+    words
+        .into_iter()
+        .map(str::trim)
+        .filter(|w| !w.is_empty())
+        .map(canonical)
+}
+
+impl IdPolicy {
+    pub fn new<'a>(
+        min_length: usize,
+        max_length: usize,
+        allowed_chars: &str,
+        reserved: impl IntoIterator<Item = &'a str>,
+        blocklist: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        Self {
+            min_length,
+            max_length,
+            allowed_chars: allowed_chars.chars().collect(),
+            reserved: canonical_words(reserved).collect(),
+            blocklist: canonical_words(blocklist).collect(),
+            generated_ids: GeneratedIdPolicy::Reject,
+        }
+    }
+
+    pub fn with_generated_id_policy(mut self, policy: GeneratedIdPolicy) -> Self {
+        self.generated_ids = policy;
+        self
+    }
+
+    pub fn validate(&self, custom_id: &str) -> Result<ID, IdValidationError> {
+        let length = custom_id.chars().count();
+        if length < self.min_length {
+            return Err(IdValidationError::TooShort(self.min_length));
+        }
+        if length > self.max_length {
+            return Err(IdValidationError::TooLong(self.max_length));
+        }
+
+        // Checked before the charset so that e.g. `favicon.ico` reports why.
+        let canonical = canonical(custom_id);
+        if self.reserved.contains(&canonical) {
+            return Err(IdValidationError::Reserved(custom_id.to_string()));
+        }
+        if let Some(c) = custom_id.chars().find(|c| !self.allowed_chars.contains(c)) {
+            return Err(IdValidationError::InvalidCharacter(c));
+        }
+        if self.blocklist.iter().any(|word| canonical.contains(word)) {
+            return Err(IdValidationError::Blocked);
+        }
+
+        let id = ID::new(custom_id.to_string());
+        if self.generated_ids == GeneratedIdPolicy::Reject && id.generated_seq().is_some() {
+            return Err(IdValidationError::LooksGenerated(custom_id.to_string()));
+        }
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> IdPolicy {
+        IdPolicy::new(
+            3,
+            12,
+            "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-_",
+            ["api", "health", "favicon.ico"],
+            ["darn"],
+        )
+    }
+
+    #[test]
+    fn test_validate() {
+        let policy = policy();
+
+        assert_eq!(policy.validate("docs").unwrap().0, "docs");
+        assert_eq!(policy.validate("ab"), Err(IdValidationError::TooShort(3)));
+        assert_eq!(
+            policy.validate("abcdefghijklm"),
+            Err(IdValidationError::TooLong(12))
+        );
+        assert_eq!(
+            policy.validate("a/b"),
+            Err(IdValidationError::InvalidCharacter('/'))
+        );
+        assert_eq!(
+            policy.validate("sale!"),
+            Err(IdValidationError::InvalidCharacter('!'))
+        );
+    }
+
+    #[test]
+    fn test_reserved_and_blocked_words_ignore_case_and_confusables() {
+        let policy = policy();
+
+        assert_eq!(
+            policy.validate("API"),
+            Err(IdValidationError::Reserved("API".to_string()))
+        );
+        // `1` and `l` normalize to the same character.
+        assert_eq!(
+            policy.validate("hea1th"),
+            Err(IdValidationError::Reserved("hea1th".to_string()))
+        );
+        assert_eq!(
+            policy.validate("favicon.ico"),
+            Err(IdValidationError::Reserved("favicon.ico".to_string()))
+        );
+        assert_eq!(policy.validate("xxDARNxx"), Err(IdValidationError::Blocked));
+        assert!(policy.validate("apis").is_ok());
+    }
+
+    #[test]
+    fn test_generated_id_policy() {
+        let generated = ID::generate(7).unwrap().0;
+
+        assert_eq!(
+            policy().validate(&generated),
+            Err(IdValidationError::LooksGenerated(generated.clone()))
+        );
+        let reserve = policy().with_generated_id_policy(GeneratedIdPolicy::Reserve);
+        assert_eq!(reserve.validate(&generated).unwrap().0, generated);
+    }
+}
@@ -1,9 +1,10 @@
-use crate::domain::id::ID;
+use crate::domain::{error::RepositoryError, id::ID};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
 use url::Url;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShortenedURL {
     pub id: ID, // pathになる
     pub original_url: Url,
@@ -11,7 +12,39 @@ pub struct ShortenedURL {
     pub expires_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl ShortenedURL {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// What `ShortenedURLRepository::create` did.
+#[derive(Debug, Clone)]
+pub enum CreateOutcome {
+    Created(ShortenedURL),
+    /// The ID already pointed at the requested destination; nothing was written.
+    AlreadyExists(ShortenedURL),
+}
+
+impl CreateOutcome {
+    /// Decides the outcome of an insert that found `existing` under the same ID.
+    /// Only a request for the same destination is treated as idempotent.
+    pub fn from_existing(existing: ShortenedURL, requested: &Url) -> Result<Self, RepositoryError> {
+        if existing.original_url == *requested {
+            Ok(CreateOutcome::AlreadyExists(existing))
+        } else {
+            Err(RepositoryError::IdTaken(existing.id.0))
+        }
+    }
+
+    pub fn into_url(self) -> ShortenedURL {
+        match self {
+            CreateOutcome::Created(url) | CreateOutcome::AlreadyExists(url) => url,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShortUrlState {
     pub id: ID,
     pub enabled: bool,
@@ -19,11 +52,113 @@ pub struct ShortUrlState {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// One edit of a link's destination.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DestinationChange {
+    pub changed_at: DateTime<Utc>,
+    pub previous_url: Url,
+    pub new_url: Url,
+    /// The admin subject that made the change.
+    pub changed_by: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Create,
+    UpdateDestination,
+    Disable,
+    Restore,
+    Delete,
+}
+
+/// One admin mutation of a link, with snapshots of what it changed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Random, to tell apart entries recorded at the same instant.
+    pub id: String,
+    pub ts: DateTime<Utc>,
+    pub actor: String,
+    pub action: AuditAction,
+    pub link_id: String,
+    pub reason: Option<String>,
+    pub request_id: Option<String>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+impl AuditEntry {
+    pub fn new(actor: &str, action: AuditAction, link_id: &str, ts: DateTime<Utc>) -> Self {
+        Self {
+            id: format!("{:016x}", rand::random::<u64>()),
+            ts,
+            actor: actor.to_string(),
+            action,
+            link_id: link_id.to_string(),
+            reason: None,
+            request_id: None,
+            before: None,
+            after: None,
+        }
+    }
+}
+
+/// Narrows an audit log listing to one link and/or one actor.
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub link_id: Option<String>,
+    pub actor: Option<String>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.link_id.as_ref().is_none_or(|id| *id == entry.link_id)
+            && self
+                .actor
+                .as_ref()
+                .is_none_or(|actor| *actor == entry.actor)
+    }
+}
+
+/// One redirect attempt, recorded in the access log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessEvent {
+    pub id: String,
+    pub ts: DateTime<Utc>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub status_code: i32,
+    /// False for unknown IDs, which get an access log entry but no last-access row.
+    pub update_last_access: bool,
+}
+
+/// Narrows an access log listing. `from` is inclusive and `to` exclusive.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AccessLogFilter {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub status_code: Option<i32>,
+}
+
+impl AccessLogFilter {
+    pub fn matches(&self, ts: DateTime<Utc>, status_code: i32) -> bool {
+        self.from.is_none_or(|from| ts >= from)
+            && self.to.is_none_or(|to| ts < to)
+            && self.status_code.is_none_or(|sc| sc == status_code)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShortUrlAdminView {
     pub id: ID,
     pub original_url: Option<Url>,
     pub created_at: Option<DateTime<Utc>>,
     pub expires_at: Option<DateTime<Utc>>,
+    pub expired: bool,
     pub state: Option<ShortUrlState>,
+    /// True when the creator metadata is gone because it outlived its
+    /// retention period.
+    pub creator_metadata_expired: bool,
 }
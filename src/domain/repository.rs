@@ -1,24 +1,76 @@
 use crate::domain::{
     id::ID,
-    models::{ShortUrlState, ShortenedURL},
+    models::{
+        AccessEvent, AccessLogFilter, AuditEntry, AuditFilter, CreateOutcome, DestinationChange,
+        ShortUrlState, ShortenedURL,
+    },
 };
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use url::Url;
 
+/// (created_at, ip, user_agent, request_id)
+pub type CreateMeta = (DateTime<Utc>, String, String, String);
+
+/// (ts, ip, user_agent, request_id, status_code)
+pub type AccessLogRow = (DateTime<Utc>, String, String, String, i32);
+
+/// The `*_page` methods take the opaque paging state of the previous page
+/// and return the next one, or `None` after the last page. A filtered page
+/// may hold fewer than `limit` entries, even none, while there are more to
+/// come, and its paging state is only valid with the same filter.
 pub trait ShortenedURLRepository {
+    /// Cheap round trip to the backing store, used by the readiness probe.
+    fn ping(&self) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    /// Fails with `RepositoryError::IdTaken` when the ID already points
+    /// somewhere else, and with `RepositoryError::IdDeleted` when it belonged
+    /// to a deleted link.
     fn create(
         &self,
         original_url: Url,
         custom_id: Option<&str>,
         expires_at: Option<DateTime<Utc>>,
-    ) -> impl std::future::Future<Output = Result<ShortenedURL>> + Send;
+    ) -> impl std::future::Future<Output = Result<CreateOutcome>> + Send;
 
     fn find_by_id(
         &self,
         id: ID,
     ) -> impl std::future::Future<Output = Result<Option<ShortenedURL>>> + Send;
 
+    /// Points the link at `new_url` and records the previous destination.
+    /// Returns `None` when the link does not exist, and the link unchanged
+    /// when it already points at `new_url`. `id` is normalized as by `ID::new`.
+    fn update_destination(
+        &self,
+        id: &str,
+        new_url: Url,
+        changed_by: &str,
+        now: DateTime<Utc>,
+    ) -> impl std::future::Future<Output = Result<Option<ShortenedURL>>> + Send;
+
+    /// Removes the link and every row kept for it, leaving a tombstone so the
+    /// ID is never handed out again. Returns false when there is neither a
+    /// link nor a tombstone; deleting again purges whatever a failed attempt
+    /// left behind. The link's audit entries are kept without their
+    /// before/after snapshots. `id` is normalized as by `ID::new`.
+    fn delete(
+        &self,
+        id: &str,
+        deleted_by: &str,
+        now: DateTime<Utc>,
+    ) -> impl std::future::Future<Output = Result<bool>> + Send;
+
+    /// Whether the ID has a tombstone, i.e. the link was deleted. `id` is
+    /// normalized as by `ID::new`.
+    fn is_deleted(&self, id: &str) -> impl std::future::Future<Output = Result<bool>> + Send;
+
+    /// Newest first.
+    fn list_destination_history(
+        &self,
+        id: &str,
+    ) -> impl std::future::Future<Output = Result<Vec<DestinationChange>>> + Send;
+
     fn list_by_created_at_page(
         &self,
         limit: i32,
@@ -37,7 +89,19 @@ pub trait ShortenedURLRepository {
     fn get_create_meta(
         &self,
         id: &str,
-    ) -> impl std::future::Future<Output = Result<Option<(DateTime<Utc>, String, String, String)>>> + Send;
+    ) -> impl std::future::Future<Output = Result<Option<CreateMeta>>> + Send;
+
+    /// How long creator metadata is kept, or `None` if it never expires.
+    fn create_meta_retention(&self) -> Option<chrono::Duration> {
+        None
+    }
+
+    /// Whether creator metadata for a link created at `created_at` is gone
+    /// because it outlived its retention, explaining why it is missing.
+    fn create_meta_aged_out(&self, created_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        self.create_meta_retention()
+            .is_some_and(|retention| created_at + retention <= now)
+    }
 
     fn get_state(
         &self,
@@ -71,12 +135,33 @@ pub trait ShortenedURLRepository {
         status_code: i32,
     ) -> impl std::future::Future<Output = Result<()>> + Send;
 
-    fn list_access_logs_recent(
+    /// Writes the access log entries and last-access rows for a batch of events.
+    fn log_access_batch(
+        &self,
+        events: &[AccessEvent],
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    /// Accesses of the link matching `filter`, newest first.
+    fn list_access_logs_page(
         &self,
         id: &str,
+        filter: &AccessLogFilter,
+        limit: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> impl std::future::Future<Output = Result<(Vec<AccessLogRow>, Option<Vec<u8>>)>> + Send;
+
+    fn record_audit(
+        &self,
+        entry: &AuditEntry,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    /// Admin actions matching `filter`, newest first.
+    fn list_audit_page(
+        &self,
+        filter: &AuditFilter,
         limit: i32,
-    ) -> impl std::future::Future<Output = Result<Vec<(DateTime<Utc>, String, String, String, i32)>>>
-    + Send;
+        paging_state: Option<Vec<u8>>,
+    ) -> impl std::future::Future<Output = Result<(Vec<AuditEntry>, Option<Vec<u8>>)>> + Send;
 
     fn get_last_access(
         &self,
@@ -1,2 +1,4 @@
+pub mod cache;
 pub mod config;
 pub mod handlers;
+pub mod routes;
@@ -0,0 +1,107 @@
+use std::time::{Duration, Instant};
+
+use moka::{Expiry, sync::Cache};
+
+use crate::{domain::models::ShortenedURL, metrics::registry::METRICS};
+
+/// What `Handler::redirect` needs to answer a request without touching storage.
+#[derive(Debug, Clone)]
+pub struct ResolvedLink {
+    pub url: ShortenedURL,
+    pub enabled: bool,
+}
+
+/// A cached lookup. `None` records that the ID did not exist.
+type Entry = Option<ResolvedLink>;
+
+struct EntryExpiry {
+    ttl: Duration,
+    negative_ttl: Duration,
+}
+
+impl Expiry<String, Entry> for EntryExpiry {
+    fn expire_after_create(&self, _key: &String, value: &Entry, _now: Instant) -> Option<Duration> {
+        Some(match value {
+            Some(_) => self.ttl,
+            None => self.negative_ttl,
+        })
+    }
+}
+
+/// Bounded LRU/TTL cache of resolved links, keyed by normalized ID.
+///
+/// Invalidation is local to this process; other instances pick up changes
+/// once their entries expire, so the TTL bounds how stale a redirect can be.
+pub struct RedirectCache {
+    entries: Cache<String, Entry>,
+}
+
+impl RedirectCache {
+    pub fn new(capacity: u64, ttl: Duration, negative_ttl: Duration) -> Self {
+        Self {
+            entries: Cache::builder()
+                .max_capacity(capacity)
+                .expire_after(EntryExpiry { ttl, negative_ttl })
+                .build(),
+        }
+    }
+
+    /// Returns `Some(entry)` on a hit, where the entry itself is `None` for a
+    /// cached miss.
+    pub fn get(&self, id: &str) -> Option<Entry> {
+        let entry = self.entries.get(id);
+        let result = match &entry {
+            Some(Some(_)) => "hit",
+            Some(None) => "negative_hit",
+            None => "miss",
+        };
+        METRICS
+            .redirect_cache_lookups
+            .with_label_values(&[result])
+            .inc();
+        entry
+    }
+
+    pub fn insert(&self, id: &str, entry: Entry) {
+        self.entries.insert(id.to_string(), entry);
+    }
+
+    pub fn invalidate(&self, id: &str) {
+        self.entries.invalidate(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use url::Url;
+
+    use super::*;
+    use crate::domain::id::ID;
+
+    #[test]
+    fn test_negative_entries_expire_separately() {
+        let cache = RedirectCache::new(10, Duration::from_secs(60), Duration::from_millis(20));
+        let link = ResolvedLink {
+            url: ShortenedURL {
+                id: ID("abcde".to_string()),
+                original_url: Url::parse("https://example.com/").unwrap(),
+                created_at: Utc::now(),
+                expires_at: None,
+            },
+            enabled: true,
+        };
+
+        cache.insert("abcde", Some(link));
+        cache.insert("missing", None);
+        assert!(matches!(cache.get("abcde"), Some(Some(_))));
+        assert!(matches!(cache.get("missing"), Some(None)));
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(matches!(cache.get("abcde"), Some(Some(_))));
+        assert!(cache.get("missing").is_none());
+
+        cache.invalidate("abcde");
+        assert!(cache.get("abcde").is_none());
+    }
+}
@@ -1,10 +1,62 @@
 use envconfig::Envconfig;
 use valuable::Valuable;
 
+use crate::domain::id::validation::GeneratedIdPolicy;
+
 #[derive(Envconfig, Debug, Valuable, Clone)]
 pub struct Config {
     #[envconfig(from = "BASE_URL", default = "http://localhost:8080")]
     pub base_url: String,
     #[envconfig(from = "PORT", default = "8080")]
     pub port: u16,
+
+    #[envconfig(from = "READINESS_TIMEOUT_MS", default = "1000")]
+    pub readiness_timeout_ms: u64,
+    /// How long to keep serving after readiness flips to not-ready on
+    /// SIGTERM, so that load balancers stop routing to this instance first.
+    #[envconfig(from = "SHUTDOWN_DELAY_SECONDS", default = "5")]
+    pub shutdown_delay_seconds: u64,
+
+    /// Maximum number of links kept in the redirect cache. 0 disables it.
+    #[envconfig(from = "REDIRECT_CACHE_CAPACITY", default = "10000")]
+    pub redirect_cache_capacity: u64,
+    /// How long other instances may keep redirecting a link after it is
+    /// disabled, edited or deleted.
+    #[envconfig(from = "REDIRECT_CACHE_TTL_SECONDS", default = "60")]
+    pub redirect_cache_ttl_seconds: u64,
+    /// How long an unknown ID is remembered as missing.
+    #[envconfig(from = "REDIRECT_CACHE_NEGATIVE_TTL_SECONDS", default = "5")]
+    pub redirect_cache_negative_ttl_seconds: u64,
+
+    #[envconfig(from = "CUSTOM_ID_MIN_LENGTH", default = "3")]
+    pub custom_id_min_length: usize,
+    #[envconfig(from = "CUSTOM_ID_MAX_LENGTH", default = "64")]
+    pub custom_id_max_length: usize,
+    #[envconfig(
+        from = "CUSTOM_ID_ALLOWED_CHARS",
+        default = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-_"
+    )]
+    pub custom_id_allowed_chars: String,
+    /// Comma-separated. Top-level route segments are always reserved on top of these.
+    #[envconfig(
+        from = "CUSTOM_ID_RESERVED_WORDS",
+        default = "admin,favicon.ico,robots.txt,sitemap.xml,login,logout,static,assets,_next"
+    )]
+    pub custom_id_reserved_words: String,
+    /// Comma-separated words that may not appear anywhere in a custom ID.
+    #[envconfig(
+        from = "CUSTOM_ID_BLOCKLIST",
+        default = "fuck,shit,cunt,bitch,whore,slut,porn,nazi"
+    )]
+    pub custom_id_blocklist: String,
+
+    /// `reject` refuses custom IDs that look like generated ones, `reserve`
+    /// accepts them and lets the generator skip over them.
+    #[envconfig(from = "CUSTOM_ID_GENERATED_POLICY", default = "reject")]
+    pub custom_id_generated_policy: GeneratedIdPolicy,
+
+    #[envconfig(from = "EXPIRES_IN_MIN_SECONDS", default = "60")]
+    pub expires_in_min_seconds: i64,
+    #[envconfig(from = "EXPIRES_IN_MAX_SECONDS", default = "31536000")]
+    pub expires_in_max_seconds: i64,
 }
@@ -1,16 +1,40 @@
 use actix_web::{
     HttpRequest, HttpResponse, Responder, ResponseError,
+    http::header,
     web::{self, Redirect},
 };
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration as StdDuration,
+};
 use thiserror::Error;
 use url::Url;
 
-use crate::domain::{
-    id::ID,
-    models::{ShortUrlAdminView, ShortUrlState},
-    repository::ShortenedURLRepository,
+use crate::{
+    access_log::logger::AccessLogger,
+    auth::authenticator::Principal,
+    domain::{
+        error::RepositoryError,
+        id::{ID, validation::IdPolicy},
+        models::{
+            AccessEvent, AccessLogFilter, AuditAction, AuditEntry, AuditFilter, CreateOutcome,
+            DestinationChange, ShortUrlAdminView, ShortUrlState, ShortenedURL,
+        },
+        repository::{AccessLogRow, ShortenedURLRepository},
+    },
+    handler::{
+        cache::{RedirectCache, ResolvedLink},
+        config::Config,
+        routes,
+    },
+    metrics,
+    rate_limit::limiter::{Decision, RateLimiter, Scope},
 };
 
 #[derive(Debug, Error)]
@@ -24,6 +48,28 @@ pub enum HandlerError {
 
     #[error("URL disabled")]
     Disabled,
+
+    #[error("URL expired")]
+    Expired,
+
+    #[error("Too many requests")]
+    RateLimited(std::time::Duration),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+}
+
+impl HandlerError {
+    /// Maps the errors a repository reports on purpose to client errors;
+    /// everything else is a storage failure.
+    fn from_repository(e: anyhow::Error) -> Self {
+        match e.downcast_ref::<RepositoryError>() {
+            Some(e @ (RepositoryError::IdTaken(_) | RepositoryError::IdDeleted(_))) => {
+                HandlerError::Conflict(e.to_string())
+            }
+            None => HandlerError::DBError(e),
+        }
+    }
 }
 
 impl ResponseError for HandlerError {
@@ -36,18 +82,267 @@ impl ResponseError for HandlerError {
             }
             HandlerError::NotFound => HttpResponse::NotFound().body("URL not found"),
             HandlerError::Disabled => HttpResponse::Gone().body("URL disabled"),
+            HandlerError::Expired => HttpResponse::Gone().body("URL expired"),
+            HandlerError::RateLimited(retry_after) => HttpResponse::TooManyRequests()
+                .insert_header((
+                    header::RETRY_AFTER,
+                    retry_after.as_secs_f64().ceil().max(1.0).to_string(),
+                ))
+                .body("Too many requests"),
+            HandlerError::Conflict(msg) => HttpResponse::Conflict().body(msg.clone()),
+        }
+    }
+}
+
+/// Decodes the opaque `page_state` query parameter of admin listings.
+fn decode_page_state(page_state: Option<&str>) -> Result<Option<Vec<u8>>, HandlerError> {
+    match page_state.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(token) => URL_SAFE_NO_PAD
+            .decode(token)
+            .map(Some)
+            .map_err(|_| HandlerError::ParamError("Invalid page_state".to_string())),
+    }
+}
+
+/// Most links a single bulk disable/restore may name.
+const MAX_BULK_IDS: usize = 100;
+
+/// Longest `reason` accepted on an admin mutation.
+const MAX_REASON_LENGTH: usize = 500;
+
+/// Validates the optional `reason` of an admin mutation. Blank means none.
+pub fn parse_reason(reason: Option<&str>) -> Result<Option<String>, HandlerError> {
+    let Some(reason) = reason.map(str::trim).filter(|r| !r.is_empty()) else {
+        return Ok(None);
+    };
+    if reason.chars().count() > MAX_REASON_LENGTH {
+        return Err(HandlerError::ParamError(format!(
+            "'reason' must be at most {} characters.",
+            MAX_REASON_LENGTH
+        )));
+    }
+    Ok(Some(reason.to_string()))
+}
+
+/// Parses the body of disable/restore, which may be left empty.
+fn parse_state_change(body: &[u8]) -> Result<AdminStateChangeParams, HandlerError> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(AdminStateChangeParams::default());
+    }
+    serde_json::from_slice(body)
+        .map_err(|e| HandlerError::ParamError(format!("Invalid request body: {}", e)))
+}
+
+/// The part of a link's state recorded in the audit log.
+pub fn state_snapshot(state: Option<&ShortUrlState>) -> serde_json::Value {
+    serde_json::json!({
+        "enabled": state.is_none_or(|s| s.enabled),
+        "disabled_at": state.and_then(|s| s.disabled_at),
+    })
+}
+
+/// The custom ID rules configured for the server.
+pub fn id_policy(config: &Config) -> IdPolicy {
+    IdPolicy::new(
+        config.custom_id_min_length,
+        config.custom_id_max_length,
+        &config.custom_id_allowed_chars,
+        routes::RESERVED_SEGMENTS
+            .iter()
+            .copied()
+            .chain(config.custom_id_reserved_words.split(',')),
+        config.custom_id_blocklist.split(','),
+    )
+    .with_generated_id_policy(config.custom_id_generated_policy)
+}
+
+/// Validates the `url` parameter of a link.
+pub fn parse_destination(url: &str) -> Result<Url, HandlerError> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Err(HandlerError::ParamError(
+            "The 'url' parameter is required.".to_string(),
+        ));
+    }
+
+    let url = Url::parse(url)
+        .map_err(|e| HandlerError::ParamError(format!("Invalid URL format: {}", e)))?;
+
+    match url.scheme() {
+        "http" | "https" => Ok(url),
+        _ => Err(HandlerError::ParamError(
+            "Only http and https URLs are supported.".to_string(),
+        )),
+    }
+}
+
+/// Resolves the requested expiration against the configured lifetime bounds.
+pub fn resolve_expires_at(
+    config: &Config,
+    expires_at: Option<DateTime<Utc>>,
+    expires_in: Option<i64>,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, HandlerError> {
+    let expires_at = match (expires_at, expires_in) {
+        (None, None) => return Ok(None),
+        (Some(_), Some(_)) => {
+            return Err(HandlerError::ParamError(
+                "Only one of 'expires_at' and 'expires_in' can be specified.".to_string(),
+            ));
         }
+        (Some(expires_at), None) => expires_at,
+        (None, Some(expires_in)) => Duration::try_seconds(expires_in)
+            .and_then(|d| now.checked_add_signed(d))
+            .ok_or_else(|| HandlerError::ParamError("'expires_in' is out of range.".to_string()))?,
+    };
+
+    let min = config.expires_in_min_seconds;
+    let max = config.expires_in_max_seconds;
+    let lifetime = (expires_at - now).num_seconds();
+    if lifetime < min || lifetime > max {
+        return Err(HandlerError::ParamError(format!(
+            "Expiration must be between {} and {} seconds from now.",
+            min, max
+        )));
     }
+
+    Ok(Some(expires_at))
 }
 
 #[derive(Clone)]
 pub struct Handler<T: ShortenedURLRepository> {
     url_repo: T,
+    config: Config,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    redirect_cache: Option<Arc<RedirectCache>>,
+    access_logger: Option<Arc<AccessLogger>>,
+    id_policy: Arc<IdPolicy>,
+    shutting_down: Arc<AtomicBool>,
 }
 
 impl<T: ShortenedURLRepository> Handler<T> {
-    pub fn new(url_repo: T) -> Self {
-        Handler { url_repo }
+    pub fn new(url_repo: T, config: Config) -> Self {
+        let redirect_cache = (config.redirect_cache_capacity > 0).then(|| {
+            Arc::new(RedirectCache::new(
+                config.redirect_cache_capacity,
+                StdDuration::from_secs(config.redirect_cache_ttl_seconds),
+                StdDuration::from_secs(config.redirect_cache_negative_ttl_seconds),
+            ))
+        });
+        let id_policy = Arc::new(id_policy(&config));
+        Handler {
+            url_repo,
+            config,
+            rate_limiter: None,
+            redirect_cache,
+            access_logger: None,
+            id_policy,
+            shutting_down: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Makes `readyz` fail from now on. Called when graceful shutdown starts.
+    pub fn mark_shutting_down(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(Arc::new(rate_limiter));
+        self
+    }
+
+    pub fn with_access_logger(mut self, access_logger: Arc<AccessLogger>) -> Self {
+        self.access_logger = Some(access_logger);
+        self
+    }
+
+    /// Keys the request by its peer address rather than the logged client IP,
+    /// which comes from headers any client can set. `X-Forwarded-For` counts
+    /// only when the peer is a trusted proxy.
+    async fn check_rate_limit(&self, scope: Scope, req: &HttpRequest) -> Result<(), HandlerError> {
+        let Some(rate_limiter) = &self.rate_limiter else {
+            return Ok(());
+        };
+        let key = req.peer_addr().map(|peer| {
+            rate_limiter
+                .trusted_proxies()
+                .client_ip(
+                    peer.ip(),
+                    req.headers()
+                        .get_all("x-forwarded-for")
+                        .filter_map(|v| v.to_str().ok())
+                        .flat_map(|v| v.split(','))
+                        .collect::<Vec<_>>()
+                        .into_iter(),
+                )
+                .to_string()
+        });
+        let key = key.as_deref().unwrap_or("unknown");
+        match rate_limiter.check(scope, key).await {
+            Decision::Allowed => Ok(()),
+            Decision::Limited { retry_after } => {
+                tracing::info!(event = "rate_limited", scope = scope.as_str(), ip = key);
+                Err(HandlerError::RateLimited(retry_after))
+            }
+        }
+    }
+
+    /// Looks up a link and its enabled flag, going through the redirect cache
+    /// when it is configured.
+    async fn resolve_link(&self, id: &ID) -> Result<Option<ResolvedLink>, HandlerError> {
+        if let Some(cached) = self
+            .redirect_cache
+            .as_ref()
+            .and_then(|cache| cache.get(id.0.as_str()))
+        {
+            return Ok(cached);
+        }
+
+        let link = match self
+            .url_repo
+            .find_by_id(id.clone())
+            .await
+            .map_err(HandlerError::DBError)?
+        {
+            Some(url) => {
+                let state = self
+                    .url_repo
+                    .get_state(id.0.as_str())
+                    .await
+                    .map_err(HandlerError::DBError)?;
+                Some(ResolvedLink {
+                    url,
+                    enabled: state.is_none_or(|s| s.enabled),
+                })
+            }
+            None => None,
+        };
+
+        if let Some(cache) = &self.redirect_cache {
+            cache.insert(id.0.as_str(), link.clone());
+        }
+        Ok(link)
+    }
+
+    /// Hands the event to the background access logger, or writes it inline
+    /// when none is configured.
+    async fn record_access(&self, event: AccessEvent) {
+        match &self.access_logger {
+            Some(logger) => logger.record(event).await,
+            None => {
+                let _ = self
+                    .url_repo
+                    .log_access_batch(std::slice::from_ref(&event))
+                    .await;
+            }
+        }
+    }
+
+    fn invalidate_cached_link(&self, id: &str) {
+        if let Some(cache) = &self.redirect_cache {
+            cache.invalidate(id);
+        }
     }
 
     fn extract_request_meta(req: &HttpRequest) -> (Option<String>, Option<String>, Option<String>) {
@@ -98,7 +393,47 @@ impl<T: ShortenedURLRepository> Handler<T> {
     }
 
     pub async fn readyz(&self) -> impl Responder + use<T> {
-        HttpResponse::Ok().body("Ok")
+        let mut checks = Vec::new();
+
+        if self.shutting_down.load(Ordering::SeqCst) {
+            checks.push(ReadinessCheck {
+                name: "shutdown",
+                ok: false,
+                error: Some("Server is shutting down".to_string()),
+            });
+        }
+
+        let timeout = StdDuration::from_millis(self.config.readiness_timeout_ms);
+        let storage_error = match tokio::time::timeout(timeout, self.url_repo.ping()).await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(e.to_string()),
+            Err(_) => Some(format!("Timed out after {}ms", timeout.as_millis())),
+        };
+        if let Some(error) = &storage_error {
+            tracing::warn!(error = error.as_str(), "Readiness check failed for storage");
+        }
+        checks.push(ReadinessCheck {
+            name: "storage",
+            ok: storage_error.is_none(),
+            error: storage_error,
+        });
+
+        let ready = checks.iter().all(|c| c.ok);
+        let response = ReadinessResponse {
+            status: if ready { "ready" } else { "not_ready" },
+            checks,
+        };
+        if ready {
+            HttpResponse::Ok().json(response)
+        } else {
+            HttpResponse::ServiceUnavailable().json(response)
+        }
+    }
+
+    pub async fn metrics(&self) -> impl Responder + use<T> {
+        HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4")
+            .body(metrics::registry::METRICS.render())
     }
 
     pub async fn shorten(
@@ -106,33 +441,38 @@ impl<T: ShortenedURLRepository> Handler<T> {
         req: HttpRequest,
         info: web::Json<ShortenParams>,
     ) -> Result<impl Responder + use<T>, HandlerError> {
-        let url = info.url.trim();
-        if url.is_empty() {
-            return Err(HandlerError::ParamError(
-                "The 'url' parameter is required.".to_string(),
-            ));
-        }
+        let (ip, user_agent, request_id) = Self::extract_request_meta(&req);
+        self.check_rate_limit(Scope::Shorten, &req).await?;
 
-        let url = Url::parse(url)
-            .map_err(|e| HandlerError::ParamError(format!("Invalid URL format: {}", e)))?;
+        let url = parse_destination(&info.url)?;
 
-        match url.scheme() {
-            "http" | "https" => {}
-            _ => {
-                return Err(HandlerError::ParamError(
-                    "Only http and https URLs are supported.".to_string(),
-                ));
-            }
+        if let Some(custom_id) = info.custom_id.as_deref() {
+            self.id_policy
+                .validate(custom_id)
+                .map_err(|e| HandlerError::ParamError(e.to_string()))?;
         }
 
-        let shortened = self
+        let now = Utc::now();
+        let expires_at = resolve_expires_at(&self.config, info.expires_at, info.expires_in, now)?;
+
+        let outcome = self
             .url_repo
-            .create(url, info.custom_id.as_deref(), None)
+            .create(url, info.custom_id.as_deref(), expires_at)
             .await
-            .map_err(|e| HandlerError::DBError(e.into()))?;
-
-        let (ip, user_agent, request_id) = Self::extract_request_meta(&req);
-        let now = chrono::Utc::now();
+            .map_err(HandlerError::from_repository)?;
+        let shortened = match outcome {
+            CreateOutcome::Created(url) => url,
+            // A retry of an earlier request: the link and its logs already exist.
+            CreateOutcome::AlreadyExists(url) => {
+                return Ok(web::Json(ShortenResponse {
+                    id: url.id,
+                    expires_at: url.expires_at,
+                }));
+            }
+        };
+        metrics::registry::record_creation(info.custom_id.is_some());
+        // The ID may have been remembered as unknown before it was created.
+        self.invalidate_cached_link(shortened.id.0.as_str());
 
         let _ = self
             .url_repo
@@ -165,7 +505,10 @@ impl<T: ShortenedURLRepository> Handler<T> {
             original_url = shortened.original_url.as_str()
         );
 
-        Ok(web::Json(ShortenResponse { id: shortened.id }))
+        Ok(web::Json(ShortenResponse {
+            id: shortened.id,
+            expires_at: shortened.expires_at,
+        }))
     }
 
     pub async fn redirect(
@@ -176,26 +519,23 @@ impl<T: ShortenedURLRepository> Handler<T> {
         let id = ID::new(path.into_inner());
 
         let (ip, user_agent, request_id) = Self::extract_request_meta(&req);
-        let now = chrono::Utc::now();
+        self.check_rate_limit(Scope::Redirect, &req).await?;
+        let now = Utc::now();
+        let access_event = |status_code: i32, update_last_access: bool| AccessEvent {
+            id: id.0.clone(),
+            ts: now,
+            ip: ip.clone(),
+            user_agent: user_agent.clone(),
+            request_id: request_id.clone(),
+            status_code,
+            update_last_access,
+        };
 
-        let url = self
-            .url_repo
-            .find_by_id(id.clone())
-            .await
-            .map_err(|e| HandlerError::DBError(e.into()))?;
-
-        let Some(url) = url else {
-            let _ = self
-                .url_repo
-                .log_access(
-                    id.0.as_str(),
-                    now,
-                    ip.as_deref(),
-                    user_agent.as_deref(),
-                    request_id.as_deref(),
-                    404,
-                )
-                .await;
+        let link = self.resolve_link(&id).await?;
+
+        let Some(ResolvedLink { url, enabled }) = link else {
+            self.record_access(access_event(404, false)).await;
+            metrics::registry::record_redirect(404);
             tracing::info!(
                 event = "short_url_access",
                 id = id.0.as_str(),
@@ -207,28 +547,14 @@ impl<T: ShortenedURLRepository> Handler<T> {
             return Err(HandlerError::NotFound);
         };
 
-        let state = self
-            .url_repo
-            .get_state(id.0.as_str())
-            .await
-            .map_err(|e| HandlerError::DBError(e.into()))?;
-        if matches!(state.as_ref(), Some(ShortUrlState { enabled: false, .. })) {
-            let _ = self.url_repo.set_last_access(id.0.as_str(), now, 410).await;
-            let _ = self
-                .url_repo
-                .log_access(
-                    id.0.as_str(),
-                    now,
-                    ip.as_deref(),
-                    user_agent.as_deref(),
-                    request_id.as_deref(),
-                    410,
-                )
-                .await;
+        if !enabled {
+            self.record_access(access_event(410, true)).await;
+            metrics::registry::record_redirect(410);
             tracing::info!(
                 event = "short_url_access",
                 id = id.0.as_str(),
                 status_code = 410,
+                reason = "disabled",
                 ip = ip.as_deref().unwrap_or(""),
                 user_agent = user_agent.as_deref().unwrap_or(""),
                 request_id = request_id.as_deref().unwrap_or("")
@@ -236,21 +562,26 @@ impl<T: ShortenedURLRepository> Handler<T> {
             return Err(HandlerError::Disabled);
         }
 
+        if url.is_expired(now) {
+            self.record_access(access_event(410, true)).await;
+            metrics::registry::record_redirect(410);
+            tracing::info!(
+                event = "short_url_access",
+                id = id.0.as_str(),
+                status_code = 410,
+                reason = "expired",
+                ip = ip.as_deref().unwrap_or(""),
+                user_agent = user_agent.as_deref().unwrap_or(""),
+                request_id = request_id.as_deref().unwrap_or("")
+            );
+            return Err(HandlerError::Expired);
+        }
+
         match url.original_url.scheme() {
             "http" | "https" => {}
             _ => {
-                let _ = self.url_repo.set_last_access(id.0.as_str(), now, 400).await;
-                let _ = self
-                    .url_repo
-                    .log_access(
-                        id.0.as_str(),
-                        now,
-                        ip.as_deref(),
-                        user_agent.as_deref(),
-                        request_id.as_deref(),
-                        400,
-                    )
-                    .await;
+                self.record_access(access_event(400, true)).await;
+                metrics::registry::record_redirect(400);
                 tracing::info!(
                     event = "short_url_access",
                     id = id.0.as_str(),
@@ -265,18 +596,8 @@ impl<T: ShortenedURLRepository> Handler<T> {
             }
         }
 
-        let _ = self.url_repo.set_last_access(id.0.as_str(), now, 308).await;
-        let _ = self
-            .url_repo
-            .log_access(
-                id.0.as_str(),
-                now,
-                ip.as_deref(),
-                user_agent.as_deref(),
-                request_id.as_deref(),
-                308,
-            )
-            .await;
+        self.record_access(access_event(308, true)).await;
+        metrics::registry::record_redirect(308);
         tracing::info!(
             event = "short_url_access",
             id = id.0.as_str(),
@@ -295,63 +616,22 @@ impl<T: ShortenedURLRepository> Handler<T> {
     ) -> Result<impl Responder + use<T>, HandlerError> {
         let limit = query.limit.unwrap_or(20).clamp(1, 100);
 
-        let paging_state = match query.page_state.as_deref().map(str::trim) {
-            None | Some("") => None,
-            Some(token) => {
-                let decoded = URL_SAFE_NO_PAD
-                    .decode(token)
-                    .map_err(|_| HandlerError::ParamError("Invalid page_state".to_string()))?;
-                Some(decoded)
-            }
-        };
+        let paging_state = decode_page_state(query.page_state.as_deref())?;
 
         let (urls, next_page_state) = self
             .url_repo
             .list_by_created_at_page(limit, paging_state)
             .await
-            .map_err(|e| HandlerError::DBError(e.into()))?;
+            .map_err(HandlerError::DBError)?;
 
+        let now = Utc::now();
         let mut items = Vec::with_capacity(urls.len());
         for url in urls {
-            let id = url.id.0.clone();
-            let state = self
-                .url_repo
-                .get_state(&id)
-                .await
-                .map_err(|e| HandlerError::DBError(e.into()))?;
-            let last_access = self
-                .url_repo
-                .get_last_access(&id)
-                .await
-                .map_err(|e| HandlerError::DBError(e.into()))?;
-            let create_meta = self
-                .url_repo
-                .get_create_meta(&id)
-                .await
-                .map_err(|e| HandlerError::DBError(e.into()))?;
-
-            let (creator_ip, creator_user_agent, creator_request_id) = match create_meta {
-                Some((_ts, ip, ua, rid)) => {
-                    let ip = (!ip.is_empty()).then_some(ip);
-                    let ua = (!ua.is_empty()).then_some(ua);
-                    let rid = (!rid.is_empty()).then_some(rid);
-                    (ip, ua, rid)
-                }
-                None => (None, None, None),
-            };
-
-            items.push(AdminLinkListItem {
-                id: url.id,
-                original_url: url.original_url,
-                created_at: url.created_at,
-                expires_at: url.expires_at,
-                enabled: state.as_ref().map(|s| s.enabled).unwrap_or(true),
-                disabled_at: state.and_then(|s| s.disabled_at),
-                last_access_at: last_access.map(|(ts, _)| ts),
-                creator_ip,
-                creator_user_agent,
-                creator_request_id,
-            });
+            items.push(
+                AdminLinkListItem::load(&self.url_repo, url, now)
+                    .await
+                    .map_err(HandlerError::DBError)?,
+            );
         }
 
         let next_page_state = next_page_state.map(|raw| URL_SAFE_NO_PAD.encode(raw));
@@ -362,32 +642,176 @@ impl<T: ShortenedURLRepository> Handler<T> {
         }))
     }
 
-    pub async fn admin_get_link(
-        &self,
-        path: web::Path<String>,
-    ) -> Result<impl Responder + use<T>, HandlerError> {
-        let id = ID::new(path.into_inner());
+    /// Records an admin mutation, tagged with the ID of the request that made it.
+    /// The mutation has already happened, so a failure is logged with the
+    /// entry rather than failing the request.
+    async fn record_audit(&self, req: &HttpRequest, mut entry: AuditEntry) {
+        let (_, _, request_id) = Self::extract_request_meta(req);
+        entry.request_id = request_id;
+        if let Err(e) = self.url_repo.record_audit(&entry).await {
+            tracing::error!(
+                event = "admin_audit_write_failed",
+                error = %e,
+                entry = serde_json::to_string(&entry).unwrap_or_default(),
+                "Failed to record admin audit entry"
+            );
+        }
+    }
 
+    async fn admin_view(&self, id: ID) -> Result<ShortUrlAdminView, HandlerError> {
         let url = self
             .url_repo
             .find_by_id(id.clone())
             .await
-            .map_err(|e| HandlerError::DBError(e.into()))?;
+            .map_err(HandlerError::DBError)?;
         let state = self
             .url_repo
             .get_state(id.0.as_str())
             .await
-            .map_err(|e| HandlerError::DBError(e.into()))?;
+            .map_err(HandlerError::DBError)?;
+        let create_meta = self
+            .url_repo
+            .get_create_meta(id.0.as_str())
+            .await
+            .map_err(HandlerError::DBError)?;
 
+        let now = Utc::now();
+        let creator_metadata_expired = create_meta.is_none()
+            && url
+                .as_ref()
+                .is_some_and(|u| self.url_repo.create_meta_aged_out(u.created_at, now));
         let view = ShortUrlAdminView {
-            id: id,
+            id,
             original_url: url.as_ref().map(|u| u.original_url.clone()),
             created_at: url.as_ref().map(|u| u.created_at),
             expires_at: url.as_ref().and_then(|u| u.expires_at),
+            expired: url.as_ref().is_some_and(|u| u.is_expired(now)),
             state,
+            creator_metadata_expired,
         };
+        Ok(view)
+    }
 
-        Ok(web::Json(view))
+    pub async fn admin_get_link(
+        &self,
+        path: web::Path<String>,
+    ) -> Result<impl Responder + use<T>, HandlerError> {
+        let id = ID::new(path.into_inner());
+        Ok(web::Json(self.admin_view(id).await?))
+    }
+
+    pub async fn admin_update_link(
+        &self,
+        req: HttpRequest,
+        principal: Principal,
+        path: web::Path<String>,
+        params: web::Json<AdminUpdateLinkParams>,
+    ) -> Result<impl Responder + use<T>, HandlerError> {
+        let id = ID::new(path.into_inner());
+        let new_url = parse_destination(&params.url)?;
+        let reason = parse_reason(params.reason.as_deref())?;
+
+        let before = self
+            .url_repo
+            .find_by_id(id.clone())
+            .await
+            .map_err(HandlerError::DBError)?
+            .ok_or(HandlerError::NotFound)?;
+        let now = Utc::now();
+        let url = self
+            .url_repo
+            .update_destination(&id.0, new_url, &principal.subject, now)
+            .await
+            .map_err(HandlerError::DBError)?
+            .ok_or(HandlerError::NotFound)?;
+        self.invalidate_cached_link(&id.0);
+
+        // Like the destination history, a no-op edit is not recorded.
+        if before.original_url != url.original_url {
+            let mut entry = AuditEntry::new(
+                &principal.subject,
+                AuditAction::UpdateDestination,
+                &id.0,
+                now,
+            );
+            entry.reason = reason;
+            entry.before = Some(serde_json::json!({ "original_url": before.original_url }));
+            entry.after = Some(serde_json::json!({ "original_url": url.original_url }));
+            self.record_audit(&req, entry).await;
+        }
+        tracing::info!(
+            event = "short_url_destination_updated",
+            id = id.0.as_str(),
+            subject = principal.subject.as_str(),
+            original_url = url.original_url.as_str()
+        );
+
+        Ok(web::Json(self.admin_view(id).await?))
+    }
+
+    /// Purges the link and its logs. `confirm` must repeat the ID, so that
+    /// a stray request cannot destroy data. Only this instance's redirect
+    /// cache is invalidated; other instances may keep redirecting the link
+    /// for up to `REDIRECT_CACHE_TTL_SECONDS`.
+    pub async fn admin_delete_link(
+        &self,
+        req: HttpRequest,
+        principal: Principal,
+        path: web::Path<String>,
+        query: web::Query<AdminDeleteQuery>,
+    ) -> Result<impl Responder + use<T>, HandlerError> {
+        let id = ID::new(path.into_inner());
+        if query.confirm.as_deref().map(str::trim) != Some(id.0.as_str()) {
+            return Err(HandlerError::ParamError(
+                "Deleting a link requires 'confirm' set to its ID.".to_string(),
+            ));
+        }
+        let reason = parse_reason(query.reason.as_deref())?;
+
+        let now = Utc::now();
+        let deleted = self
+            .url_repo
+            .delete(&id.0, &principal.subject, now)
+            .await
+            .map_err(HandlerError::DBError)?;
+        if !deleted {
+            return Err(HandlerError::NotFound);
+        }
+        self.invalidate_cached_link(&id.0);
+
+        // No snapshots: the point of deleting is to not keep the link's data.
+        let mut entry = AuditEntry::new(&principal.subject, AuditAction::Delete, &id.0, now);
+        entry.reason = reason;
+        self.record_audit(&req, entry).await;
+        tracing::info!(
+            event = "short_url_deleted",
+            id = id.0.as_str(),
+            subject = principal.subject.as_str()
+        );
+
+        Ok(HttpResponse::NoContent().finish())
+    }
+
+    pub async fn admin_link_history(
+        &self,
+        path: web::Path<String>,
+    ) -> Result<impl Responder + use<T>, HandlerError> {
+        let id = ID::new(path.into_inner());
+        let url = self
+            .url_repo
+            .find_by_id(id.clone())
+            .await
+            .map_err(HandlerError::DBError)?;
+        if url.is_none() {
+            return Err(HandlerError::NotFound);
+        }
+
+        let items = self
+            .url_repo
+            .list_destination_history(&id.0)
+            .await
+            .map_err(HandlerError::DBError)?;
+        Ok(web::Json(AdminLinkHistoryResponse { items }))
     }
 
     pub async fn admin_list_access_logs(
@@ -402,65 +826,243 @@ impl<T: ShortenedURLRepository> Handler<T> {
                 "The 'id' parameter is required.".to_string(),
             ));
         }
+        let id = ID::new(id.to_string());
 
         let url = self
             .url_repo
-            .find_by_id(ID::new(id.to_string()))
+            .find_by_id(id.clone())
             .await
-            .map_err(|e| HandlerError::DBError(e.into()))?;
+            .map_err(HandlerError::DBError)?;
         if url.is_none() {
             return Err(HandlerError::NotFound);
         }
 
         let limit = query.limit.unwrap_or(100).clamp(1, 500);
-        let rows = self
+        let filter = AccessLogFilter {
+            from: query.from,
+            to: query.to,
+            status_code: query.status_code,
+        };
+        if let (Some(from), Some(to)) = (filter.from, filter.to)
+            && from >= to
+        {
+            return Err(HandlerError::ParamError(
+                "'from' must be earlier than 'to'.".to_string(),
+            ));
+        }
+        let paging_state = decode_page_state(query.page_state.as_deref())?;
+
+        let (rows, next_page_state) = self
             .url_repo
-            .list_access_logs_recent(id, limit)
+            .list_access_logs_page(&id.0, &filter, limit, paging_state)
             .await
-            .map_err(|e| HandlerError::DBError(e.into()))?;
-
-        let mut items = Vec::with_capacity(rows.len());
-        for (ts, ip, ua, rid, status_code) in rows {
-            let ip = (!ip.is_empty()).then_some(ip);
-            let ua = (!ua.is_empty()).then_some(ua);
-            let rid = (!rid.is_empty()).then_some(rid);
-            items.push(AdminAccessLogItem {
-                ts,
-                ip,
-                user_agent: ua,
-                request_id: rid,
-                status_code,
-            });
-        }
+            .map_err(HandlerError::DBError)?;
 
-        Ok(web::Json(AdminAccessLogResponse { items }))
+        Ok(web::Json(AdminAccessLogResponse {
+            items: rows.into_iter().map(AdminAccessLogItem::from).collect(),
+            next_page_state: next_page_state.map(|raw| URL_SAFE_NO_PAD.encode(raw)),
+        }))
     }
 
     pub async fn admin_disable(
         &self,
+        req: HttpRequest,
+        principal: Principal,
         path: web::Path<String>,
+        body: web::Bytes,
     ) -> Result<impl Responder + use<T>, HandlerError> {
-        let id = path.into_inner();
-        let now = chrono::Utc::now();
-        self.url_repo
-            .set_enabled(&id, false, now)
+        self.admin_set_enabled(req, principal, path, body, false)
             .await
-            .map_err(|e| HandlerError::DBError(e.into()))?;
-        Ok(HttpResponse::Ok().finish())
     }
 
     pub async fn admin_restore(
         &self,
+        req: HttpRequest,
+        principal: Principal,
         path: web::Path<String>,
+        body: web::Bytes,
     ) -> Result<impl Responder + use<T>, HandlerError> {
-        let id = path.into_inner();
+        self.admin_set_enabled(req, principal, path, body, true)
+            .await
+    }
+
+    async fn admin_set_enabled(
+        &self,
+        req: HttpRequest,
+        principal: Principal,
+        path: web::Path<String>,
+        body: web::Bytes,
+        enabled: bool,
+    ) -> Result<web::Json<ShortUrlAdminView>, HandlerError> {
+        let id = ID::new(path.into_inner());
+        let params = parse_state_change(&body)?;
+        let reason = parse_reason(params.reason.as_deref())?;
+
+        let view = self
+            .set_enabled(&req, &principal, id, enabled, reason)
+            .await?
+            .ok_or(HandlerError::NotFound)?;
+        Ok(web::Json(view))
+    }
+
+    pub async fn admin_bulk_disable(
+        &self,
+        req: HttpRequest,
+        principal: Principal,
+        params: web::Json<AdminBulkStateChangeParams>,
+    ) -> Result<impl Responder + use<T>, HandlerError> {
+        self.admin_bulk_set_enabled(req, principal, params, false)
+            .await
+    }
+
+    pub async fn admin_bulk_restore(
+        &self,
+        req: HttpRequest,
+        principal: Principal,
+        params: web::Json<AdminBulkStateChangeParams>,
+    ) -> Result<impl Responder + use<T>, HandlerError> {
+        self.admin_bulk_set_enabled(req, principal, params, true)
+            .await
+    }
+
+    /// Applies the change to each link in turn. Unknown IDs are reported per
+    /// item rather than failing the request; a storage error stops it, leaving
+    /// the links before it changed.
+    async fn admin_bulk_set_enabled(
+        &self,
+        req: HttpRequest,
+        principal: Principal,
+        params: web::Json<AdminBulkStateChangeParams>,
+        enabled: bool,
+    ) -> Result<web::Json<AdminBulkStateChangeResponse>, HandlerError> {
+        let params = params.into_inner();
+        let reason = parse_reason(params.reason.as_deref())?;
+        if params.ids.is_empty() || params.ids.len() > MAX_BULK_IDS {
+            return Err(HandlerError::ParamError(format!(
+                "'ids' must list between 1 and {} IDs.",
+                MAX_BULK_IDS
+            )));
+        }
+
+        let mut ids: Vec<ID> = Vec::with_capacity(params.ids.len());
+        for id in params.ids {
+            let id = id.trim();
+            if id.is_empty() {
+                return Err(HandlerError::ParamError(
+                    "'ids' must not contain blank IDs.".to_string(),
+                ));
+            }
+            let id = ID::new(id.to_string());
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+
+        let mut items = Vec::with_capacity(ids.len());
+        for id in ids {
+            let link = self
+                .set_enabled(&req, &principal, id.clone(), enabled, reason.clone())
+                .await?;
+            let status = match link {
+                Some(_) => AdminBulkItemStatus::Updated,
+                None => AdminBulkItemStatus::NotFound,
+            };
+            items.push(AdminBulkStateChangeItem { id, status, link });
+        }
+        Ok(web::Json(AdminBulkStateChangeResponse { items }))
+    }
+
+    /// Disables or restores a link and records it in the audit log. Returns
+    /// `None` when there is no such link.
+    async fn set_enabled(
+        &self,
+        req: &HttpRequest,
+        principal: &Principal,
+        id: ID,
+        enabled: bool,
+        reason: Option<String>,
+    ) -> Result<Option<ShortUrlAdminView>, HandlerError> {
+        let url = self
+            .url_repo
+            .find_by_id(id.clone())
+            .await
+            .map_err(HandlerError::DBError)?;
+        if url.is_none() {
+            return Ok(None);
+        }
+
+        let before = self
+            .url_repo
+            .get_state(&id.0)
+            .await
+            .map_err(HandlerError::DBError)?;
         let now = chrono::Utc::now();
         self.url_repo
-            .set_enabled(&id, true, now)
+            .set_enabled(&id.0, enabled, now)
             .await
-            .map_err(|e| HandlerError::DBError(e.into()))?;
-        Ok(HttpResponse::Ok().finish())
+            .map_err(HandlerError::DBError)?;
+        self.invalidate_cached_link(&id.0);
+
+        let view = self.admin_view(id).await?;
+        let action = if enabled {
+            AuditAction::Restore
+        } else {
+            AuditAction::Disable
+        };
+        let mut entry = AuditEntry::new(&principal.subject, action, &view.id.0, now);
+        entry.reason = reason;
+        entry.before = Some(state_snapshot(before.as_ref()));
+        entry.after = Some(state_snapshot(view.state.as_ref()));
+        self.record_audit(req, entry).await;
+
+        Ok(Some(view))
     }
+
+    pub async fn admin_list_audit(
+        &self,
+        query: web::Query<AdminAuditQuery>,
+    ) -> Result<impl Responder + use<T>, HandlerError> {
+        let limit = query.limit.unwrap_or(50).clamp(1, 100);
+        let filter = AuditFilter {
+            link_id: query
+                .link_id
+                .as_deref()
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(|id| ID::new(id.to_string()).0),
+            actor: query
+                .actor
+                .as_deref()
+                .map(str::trim)
+                .filter(|actor| !actor.is_empty())
+                .map(str::to_string),
+        };
+        let paging_state = decode_page_state(query.page_state.as_deref())?;
+
+        let (items, next_page_state) = self
+            .url_repo
+            .list_audit_page(&filter, limit, paging_state)
+            .await
+            .map_err(HandlerError::DBError)?;
+
+        Ok(web::Json(AdminAuditResponse {
+            items,
+            next_page_state: next_page_state.map(|raw| URL_SAFE_NO_PAD.encode(raw)),
+        }))
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReadinessCheck {
+    pub name: &'static str,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ReadinessResponse {
+    pub status: &'static str,
+    pub checks: Vec<ReadinessCheck>,
 }
 
 #[derive(Serialize)]
@@ -470,11 +1072,116 @@ pub struct AdminLinkListItem {
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
     pub enabled: bool,
+    pub expired: bool,
     pub disabled_at: Option<chrono::DateTime<chrono::Utc>>,
     pub last_access_at: Option<chrono::DateTime<chrono::Utc>>,
     pub creator_ip: Option<String>,
     pub creator_user_agent: Option<String>,
     pub creator_request_id: Option<String>,
+    pub creator_metadata_expired: bool,
+}
+
+impl AdminLinkListItem {
+    /// Gathers the state, last access and creator metadata of `url`.
+    pub async fn load<T: ShortenedURLRepository>(
+        repo: &T,
+        url: ShortenedURL,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Self> {
+        let id = url.id.0.as_str();
+        let state = repo.get_state(id).await?;
+        let last_access = repo.get_last_access(id).await?;
+        let create_meta = repo.get_create_meta(id).await?;
+
+        let creator_metadata_expired =
+            create_meta.is_none() && repo.create_meta_aged_out(url.created_at, now);
+        let (creator_ip, creator_user_agent, creator_request_id) = match create_meta {
+            Some((_ts, ip, ua, rid)) => {
+                let ip = (!ip.is_empty()).then_some(ip);
+                let ua = (!ua.is_empty()).then_some(ua);
+                let rid = (!rid.is_empty()).then_some(rid);
+                (ip, ua, rid)
+            }
+            None => (None, None, None),
+        };
+
+        let expired = url.is_expired(now);
+        Ok(AdminLinkListItem {
+            id: url.id,
+            original_url: url.original_url,
+            created_at: url.created_at,
+            expires_at: url.expires_at,
+            enabled: state.as_ref().map(|s| s.enabled).unwrap_or(true),
+            expired,
+            disabled_at: state.and_then(|s| s.disabled_at),
+            last_access_at: last_access.map(|(ts, _)| ts),
+            creator_ip,
+            creator_user_agent,
+            creator_request_id,
+            creator_metadata_expired,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AdminUpdateLinkParams {
+    pub url: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AdminDeleteQuery {
+    pub confirm: Option<String>,
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct AdminStateChangeParams {
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AdminBulkStateChangeParams {
+    pub ids: Vec<String>,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminBulkItemStatus {
+    Updated,
+    NotFound,
+}
+
+#[derive(Serialize)]
+pub struct AdminBulkStateChangeItem {
+    pub id: ID,
+    pub status: AdminBulkItemStatus,
+    pub link: Option<ShortUrlAdminView>,
+}
+
+#[derive(Serialize)]
+pub struct AdminBulkStateChangeResponse {
+    pub items: Vec<AdminBulkStateChangeItem>,
+}
+
+#[derive(Deserialize)]
+pub struct AdminAuditQuery {
+    pub link_id: Option<String>,
+    pub actor: Option<String>,
+    pub limit: Option<i32>,
+    pub page_state: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AdminAuditResponse {
+    pub items: Vec<AuditEntry>,
+    pub next_page_state: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AdminLinkHistoryResponse {
+    pub items: Vec<DestinationChange>,
 }
 
 #[derive(Serialize)]
@@ -492,6 +1199,10 @@ pub struct AdminListQuery {
 #[derive(Deserialize)]
 pub struct AdminAccessLogQuery {
     pub limit: Option<i32>,
+    pub page_state: Option<String>,
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+    pub status_code: Option<i32>,
 }
 
 #[derive(Serialize)]
@@ -503,18 +1214,86 @@ pub struct AdminAccessLogItem {
     pub status_code: i32,
 }
 
+impl From<AccessLogRow> for AdminAccessLogItem {
+    fn from((ts, ip, ua, rid, status_code): AccessLogRow) -> Self {
+        AdminAccessLogItem {
+            ts,
+            ip: (!ip.is_empty()).then_some(ip),
+            user_agent: (!ua.is_empty()).then_some(ua),
+            request_id: (!rid.is_empty()).then_some(rid),
+            status_code,
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct AdminAccessLogResponse {
     pub items: Vec<AdminAccessLogItem>,
+    pub next_page_state: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct ShortenParams {
     pub url: String,
     pub custom_id: Option<String>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub expires_in: Option<i64>,
 }
 
 #[derive(Serialize)]
 pub struct ShortenResponse {
     pub id: ID,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use actix_web::http::StatusCode;
+    use envconfig::Envconfig;
+
+    use super::*;
+
+    #[test]
+    fn test_resolve_expires_at() {
+        let config = Config::init_from_hashmap(&HashMap::from([
+            ("EXPIRES_IN_MIN_SECONDS".to_string(), "60".to_string()),
+            ("EXPIRES_IN_MAX_SECONDS".to_string(), "86400".to_string()),
+        ]))
+        .unwrap();
+        let now = Utc::now();
+        let resolve =
+            |expires_at, expires_in| resolve_expires_at(&config, expires_at, expires_in, now);
+
+        assert_eq!(resolve(None, None).unwrap(), None);
+        assert_eq!(
+            resolve(None, Some(3600)).unwrap(),
+            Some(now + Duration::hours(1))
+        );
+        assert_eq!(
+            resolve(Some(now + Duration::hours(2)), None).unwrap(),
+            Some(now + Duration::hours(2))
+        );
+        assert_eq!(
+            resolve(None, Some(60)).unwrap(),
+            Some(now + Duration::seconds(60))
+        );
+        assert_eq!(
+            resolve(None, Some(86400)).unwrap(),
+            Some(now + Duration::days(1))
+        );
+
+        assert!(resolve(None, Some(59)).is_err());
+        assert!(resolve(None, Some(86401)).is_err());
+        assert!(resolve(None, Some(i64::MAX)).is_err());
+        assert!(resolve(Some(now - Duration::hours(1)), None).is_err());
+        assert!(resolve(Some(now + Duration::hours(1)), Some(3600)).is_err());
+    }
+
+    #[test]
+    fn test_expired_link_is_gone() {
+        let response = HandlerError::Expired.error_response();
+        assert_eq!(response.status(), StatusCode::GONE);
+    }
 }
@@ -0,0 +1,175 @@
+use actix_web::{middleware::from_fn, web};
+
+use crate::{
+    auth::middleware::require_admin,
+    domain::repository::ShortenedURLRepository,
+    handler::handlers::{Handler, ShortenParams},
+};
+
+/// First path segments served by routes other than the `/{id}` redirect.
+pub const RESERVED_SEGMENTS: &[&str] = &["api", "health", "metrics"];
+
+pub fn configure<T>(cfg: &mut web::ServiceConfig)
+where
+    T: ShortenedURLRepository + 'static,
+{
+    cfg.service(
+        web::scope("/health")
+            .route(
+                "/readyz",
+                web::get()
+                    .to(|handler: web::Data<Handler<T>>| async move { handler.readyz().await }),
+            )
+            .route(
+                "/livez",
+                web::get()
+                    .to(|handler: web::Data<Handler<T>>| async move { handler.livez().await }),
+            ),
+    )
+    .service(
+        web::scope("/api").service(
+            web::scope("/v1")
+                .route(
+                    "/shorten",
+                    web::post().to(
+                        |handler: web::Data<Handler<T>>,
+                         req: actix_web::HttpRequest,
+                         info: web::Json<ShortenParams>| async move {
+                            handler.shorten(req, info).await
+                        },
+                    ),
+                )
+                .service(
+                    web::scope("/admin")
+                        .wrap(from_fn(require_admin))
+                        .route(
+                            "/audit",
+                            web::get().to(|handler: web::Data<Handler<T>>, query| async move {
+                                handler.admin_list_audit(query).await
+                            }),
+                        )
+                        .service(
+                            web::scope("/links")
+                                .route(
+                                    "",
+                                    web::get().to(
+                                        |handler: web::Data<Handler<T>>, query| async move {
+                                            handler.admin_list_links(query).await
+                                        },
+                                    ),
+                                )
+                                .route(
+                                    "/disable",
+                                    web::post().to(
+                                        |handler: web::Data<Handler<T>>,
+                                         req,
+                                         principal,
+                                         params| async move {
+                                            handler.admin_bulk_disable(req, principal, params).await
+                                        },
+                                    ),
+                                )
+                                .route(
+                                    "/restore",
+                                    web::post().to(
+                                        |handler: web::Data<Handler<T>>,
+                                         req,
+                                         principal,
+                                         params| async move {
+                                            handler.admin_bulk_restore(req, principal, params).await
+                                        },
+                                    ),
+                                )
+                                .route(
+                                    "/{id}/accesses",
+                                    web::get().to(
+                                        |handler: web::Data<Handler<T>>, path, query| async move {
+                                            handler.admin_list_access_logs(path, query).await
+                                        },
+                                    ),
+                                )
+                                .route(
+                                    "/{id}",
+                                    web::get().to(
+                                        |handler: web::Data<Handler<T>>, path| async move {
+                                            handler.admin_get_link(path).await
+                                        },
+                                    ),
+                                )
+                                .route(
+                                    "/{id}",
+                                    web::patch().to(
+                                        |handler: web::Data<Handler<T>>,
+                                         req,
+                                         principal,
+                                         path,
+                                         params| async move {
+                                            handler
+                                                .admin_update_link(req, principal, path, params)
+                                                .await
+                                        },
+                                    ),
+                                )
+                                .route(
+                                    "/{id}",
+                                    web::delete().to(
+                                        |handler: web::Data<Handler<T>>,
+                                         req,
+                                         principal,
+                                         path,
+                                         query| async move {
+                                            handler
+                                                .admin_delete_link(req, principal, path, query)
+                                                .await
+                                        },
+                                    ),
+                                )
+                                .route(
+                                    "/{id}/history",
+                                    web::get().to(
+                                        |handler: web::Data<Handler<T>>, path| async move {
+                                            handler.admin_link_history(path).await
+                                        },
+                                    ),
+                                )
+                                .route(
+                                    "/{id}/disable",
+                                    web::post().to(
+                                        |handler: web::Data<Handler<T>>,
+                                         req,
+                                         principal,
+                                         path,
+                                         body| async move {
+                                            handler.admin_disable(req, principal, path, body).await
+                                        },
+                                    ),
+                                )
+                                .route(
+                                    "/{id}/restore",
+                                    web::post().to(
+                                        |handler: web::Data<Handler<T>>,
+                                         req,
+                                         principal,
+                                         path,
+                                         body| async move {
+                                            handler.admin_restore(req, principal, path, body).await
+                                        },
+                                    ),
+                                ),
+                        ),
+                ),
+        ),
+    )
+    .route(
+        "/metrics",
+        web::get().to(|handler: web::Data<Handler<T>>| async move { handler.metrics().await }),
+    )
+    .route(
+        "/{id}",
+        web::get().to(
+            |handler: web::Data<Handler<T>>,
+             req: actix_web::HttpRequest,
+             path: web::Path<String>| async move { handler.redirect(req, path).await },
+        ),
+    );
+}
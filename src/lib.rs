@@ -1,4 +1,10 @@
+pub mod access_log;
+pub mod auth;
 pub mod config;
 pub mod domain;
 pub mod handler;
+pub mod memory;
+pub mod metrics;
+pub mod rate_limit;
 pub mod scylla;
+pub mod sqlite;
@@ -1,11 +1,20 @@
-use actix_web::{App, HttpServer, web};
-use std::sync::Arc;
+use actix_web::{App, HttpServer, middleware::from_fn, web};
+use std::{sync::Arc, time::Duration};
 use tracing_subscriber::fmt::time::ChronoLocal;
 use valuable::Valuable;
 use walnuk::{
-    config::{self, logger::LoggerConfig},
-    handler::handlers::Handler,
-    scylla::{self, db::DB},
+    access_log::logger::AccessLogger,
+    auth::authenticator::AdminAuth,
+    config::{self, StorageBackend, logger::LoggerConfig},
+    domain::{
+        id::generator::{self, IdGenerator},
+        repository::ShortenedURLRepository,
+    },
+    handler::{handlers::Handler, routes},
+    memory::repository::InMemoryRepository,
+    metrics::middleware::track_http,
+    rate_limit::limiter::RateLimiter,
+    scylla, sqlite,
 };
 
 fn build_logger(config: &LoggerConfig) {
@@ -17,6 +26,102 @@ fn build_logger(config: &LoggerConfig) {
     }
 }
 
+async fn wait_for_shutdown_signal() {
+    let ctrl_c = tokio::signal::ctrl_c();
+    #[cfg(unix)]
+    {
+        let mut terminate =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                .expect("Failed to install SIGTERM handler");
+        tokio::select! {
+            _ = ctrl_c => {}
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = ctrl_c.await;
+    }
+}
+
+async fn build_rate_limiter(
+    cfg: &config::Config,
+    scylla: Option<Arc<scylla::db::DB>>,
+) -> Option<RateLimiter> {
+    match RateLimiter::from_config(&cfg.rate_limit, scylla).await {
+        Ok(rate_limiter) => rate_limiter,
+        Err(err) => {
+            tracing::error!("Failed to configure rate limiting: {:?}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn serve<T>(
+    repo: T,
+    rate_limiter: Option<RateLimiter>,
+    cfg: config::Config,
+) -> std::io::Result<()>
+where
+    T: ShortenedURLRepository + Clone + Send + Sync + 'static,
+{
+    let access_logger = cfg
+        .access_log
+        .async_enabled
+        .then(|| Arc::new(AccessLogger::spawn(repo.clone(), &cfg.access_log)));
+
+    let mut handler = Handler::new(repo, cfg.handler.clone());
+    if let Some(rate_limiter) = rate_limiter {
+        handler = handler.with_rate_limiter(rate_limiter);
+    }
+    if let Some(access_logger) = &access_logger {
+        handler = handler.with_access_logger(Arc::clone(access_logger));
+    }
+    let handler = web::Data::new(handler);
+    let admin_auth = match AdminAuth::from_config(&cfg.auth) {
+        Ok(auth) => web::Data::new(auth),
+        Err(err) => {
+            tracing::error!("Failed to configure admin authentication: {:?}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let shutdown_handler = handler.clone();
+    let shutdown_delay = Duration::from_secs(cfg.handler.shutdown_delay_seconds);
+
+    // Signals are handled here instead of by actix so that readiness can flip
+    // before the listener closes.
+    let server = HttpServer::new(move || {
+        App::new()
+            .wrap(from_fn(track_http))
+            .app_data(handler.clone())
+            .app_data(admin_auth.clone())
+            .configure(routes::configure::<T>)
+    })
+    .disable_signals()
+    .bind(("0.0.0.0", cfg.handler.port))?
+    .run();
+
+    let server_handle = server.handle();
+    actix_web::rt::spawn(async move {
+        wait_for_shutdown_signal().await;
+        tracing::info!(
+            delay_seconds = shutdown_delay.as_secs(),
+            "Shutdown signal received, marking not ready"
+        );
+        shutdown_handler.mark_shutting_down();
+        tokio::time::sleep(shutdown_delay).await;
+        server_handle.stop(true).await;
+    });
+
+    let result = server.await;
+    // Workers are stopped by now, so no more events can be queued.
+    if let Some(access_logger) = access_logger {
+        access_logger.shutdown().await;
+    }
+    result
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     let cfg = match config::load() {
@@ -28,94 +133,87 @@ async fn main() -> std::io::Result<()> {
     };
     build_logger(&cfg.logger);
 
+    if cfg.storage_backend == StorageBackend::Scylla && cfg.scylla.url.is_none() {
+        tracing::error!("SCYLLA_URL must be set when STORAGE_BACKEND is scylla");
+        std::process::exit(1);
+    }
+
     tracing::debug!(config = cfg.as_value(), "Configuration loaded successfully");
-    let db = scylla::db::DB::new(cfg.scylla)
-        .await
-        .expect("Failed to connect to ScyllaDB");
-    let repo = Arc::new(db);
-    let handler = web::Data::new(Handler::new(Arc::clone(&repo)));
 
-    HttpServer::new(move || {
-        App::new()
-            .app_data(handler.clone())
-            .service(
-                web::scope("/health")
-                    .route(
-                        "/readyz",
-                        web::get().to(|handler: web::Data<Handler<Arc<DB>>>| async move {
-                            handler.readyz().await
-                        }),
-                    )
-                    .route(
-                        "/livez",
-                        web::get().to(|handler: web::Data<Handler<Arc<DB>>>| async move {
-                            handler.livez().await
-                        }),
-                    ),
-            )
-            .service(web::scope("/api").service(web::scope("/v1").route(
-                "/shorten",
-                web::post().to(
-                    |handler: web::Data<Handler<Arc<DB>>>, req: actix_web::HttpRequest, info: web::Json<walnuk::handler::handlers::ShortenParams>| async move {
-                        handler.shorten(req, info).await
-                    },
-                ),
-            )
-            .service(
-                web::scope("/admin").service(
-                    web::scope("/links")
-                        .route(
-                            "",
-                            web::get().to(
-                                |handler: web::Data<Handler<Arc<DB>>>, query| async move {
-                                    handler.admin_list_links(query).await
-                                },
-                            ),
-                        )
-                        .route(
-                            "/{id}/accesses",
-                            web::get().to(
-                                |handler: web::Data<Handler<Arc<DB>>>, path, query| async move {
-                                    handler.admin_list_access_logs(path, query).await
-                                },
-                            ),
-                        )
-                        .route(
-                            "/{id}",
-                            web::get().to(
-                                |handler: web::Data<Handler<Arc<DB>>>, path| async move {
-                                    handler.admin_get_link(path).await
-                                },
-                            ),
-                        )
-                        .route(
-                            "/{id}/disable",
-                            web::post().to(
-                                |handler: web::Data<Handler<Arc<DB>>>, path| async move {
-                                    handler.admin_disable(path).await
-                                },
-                            ),
-                        )
-                        .route(
-                            "/{id}/restore",
-                            web::post().to(
-                                |handler: web::Data<Handler<Arc<DB>>>, path| async move {
-                                    handler.admin_restore(path).await
-                                },
-                            ),
-                        ),
-                ),
-            )))
-            .route(
-                "/{id}",
-                web::get().to(
-                    |handler: web::Data<Handler<Arc<DB>>>, req: actix_web::HttpRequest, path: web::Path<String>| async move {
-                        handler.redirect(req, path).await
-                    },
-                ),
-            )
-    })
-    .bind(("0.0.0.0", cfg.handler.port))?
-    .run()
-    .await
+    if let Err(err) = IdGenerator::from_config(&cfg.id).and_then(generator::install) {
+        tracing::error!("Failed to configure ID generation: {:?}", err);
+        std::process::exit(1);
+    }
+
+    // Applies storage migrations and exits, so they can run as a one-off job
+    // before a rollout instead of racing on replica startup.
+    let migrate_only = std::env::args().skip(1).any(|arg| arg == "--migrate-only");
+    // Restores rows a failed `create` left out of the secondary tables, then exits.
+    let reconcile = std::env::args().skip(1).any(|arg| arg == "--reconcile");
+
+    match cfg.storage_backend {
+        StorageBackend::Scylla if reconcile => {
+            let db = scylla::db::DB::new(cfg.scylla.clone())
+                .await
+                .expect("Failed to connect to ScyllaDB");
+            match scylla::reconcile::reconcile_links(&db, true).await {
+                Ok(report) => {
+                    tracing::info!(
+                        scanned = report.scanned,
+                        missing_state = report.missing_state,
+                        missing_listing = report.missing_listing,
+                        stale_listing = report.stale_listing,
+                        repaired = report.repaired,
+                        "Reconciliation finished"
+                    );
+                    Ok(())
+                }
+                Err(err) => {
+                    tracing::error!("Reconciliation failed: {:?}", err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        StorageBackend::Sqlite | StorageBackend::Memory if reconcile => {
+            // Both write a link and its rows in a single transaction or lock.
+            tracing::info!("Nothing to reconcile for this storage backend");
+            Ok(())
+        }
+        StorageBackend::Scylla if migrate_only => {
+            if let Err(err) = scylla::db::DB::migrate(cfg.scylla.clone()).await {
+                tracing::error!("Failed to migrate ScyllaDB: {:?}", err);
+                std::process::exit(1);
+            }
+            tracing::info!("ScyllaDB migrations are up to date");
+            Ok(())
+        }
+        StorageBackend::Sqlite if migrate_only => {
+            // Opening the database applies pending migrations.
+            sqlite::db::DB::open(cfg.sqlite.clone()).expect("Failed to open SQLite");
+            tracing::info!("SQLite migrations are up to date");
+            Ok(())
+        }
+        StorageBackend::Memory if migrate_only => {
+            tracing::info!("The in-memory storage backend has no migrations");
+            Ok(())
+        }
+        StorageBackend::Scylla => {
+            let db = scylla::db::DB::new(cfg.scylla.clone())
+                .await
+                .expect("Failed to connect to ScyllaDB");
+            let db = Arc::new(db);
+            let rate_limiter = build_rate_limiter(&cfg, Some(Arc::clone(&db))).await;
+            serve(db, rate_limiter, cfg).await
+        }
+        StorageBackend::Sqlite => {
+            let db = sqlite::db::DB::open(cfg.sqlite.clone()).expect("Failed to open SQLite");
+            let rate_limiter = build_rate_limiter(&cfg, None).await;
+            serve(Arc::new(db), rate_limiter, cfg).await
+        }
+        StorageBackend::Memory => {
+            tracing::warn!("Using the in-memory storage backend; data is lost on restart");
+            let rate_limiter = build_rate_limiter(&cfg, None).await;
+            serve(Arc::new(InMemoryRepository::new()), rate_limiter, cfg).await
+        }
+    }
 }
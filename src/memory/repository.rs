@@ -0,0 +1,502 @@
+use crate::domain::{
+    error::RepositoryError,
+    id::{ID, generator::generator},
+    models::{
+        AccessEvent, AccessLogFilter, AuditEntry, AuditFilter, CreateOutcome, DestinationChange,
+        ShortUrlState, ShortenedURL,
+    },
+    repository::{AccessLogRow, CreateMeta, ShortenedURLRepository},
+};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+use url::Url;
+
+#[derive(Debug, Clone)]
+pub struct CreateLog {
+    pub id: String,
+    pub ts: DateTime<Utc>,
+    pub ip: String,
+    pub user_agent: String,
+    pub original_url: String,
+    pub request_id: String,
+}
+
+#[derive(Default)]
+struct Store {
+    current_id: i64,
+    urls: HashMap<String, ShortenedURL>,
+    states: HashMap<String, ShortUrlState>,
+    destination_history: HashMap<String, Vec<DestinationChange>>,
+    /// `(deleted_at, deleted_by)` of every deleted link.
+    tombstones: HashMap<String, (DateTime<Utc>, String)>,
+    audit_log: Vec<AuditEntry>,
+    create_meta: HashMap<String, CreateMeta>,
+    create_logs: Vec<CreateLog>,
+    access_logs: HashMap<String, Vec<AccessLogRow>>,
+    last_access: HashMap<String, (DateTime<Utc>, i32)>,
+}
+
+/// A process-local `ShortenedURLRepository` with the same observable semantics
+/// as the ScyllaDB implementation. Intended for tests and local development.
+#[derive(Default)]
+pub struct InMemoryRepository {
+    store: Mutex<Store>,
+    create_meta_retention: Option<chrono::Duration>,
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hides creator metadata once it is older than `retention`, like a TTL.
+    pub fn with_create_meta_retention(mut self, retention: chrono::Duration) -> Self {
+        self.create_meta_retention = Some(retention);
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Store> {
+        self.store.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn create_logs(&self) -> Vec<CreateLog> {
+        self.lock().create_logs.clone()
+    }
+}
+
+/// Paging state is the `(created_at, id)` key of the last returned row, so that
+/// a page boundary stays stable while new links are being created.
+fn encode_paging_state(url: &ShortenedURL) -> Vec<u8> {
+    format!("{}:{}", url.created_at.timestamp_micros(), url.id.0).into_bytes()
+}
+
+fn decode_paging_state(raw: &[u8]) -> Result<(DateTime<Utc>, String)> {
+    let raw = std::str::from_utf8(raw).map_err(|_| anyhow!("Invalid paging state"))?;
+    let (micros, id) = raw
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Invalid paging state"))?;
+    let created_at = micros
+        .parse::<i64>()
+        .ok()
+        .and_then(DateTime::from_timestamp_micros)
+        .ok_or_else(|| anyhow!("Invalid paging state"))?;
+    Ok((created_at, id.to_string()))
+}
+
+fn encode_access_log_paging_state((ts, position): (DateTime<Utc>, usize)) -> Vec<u8> {
+    format!("{}:{}", ts.timestamp_micros(), position).into_bytes()
+}
+
+fn decode_access_log_paging_state(raw: &[u8]) -> Result<(DateTime<Utc>, usize)> {
+    let invalid = || anyhow!("Invalid paging state");
+    let raw = std::str::from_utf8(raw).map_err(|_| invalid())?;
+    let (micros, position) = raw.split_once(':').ok_or_else(invalid)?;
+    let ts = micros
+        .parse::<i64>()
+        .ok()
+        .and_then(DateTime::from_timestamp_micros)
+        .ok_or_else(invalid)?;
+    let position = position.parse::<usize>().map_err(|_| invalid())?;
+    Ok((ts, position))
+}
+
+impl ShortenedURLRepository for Arc<InMemoryRepository> {
+    async fn ping(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn create(
+        &self,
+        original_url: Url,
+        custom_id: Option<&str>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<CreateOutcome> {
+        let mut store = self.lock();
+
+        let id = match custom_id {
+            Some(cid) => {
+                let id = ID::new(cid.to_string());
+                if store.tombstones.contains_key(&id.0) {
+                    return Err(RepositoryError::IdDeleted(id.0).into());
+                }
+                id
+            }
+            // Skips IDs that are already taken, e.g. claimed as a custom ID.
+            None => loop {
+                let id = if generator().uses_sequence() {
+                    store.current_id += 1;
+                    ID::generate(store.current_id)?
+                } else {
+                    generator().random()?
+                };
+                if !store.urls.contains_key(&id.0) && !store.tombstones.contains_key(&id.0) {
+                    break id;
+                }
+            },
+        };
+
+        if let Some(existing) = store.urls.get(&id.0) {
+            return Ok(CreateOutcome::from_existing(
+                existing.clone(),
+                &original_url,
+            )?);
+        }
+
+        let created_at = Utc::now();
+        let url = ShortenedURL {
+            id: id.clone(),
+            original_url,
+            created_at,
+            expires_at,
+        };
+        store.urls.insert(id.0.clone(), url.clone());
+        store.states.insert(
+            id.0.clone(),
+            ShortUrlState {
+                id,
+                enabled: true,
+                disabled_at: None,
+                updated_at: created_at,
+            },
+        );
+
+        Ok(CreateOutcome::Created(url))
+    }
+
+    async fn find_by_id(&self, id: ID) -> Result<Option<ShortenedURL>> {
+        Ok(self.lock().urls.get(&id.0).cloned())
+    }
+
+    async fn update_destination(
+        &self,
+        id: &str,
+        new_url: Url,
+        changed_by: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<ShortenedURL>> {
+        let id = ID::new(id.to_string());
+        let id = id.0.as_str();
+        let mut store = self.lock();
+        let Some(url) = store.urls.get_mut(id) else {
+            return Ok(None);
+        };
+        if url.original_url == new_url {
+            return Ok(Some(url.clone()));
+        }
+
+        let previous_url = std::mem::replace(&mut url.original_url, new_url.clone());
+        let url = url.clone();
+        store
+            .destination_history
+            .entry(id.to_string())
+            .or_default()
+            .push(DestinationChange {
+                changed_at: now,
+                previous_url,
+                new_url,
+                changed_by: changed_by.to_string(),
+            });
+        Ok(Some(url))
+    }
+
+    async fn delete(&self, id: &str, deleted_by: &str, now: DateTime<Utc>) -> Result<bool> {
+        let id = ID::new(id.to_string());
+        let id = id.0.as_str();
+        let mut store = self.lock();
+        if !store.urls.contains_key(id) && !store.tombstones.contains_key(id) {
+            return Ok(false);
+        }
+
+        store
+            .tombstones
+            .entry(id.to_string())
+            .or_insert_with(|| (now, deleted_by.to_string()));
+        store.urls.remove(id);
+        store.states.remove(id);
+        store.create_meta.remove(id);
+        store.create_logs.retain(|log| log.id != id);
+        store.access_logs.remove(id);
+        store.last_access.remove(id);
+        store.destination_history.remove(id);
+        for entry in store.audit_log.iter_mut().filter(|e| e.link_id == id) {
+            entry.before = None;
+            entry.after = None;
+        }
+        Ok(true)
+    }
+
+    async fn is_deleted(&self, id: &str) -> Result<bool> {
+        Ok(self
+            .lock()
+            .tombstones
+            .contains_key(&ID::new(id.to_string()).0))
+    }
+
+    async fn list_destination_history(&self, id: &str) -> Result<Vec<DestinationChange>> {
+        let store = self.lock();
+        let mut changes = store
+            .destination_history
+            .get(id)
+            .cloned()
+            .unwrap_or_default();
+        changes.reverse();
+        Ok(changes)
+    }
+
+    async fn list_by_created_at_page(
+        &self,
+        limit: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> Result<(Vec<ShortenedURL>, Option<Vec<u8>>)> {
+        let page_size = limit.clamp(1, 100) as usize;
+        let after = paging_state
+            .as_deref()
+            .map(decode_paging_state)
+            .transpose()?;
+
+        let store = self.lock();
+        let mut urls: Vec<&ShortenedURL> = store.urls.values().collect();
+        urls.sort_by(|a, b| {
+            b.created_at
+                .cmp(&a.created_at)
+                .then_with(|| a.id.0.cmp(&b.id.0))
+        });
+
+        let mut rest = urls.into_iter().filter(|u| match &after {
+            Some((created_at, id)) => {
+                u.created_at < *created_at || (u.created_at == *created_at && u.id.0 > *id)
+            }
+            None => true,
+        });
+
+        let out: Vec<ShortenedURL> = rest.by_ref().take(page_size).cloned().collect();
+        let next_page_state = match (out.last(), rest.next()) {
+            (Some(last), Some(_)) => Some(encode_paging_state(last)),
+            _ => None,
+        };
+
+        Ok((out, next_page_state))
+    }
+
+    async fn save_create_meta_if_absent(
+        &self,
+        id: &str,
+        created_at: DateTime<Utc>,
+        ip: Option<&str>,
+        user_agent: Option<&str>,
+        request_id: Option<&str>,
+    ) -> Result<()> {
+        self.lock()
+            .create_meta
+            .entry(id.to_string())
+            .or_insert_with(|| {
+                (
+                    created_at,
+                    ip.unwrap_or("").to_string(),
+                    user_agent.unwrap_or("").to_string(),
+                    request_id.unwrap_or("").to_string(),
+                )
+            });
+        Ok(())
+    }
+
+    async fn get_create_meta(&self, id: &str) -> Result<Option<CreateMeta>> {
+        let now = Utc::now();
+        Ok(self.lock().create_meta.get(id).cloned().filter(|meta| {
+            self.create_meta_retention
+                .is_none_or(|retention| meta.0 + retention > now)
+        }))
+    }
+
+    fn create_meta_retention(&self) -> Option<chrono::Duration> {
+        self.create_meta_retention
+    }
+
+    async fn get_state(&self, id: &str) -> Result<Option<ShortUrlState>> {
+        Ok(self.lock().states.get(id).cloned())
+    }
+
+    async fn set_enabled(&self, id: &str, enabled: bool, now: DateTime<Utc>) -> Result<()> {
+        let disabled_at = if enabled { None } else { Some(now) };
+        self.lock().states.insert(
+            id.to_string(),
+            ShortUrlState {
+                id: ID::new(id.to_string()),
+                enabled,
+                disabled_at,
+                updated_at: now,
+            },
+        );
+        Ok(())
+    }
+
+    async fn log_create(
+        &self,
+        id: &str,
+        ts: DateTime<Utc>,
+        ip: Option<&str>,
+        user_agent: Option<&str>,
+        original_url: &str,
+        request_id: Option<&str>,
+    ) -> Result<()> {
+        self.lock().create_logs.push(CreateLog {
+            id: id.to_string(),
+            ts,
+            ip: ip.unwrap_or("").to_string(),
+            user_agent: user_agent.unwrap_or("").to_string(),
+            original_url: original_url.to_string(),
+            request_id: request_id.unwrap_or("").to_string(),
+        });
+        Ok(())
+    }
+
+    async fn log_access(
+        &self,
+        id: &str,
+        ts: DateTime<Utc>,
+        ip: Option<&str>,
+        user_agent: Option<&str>,
+        request_id: Option<&str>,
+        status_code: i32,
+    ) -> Result<()> {
+        self.lock()
+            .access_logs
+            .entry(id.to_string())
+            .or_default()
+            .push((
+                ts,
+                ip.unwrap_or("").to_string(),
+                user_agent.unwrap_or("").to_string(),
+                request_id.unwrap_or("").to_string(),
+                status_code,
+            ));
+        Ok(())
+    }
+
+    async fn list_access_logs_page(
+        &self,
+        id: &str,
+        filter: &AccessLogFilter,
+        limit: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> Result<(Vec<AccessLogRow>, Option<Vec<u8>>)> {
+        let page_size = limit.clamp(1, 500) as usize;
+        let after = paging_state
+            .as_deref()
+            .map(decode_access_log_paging_state)
+            .transpose()?;
+
+        // Rows are keyed by `(ts, position)`, position being the insertion
+        // order, so entries sharing a timestamp still page deterministically.
+        let mut rows: Vec<((DateTime<Utc>, usize), AccessLogRow)> = self
+            .lock()
+            .access_logs
+            .get(id)
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, row)| filter.matches(row.0, row.4))
+            .map(|(position, row)| ((row.0, position), row.clone()))
+            .filter(|(key, _)| after.is_none_or(|after| *key < after))
+            .collect();
+        rows.sort_by_key(|(key, _)| std::cmp::Reverse(*key));
+
+        let has_more = rows.len() > page_size;
+        rows.truncate(page_size);
+        let next_page_state = has_more
+            .then(|| {
+                rows.last()
+                    .map(|(key, _)| encode_access_log_paging_state(*key))
+            })
+            .flatten();
+        Ok((
+            rows.into_iter().map(|(_, row)| row).collect(),
+            next_page_state,
+        ))
+    }
+
+    async fn record_audit(&self, entry: &AuditEntry) -> Result<()> {
+        self.lock().audit_log.push(entry.clone());
+        Ok(())
+    }
+
+    async fn list_audit_page(
+        &self,
+        filter: &AuditFilter,
+        limit: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> Result<(Vec<AuditEntry>, Option<Vec<u8>>)> {
+        let page_size = limit.clamp(1, 100) as usize;
+        let after = paging_state
+            .as_deref()
+            .map(decode_paging_state)
+            .transpose()?;
+
+        let store = self.lock();
+        let mut entries: Vec<&AuditEntry> = store
+            .audit_log
+            .iter()
+            .filter(|e| filter.matches(e))
+            .collect();
+        // The cursor only keeps microseconds, so order at that precision.
+        entries.sort_by(|a, b| {
+            (b.ts.timestamp_micros(), &b.id).cmp(&(a.ts.timestamp_micros(), &a.id))
+        });
+
+        let mut rest = entries.into_iter().filter(|e| match &after {
+            Some((ts, id)) => {
+                let (micros, cursor) = (e.ts.timestamp_micros(), ts.timestamp_micros());
+                micros < cursor || (micros == cursor && e.id < *id)
+            }
+            None => true,
+        });
+
+        let out: Vec<AuditEntry> = rest.by_ref().take(page_size).cloned().collect();
+        let next_page_state = match (out.last(), rest.next()) {
+            (Some(last), Some(_)) => {
+                Some(format!("{}:{}", last.ts.timestamp_micros(), last.id).into_bytes())
+            }
+            _ => None,
+        };
+
+        Ok((out, next_page_state))
+    }
+
+    async fn get_last_access(&self, id: &str) -> Result<Option<(DateTime<Utc>, i32)>> {
+        Ok(self.lock().last_access.get(id).copied())
+    }
+
+    async fn log_access_batch(&self, events: &[AccessEvent]) -> Result<()> {
+        let mut store = self.lock();
+        for event in events {
+            store
+                .access_logs
+                .entry(event.id.clone())
+                .or_default()
+                .push((
+                    event.ts,
+                    event.ip.clone().unwrap_or_default(),
+                    event.user_agent.clone().unwrap_or_default(),
+                    event.request_id.clone().unwrap_or_default(),
+                    event.status_code,
+                ));
+            if event.update_last_access {
+                store
+                    .last_access
+                    .insert(event.id.clone(), (event.ts, event.status_code));
+            }
+        }
+        Ok(())
+    }
+
+    async fn set_last_access(&self, id: &str, ts: DateTime<Utc>, status_code: i32) -> Result<()> {
+        self.lock()
+            .last_access
+            .insert(id.to_string(), (ts, status_code));
+        Ok(())
+    }
+}
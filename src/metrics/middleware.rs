@@ -0,0 +1,36 @@
+use std::time::Instant;
+
+use actix_web::{
+    Error,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+};
+
+use crate::metrics::registry::METRICS;
+
+/// Records request latency labelled by the matched route pattern rather than
+/// the raw path, so short URL IDs do not explode label cardinality.
+pub async fn track_http<B>(req: ServiceRequest, next: Next<B>) -> Result<ServiceResponse<B>, Error>
+where
+    B: MessageBody,
+{
+    let start = Instant::now();
+    let method = req.method().to_string();
+    let route = req
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let res = next.call(req).await;
+
+    let status = match &res {
+        Ok(res) => res.status().as_u16(),
+        Err(e) => e.as_response_error().status_code().as_u16(),
+    };
+    METRICS
+        .http_request_duration
+        .with_label_values(&[method.as_str(), route.as_str(), &status.to_string()])
+        .observe(start.elapsed().as_secs_f64());
+
+    res
+}
@@ -0,0 +1,133 @@
+use std::{future::Future, sync::LazyLock, time::Instant};
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+};
+
+/// Process-wide metrics, registered once on a dedicated registry.
+pub struct Metrics {
+    registry: Registry,
+    pub redirects: IntCounterVec,
+    pub creations: IntCounterVec,
+    pub redirect_cache_lookups: IntCounterVec,
+    pub access_log_dropped_events: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub scylla_statement_duration: HistogramVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("walnuk".to_string()), None).expect("metric prefix is valid");
+
+        let redirects = IntCounterVec::new(
+            Opts::new(
+                "redirects_total",
+                "Redirect requests by response status code",
+            ),
+            &["status"],
+        )
+        .expect("metric definition is valid");
+        let creations = IntCounterVec::new(
+            Opts::new("creations_total", "Short URLs created, by kind of ID"),
+            &["id_kind"],
+        )
+        .expect("metric definition is valid");
+        let redirect_cache_lookups = IntCounterVec::new(
+            Opts::new(
+                "redirect_cache_lookups_total",
+                "Redirect cache lookups by result (hit, negative_hit, miss)",
+            ),
+            &["result"],
+        )
+        .expect("metric definition is valid");
+        let access_log_dropped_events = IntCounterVec::new(
+            Opts::new(
+                "access_log_dropped_events_total",
+                "Access events that were never written, by reason",
+            ),
+            &["reason"],
+        )
+        .expect("metric definition is valid");
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency by route",
+            ),
+            &["method", "route", "status"],
+        )
+        .expect("metric definition is valid");
+        let scylla_statement_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "scylla_statement_duration_seconds",
+                "ScyllaDB statement execution time",
+            )
+            .buckets(vec![
+                0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+            ]),
+            &["statement", "outcome"],
+        )
+        .expect("metric definition is valid");
+
+        for collector in [
+            Box::new(redirects.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(creations.clone()),
+            Box::new(redirect_cache_lookups.clone()),
+            Box::new(access_log_dropped_events.clone()),
+            Box::new(http_request_duration.clone()),
+            Box::new(scylla_statement_duration.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("metrics are registered once");
+        }
+
+        Self {
+            registry,
+            redirects,
+            creations,
+            redirect_cache_lookups,
+            access_log_dropped_events,
+            http_request_duration,
+            scylla_statement_duration,
+        }
+    }
+
+    /// Renders every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut buf = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buf) {
+            tracing::warn!(error = %e, "Failed to encode metrics");
+        }
+        String::from_utf8(buf).unwrap_or_default()
+    }
+}
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub fn record_redirect(status_code: u16) {
+    METRICS
+        .redirects
+        .with_label_values(&[status_code.to_string()])
+        .inc();
+}
+
+pub fn record_creation(custom_id: bool) {
+    let kind = if custom_id { "custom" } else { "generated" };
+    METRICS.creations.with_label_values(&[kind]).inc();
+}
+
+/// Runs a ScyllaDB statement and records how long it took under `statement`.
+pub async fn time_statement<F, V, E>(statement: &'static str, fut: F) -> Result<V, E>
+where
+    F: Future<Output = Result<V, E>>,
+{
+    let start = Instant::now();
+    let result = fut.await;
+    let outcome = if result.is_ok() { "ok" } else { "error" };
+    METRICS
+        .scylla_statement_duration
+        .with_label_values(&[statement, outcome])
+        .observe(start.elapsed().as_secs_f64());
+    result
+}
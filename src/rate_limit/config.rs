@@ -0,0 +1,35 @@
+use envconfig::Envconfig;
+use strum::EnumString;
+use valuable::Valuable;
+
+#[derive(EnumString, Debug, Valuable, Clone, Copy, PartialEq, Eq)]
+#[strum(ascii_case_insensitive)]
+pub enum RateLimitBackend {
+    Memory,
+    Scylla,
+}
+
+#[derive(Envconfig, Debug, Valuable, Clone)]
+pub struct Config {
+    #[envconfig(from = "RATE_LIMIT_ENABLED", default = "true")]
+    pub enabled: bool,
+
+    #[envconfig(from = "RATE_LIMIT_BACKEND", default = "memory")]
+    pub backend: RateLimitBackend,
+
+    #[envconfig(from = "RATE_LIMIT_SHORTEN_BURST", default = "10")]
+    pub shorten_burst: u32,
+    #[envconfig(from = "RATE_LIMIT_SHORTEN_PER_MINUTE", default = "10")]
+    pub shorten_per_minute: u32,
+
+    #[envconfig(from = "RATE_LIMIT_REDIRECT_BURST", default = "120")]
+    pub redirect_burst: u32,
+    #[envconfig(from = "RATE_LIMIT_REDIRECT_PER_MINUTE", default = "600")]
+    pub redirect_per_minute: u32,
+
+    /// Comma-separated addresses or CIDR ranges of the reverse proxies whose
+    /// `X-Forwarded-For` is believed. Clients are otherwise keyed by the peer
+    /// address, since any client can send the header.
+    #[envconfig(from = "RATE_LIMIT_TRUSTED_PROXIES", default = "")]
+    pub trusted_proxies: String,
+}
@@ -0,0 +1,397 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+
+use crate::{
+    rate_limit::config::{Config, RateLimitBackend},
+    scylla::{self, rate_limit::ScyllaBuckets},
+};
+
+const LOCAL_PRUNE_THRESHOLD: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    Shorten,
+    Redirect,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Shorten => "shorten",
+            Scope::Redirect => "redirect",
+        }
+    }
+}
+
+/// A token bucket holding at most `burst` tokens, refilled at `per_minute`
+/// tokens per minute. A `burst` of zero disables limiting.
+#[derive(Debug, Clone, Copy)]
+pub struct Quota {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+impl Quota {
+    fn refill_per_second(&self) -> f64 {
+        f64::from(self.per_minute) / 60.0
+    }
+
+    /// Time after which an untouched bucket is full again, so its state can be dropped.
+    pub fn idle_expiry(&self) -> Duration {
+        let rate = self.refill_per_second();
+        if rate <= 0.0 {
+            return Duration::from_secs(24 * 60 * 60);
+        }
+        Duration::from_secs_f64(f64::from(self.burst) / rate).max(Duration::from_secs(1))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+    Allowed,
+    Limited { retry_after: Duration },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+    pub tokens: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Bucket {
+    pub fn full(quota: &Quota, now: DateTime<Utc>) -> Self {
+        Self {
+            tokens: f64::from(quota.burst),
+            updated_at: now,
+        }
+    }
+
+    /// Refills the bucket for the time elapsed since `updated_at`, then tries to take one token.
+    pub fn take(&mut self, quota: &Quota, now: DateTime<Utc>) -> Decision {
+        let rate = quota.refill_per_second();
+        let elapsed = (now - self.updated_at).as_seconds_f64().max(0.0);
+        self.tokens = (self.tokens + elapsed * rate).min(f64::from(quota.burst));
+        self.updated_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Decision::Allowed;
+        }
+
+        let retry_after = if rate > 0.0 {
+            Duration::from_secs_f64((1.0 - self.tokens) / rate)
+        } else {
+            quota.idle_expiry()
+        };
+        Decision::Limited { retry_after }
+    }
+}
+
+#[derive(Default)]
+pub struct LocalBuckets {
+    buckets: Mutex<HashMap<(Scope, String), Bucket>>,
+}
+
+impl LocalBuckets {
+    pub fn acquire(&self, scope: Scope, key: &str, quota: &Quota, now: DateTime<Utc>) -> Decision {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() >= LOCAL_PRUNE_THRESHOLD {
+            let idle = quota.idle_expiry();
+            buckets.retain(|_, b| (now - b.updated_at).to_std().unwrap_or_default() < idle);
+        }
+
+        buckets
+            .entry((scope, key.to_string()))
+            .or_insert_with(|| Bucket::full(quota, now))
+            .take(quota, now)
+    }
+}
+
+pub enum Store {
+    Local(LocalBuckets),
+    Scylla(Box<ScyllaBuckets>),
+}
+
+/// Reverse proxies allowed to report the client address in `X-Forwarded-For`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies(Vec<(IpAddr, u8)>);
+
+impl FromStr for TrustedProxies {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut ranges = Vec::new();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (addr, prefix) = match entry.split_once('/') {
+                Some((addr, prefix)) => (addr, Some(prefix)),
+                None => (entry, None),
+            };
+            let addr: IpAddr = addr
+                .parse()
+                .with_context(|| format!("Invalid trusted proxy '{}'", entry))?;
+            let max = if addr.is_ipv4() { 32 } else { 128 };
+            let prefix = match prefix {
+                Some(prefix) => prefix
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|p| *p <= max)
+                    .ok_or_else(|| anyhow!("Invalid prefix length in trusted proxy '{}'", entry))?,
+                None => max,
+            };
+            ranges.push((addr, prefix));
+        }
+        Ok(Self(ranges))
+    }
+}
+
+impl TrustedProxies {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.0.iter().any(|&(net, prefix)| match (net, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        })
+    }
+
+    /// The address to rate limit a request from `peer` by. `forwarded_for`
+    /// holds the `X-Forwarded-For` hops in order; they are walked from the
+    /// right while each hop is a trusted proxy, so that entries the client
+    /// wrote itself are never used.
+    pub fn client_ip<'a>(
+        &self,
+        peer: IpAddr,
+        forwarded_for: impl DoubleEndedIterator<Item = &'a str>,
+    ) -> IpAddr {
+        let mut client = peer;
+        if !self.contains(peer) {
+            return client;
+        }
+        for hop in forwarded_for.rev() {
+            let Ok(hop) = hop.trim().parse::<IpAddr>() else {
+                break;
+            };
+            client = hop;
+            if !self.contains(hop) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+pub struct RateLimiter {
+    store: Store,
+    shorten: Quota,
+    redirect: Quota,
+    trusted_proxies: TrustedProxies,
+}
+
+impl RateLimiter {
+    pub fn new(store: Store, shorten: Quota, redirect: Quota) -> Self {
+        Self {
+            store,
+            shorten,
+            redirect,
+            trusted_proxies: TrustedProxies::default(),
+        }
+    }
+
+    pub fn with_trusted_proxies(mut self, trusted_proxies: TrustedProxies) -> Self {
+        self.trusted_proxies = trusted_proxies;
+        self
+    }
+
+    pub fn trusted_proxies(&self) -> &TrustedProxies {
+        &self.trusted_proxies
+    }
+
+    /// Returns `None` when rate limiting is disabled. The Scylla backend needs
+    /// the ScyllaDB storage backend's connection.
+    pub async fn from_config(
+        config: &Config,
+        scylla: Option<Arc<scylla::db::DB>>,
+    ) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let trusted_proxies = config
+            .trusted_proxies
+            .parse()
+            .context("Invalid RATE_LIMIT_TRUSTED_PROXIES")?;
+
+        let store = match (config.backend, scylla) {
+            (RateLimitBackend::Memory, _) => Store::Local(LocalBuckets::default()),
+            (RateLimitBackend::Scylla, Some(db)) => {
+                Store::Scylla(Box::new(ScyllaBuckets::new(db).await?))
+            }
+            (RateLimitBackend::Scylla, None) => {
+                bail!("RATE_LIMIT_BACKEND=scylla requires STORAGE_BACKEND=scylla")
+            }
+        };
+
+        Ok(Some(
+            Self::new(
+                store,
+                Quota {
+                    burst: config.shorten_burst,
+                    per_minute: config.shorten_per_minute,
+                },
+                Quota {
+                    burst: config.redirect_burst,
+                    per_minute: config.redirect_per_minute,
+                },
+            )
+            .with_trusted_proxies(trusted_proxies),
+        ))
+    }
+
+    fn quota(&self, scope: Scope) -> &Quota {
+        match scope {
+            Scope::Shorten => &self.shorten,
+            Scope::Redirect => &self.redirect,
+        }
+    }
+
+    /// Takes a token for `key` in `scope`. Storage errors fail open so that a
+    /// rate limiter outage never takes the service down with it.
+    pub async fn check(&self, scope: Scope, key: &str) -> Decision {
+        let quota = self.quota(scope);
+        if quota.burst == 0 {
+            return Decision::Allowed;
+        }
+
+        let now = Utc::now();
+        match &self.store {
+            Store::Local(buckets) => buckets.acquire(scope, key, quota, now),
+            Store::Scylla(buckets) => buckets
+                .acquire(scope.as_str(), key, quota, now)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!(error = %e, scope = scope.as_str(), "Rate limit check failed");
+                    Decision::Allowed
+                }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_take_and_refill() {
+        let quota = Quota {
+            burst: 2,
+            per_minute: 60,
+        };
+        let now = Utc::now();
+        let mut bucket = Bucket::full(&quota, now);
+
+        assert_eq!(bucket.take(&quota, now), Decision::Allowed);
+        assert_eq!(bucket.take(&quota, now), Decision::Allowed);
+        let Decision::Limited { retry_after } = bucket.take(&quota, now) else {
+            panic!("expected the bucket to be empty");
+        };
+        assert_eq!(retry_after, Duration::from_secs(1));
+
+        let later = now + chrono::Duration::milliseconds(1500);
+        assert_eq!(bucket.take(&quota, later), Decision::Allowed);
+        assert!(matches!(
+            bucket.take(&quota, later),
+            Decision::Limited { .. }
+        ));
+
+        // Refill never exceeds the burst size.
+        let much_later = later + chrono::Duration::hours(1);
+        assert_eq!(bucket.take(&quota, much_later), Decision::Allowed);
+        assert_eq!(bucket.take(&quota, much_later), Decision::Allowed);
+        assert!(matches!(
+            bucket.take(&quota, much_later),
+            Decision::Limited { .. }
+        ));
+    }
+
+    #[test]
+    fn test_local_buckets_are_per_scope_and_key() {
+        let quota = Quota {
+            burst: 1,
+            per_minute: 1,
+        };
+        let now = Utc::now();
+        let buckets = LocalBuckets::default();
+
+        assert_eq!(
+            buckets.acquire(Scope::Shorten, "a", &quota, now),
+            Decision::Allowed
+        );
+        assert_ne!(
+            buckets.acquire(Scope::Shorten, "a", &quota, now),
+            Decision::Allowed
+        );
+        assert_eq!(
+            buckets.acquire(Scope::Shorten, "b", &quota, now),
+            Decision::Allowed
+        );
+        assert_eq!(
+            buckets.acquire(Scope::Redirect, "a", &quota, now),
+            Decision::Allowed
+        );
+    }
+
+    #[test]
+    fn test_trusted_proxies_client_ip() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let proxies: TrustedProxies = "10.0.0.0/8, 192.0.2.10, fd00::/8".parse().unwrap();
+        assert!(proxies.contains(ip("10.1.2.3")));
+        assert!(proxies.contains(ip("::ffff:10.1.2.3")));
+        assert!(proxies.contains(ip("192.0.2.10")));
+        assert!(!proxies.contains(ip("192.0.2.11")));
+        assert!(proxies.contains(ip("fd12::1")));
+
+        // An untrusted peer is the client, whatever it claims.
+        assert_eq!(
+            proxies.client_ip(ip("198.51.100.7"), ["203.0.113.1"].into_iter()),
+            ip("198.51.100.7")
+        );
+        // The rightmost hop no trusted proxy vouches for is the client; the
+        // spoofed entries left of it are ignored.
+        assert_eq!(
+            proxies.client_ip(
+                ip("10.0.0.1"),
+                ["1.1.1.1", "203.0.113.1", "192.0.2.10"].into_iter()
+            ),
+            ip("203.0.113.1")
+        );
+        assert_eq!(
+            proxies.client_ip(ip("10.0.0.1"), ["garbage", "10.0.0.2"].into_iter()),
+            ip("10.0.0.2")
+        );
+        assert_eq!(
+            proxies.client_ip(ip("10.0.0.1"), std::iter::empty()),
+            ip("10.0.0.1")
+        );
+
+        assert_eq!(
+            "".parse::<TrustedProxies>().unwrap(),
+            TrustedProxies::default()
+        );
+        assert!("10.0.0.0/33".parse::<TrustedProxies>().is_err());
+        assert!("proxy.local".parse::<TrustedProxies>().is_err());
+    }
+}
@@ -1,2 +1,9 @@
+pub mod buckets;
 pub mod config;
+pub mod consistency;
 pub mod db;
+pub mod id_lease;
+pub mod migrations;
+pub mod rate_limit;
+pub mod reconcile;
+pub mod retention;
@@ -0,0 +1,86 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+
+/// Partition of `short_urls_by_created_at` a link created at `ts` lives in.
+/// Buckets are UTC months, so their names sort the same way as their contents.
+pub fn month_bucket(ts: DateTime<Utc>) -> String {
+    ts.format("%Y-%m").to_string()
+}
+
+/// Per-link partition of the access log an event at `ts` lives in.
+pub fn day_bucket(ts: DateTime<Utc>) -> String {
+    ts.format("%Y-%m-%d").to_string()
+}
+
+/// Position in a listing that spans buckets: the bucket to read next and the
+/// ScyllaDB paging state within it, or `None` to start at its first row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListCursor {
+    pub bucket: String,
+    pub paging_state: Option<Vec<u8>>,
+}
+
+impl ListCursor {
+    pub fn start_of(bucket: String) -> Self {
+        Self {
+            bucket,
+            paging_state: None,
+        }
+    }
+
+    /// Encodes as a length-prefixed bucket name followed by the raw paging
+    /// state.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(1 + self.bucket.len());
+        out.push(self.bucket.len() as u8);
+        out.extend_from_slice(self.bucket.as_bytes());
+        if let Some(paging_state) = &self.paging_state {
+            out.extend_from_slice(paging_state);
+        }
+        out
+    }
+
+    pub fn decode(raw: &[u8]) -> Result<Self> {
+        let invalid = || anyhow!("Invalid paging state");
+        let (&len, rest) = raw.split_first().ok_or_else(invalid)?;
+        let len = len as usize;
+        if rest.len() < len {
+            return Err(invalid());
+        }
+        let (bucket, paging_state) = rest.split_at(len);
+        let bucket = std::str::from_utf8(bucket).map_err(|_| invalid())?;
+        if bucket.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            bucket: bucket.to_string(),
+            paging_state: (!paging_state.is_empty()).then(|| paging_state.to_vec()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_list_cursor_round_trip() {
+        let ts = Utc.with_ymd_and_hms(2025, 3, 31, 23, 59, 59).unwrap();
+        assert_eq!(month_bucket(ts), "2025-03");
+        assert_eq!(day_bucket(ts), "2025-03-31");
+
+        let start = ListCursor::start_of(month_bucket(ts));
+        assert_eq!(ListCursor::decode(&start.encode()).unwrap(), start);
+
+        let mid = ListCursor {
+            bucket: "2025-02".to_string(),
+            paging_state: Some(vec![0, 1, 2, 255]),
+        };
+        assert_eq!(ListCursor::decode(&mid.encode()).unwrap(), mid);
+
+        assert!(ListCursor::decode(&[]).is_err());
+        assert!(ListCursor::decode(&[7, b'2']).is_err());
+        assert!(ListCursor::decode(&[0, 1, 2]).is_err());
+    }
+}
@@ -1,10 +1,13 @@
 use envconfig::Envconfig;
 use valuable::Valuable;
 
+use crate::scylla::retention::Retention;
+
 #[derive(Envconfig, Debug, Valuable, Clone)]
 pub struct Config {
+    /// Required when `STORAGE_BACKEND` is `scylla`.
     #[envconfig(from = "SCYLLA_URL")]
-    pub url: String,
+    pub url: Option<String>,
 
     #[envconfig(from = "SCYLLA_USER", default = "cassandra")]
     pub user: String,
@@ -15,6 +18,27 @@ pub struct Config {
     #[envconfig(from = "SCYLLA_KEYSPACE", default = "walnuk")]
     pub keyspace: String,
 
+    /// When false, startup only checks that migrations were applied, e.g. by a
+    /// separate `--migrate-only` job.
+    #[envconfig(from = "SCYLLA_MIGRATE_ON_STARTUP", default = "true")]
+    pub migrate_on_startup: bool,
+
+    /// Sequence values leased per round trip to `id_seq`. Must be at least 1.
+    #[envconfig(from = "SCYLLA_ID_LEASE_SIZE", default = "100")]
+    pub id_lease_size: i64,
+
+    /// Retention is applied as a TTL when rows are written, so a change only
+    /// affects rows written afterwards.
+    #[envconfig(from = "SCYLLA_CREATE_LOG_RETENTION", default = "30d")]
+    pub create_log_retention: Retention,
+
+    #[envconfig(from = "SCYLLA_ACCESS_LOG_RETENTION", default = "30d")]
+    pub access_log_retention: Retention,
+
+    /// Creator IP, user agent and request ID shown in the admin views.
+    #[envconfig(from = "SCYLLA_CREATE_META_RETENTION", default = "forever")]
+    pub create_meta_retention: Retention,
+
     #[envconfig(from = "SCYLLA_CA_CERT_PATH")]
     pub ca_cert_path: Option<String>,
 
@@ -0,0 +1,335 @@
+use crate::{
+    domain::id::{ID, generator::generator},
+    scylla::{
+        db::{
+            DB, SHORT_URL_CREATE_META_TABLE_NAME, SHORT_URL_LAST_ACCESS_TABLE_NAME,
+            SHORT_URL_STATE_TABLE_NAME, SHORT_URL_TABLE_NAME, SHORT_URLS_BY_CREATED_AT_TABLE_NAME,
+            lwt_applied,
+        },
+        reconcile::{ReconcileReport, reconcile_links},
+    },
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use const_format::formatcp;
+use scylla::{
+    client::session::Session,
+    deserialize::row::DeserializeRow,
+    response::{PagingState, PagingStateResponse},
+    statement::{prepared::PreparedStatement, unprepared::Statement},
+};
+
+const SCAN_PAGE_SIZE: i32 = 500;
+
+const SCAN_LINK_IDS_QUERY: &str = formatcp!("SELECT id FROM {SHORT_URL_TABLE_NAME}");
+const SCAN_STATE_IDS_QUERY: &str = formatcp!("SELECT id FROM {SHORT_URL_STATE_TABLE_NAME}");
+const SCAN_LAST_ACCESS_IDS_QUERY: &str =
+    formatcp!("SELECT id FROM {SHORT_URL_LAST_ACCESS_TABLE_NAME}");
+const SCAN_CREATE_META_IDS_QUERY: &str =
+    formatcp!("SELECT id FROM {SHORT_URL_CREATE_META_TABLE_NAME}");
+const SCAN_LISTING_QUERY: &str =
+    formatcp!("SELECT bucket, created_at, id FROM {SHORT_URLS_BY_CREATED_AT_TABLE_NAME}");
+
+/// Walks a full-table query one page at a time.
+struct Scan {
+    statement: Statement,
+    paging_state: Option<PagingState>,
+}
+
+impl Scan {
+    fn new(query: &str) -> Self {
+        Self {
+            statement: Statement::new(query).with_page_size(SCAN_PAGE_SIZE),
+            paging_state: Some(PagingState::start()),
+        }
+    }
+
+    async fn next_page<R>(&mut self, session: &Session) -> Result<Option<Vec<R>>>
+    where
+        R: for<'frame> DeserializeRow<'frame, 'frame>,
+    {
+        let Some(paging_state) = self.paging_state.take() else {
+            return Ok(None);
+        };
+        let (result, paging_state_response) = session
+            .query_single_page(self.statement.clone(), &[], paging_state)
+            .await?;
+        if let PagingStateResponse::HasMorePages { state } = paging_state_response {
+            self.paging_state = Some(state);
+        }
+        let rows = result.into_rows_result()?;
+        let page = rows.rows::<R>()?.collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(page))
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CheckReport {
+    /// Links missing rows in the secondary tables.
+    pub links: ReconcileReport,
+    /// `short_url_state` rows whose link does not exist.
+    pub orphan_state: u64,
+    /// `short_urls_by_created_at` rows whose link does not exist.
+    pub orphan_listing: u64,
+    /// `short_url_last_access` rows whose link does not exist.
+    pub orphan_last_access: u64,
+    /// `short_url_create_meta` rows whose link does not exist.
+    pub orphan_create_meta: u64,
+    pub id_seq: Option<IdSeqCheck>,
+    /// Orphans deleted and `id_seq` advances; see `links.repaired` for the rest.
+    pub repaired: u64,
+}
+
+/// `id_seq` must not be behind a generated ID, or the allocator would hand
+/// out values whose IDs are already taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdSeqCheck {
+    pub current: i64,
+    pub max_generated: Option<i64>,
+}
+
+impl IdSeqCheck {
+    pub fn is_behind(&self) -> bool {
+        self.max_generated.is_some_and(|max| max > self.current)
+    }
+}
+
+impl CheckReport {
+    pub fn issues(&self) -> u64 {
+        self.links.missing_state
+            + self.links.missing_listing
+            + self.links.stale_listing
+            + self.orphan_state
+            + self.orphan_listing
+            + self.orphan_last_access
+            + self.orphan_create_meta
+            + u64::from(self.id_seq.is_some_and(|check| check.is_behind()))
+    }
+
+    /// Issues left in place are reported through the exit code, so the check
+    /// can gate a deployment or alert from a cron job.
+    pub fn exit_code(&self, repair: bool) -> i32 {
+        if self.issues() > 0 && !repair { 1 } else { 0 }
+    }
+}
+
+/// Whether `check` should repair, given its `--dry-run` and `--repair` flags.
+/// A dry run is the default; asking for both is an error.
+pub fn repair_requested(dry_run: bool, repair: bool) -> Result<bool, &'static str> {
+    if dry_run && repair {
+        return Err("--dry-run and --repair are mutually exclusive");
+    }
+    Ok(repair)
+}
+
+async fn link_exists(db: &DB, id: &str) -> Result<bool> {
+    Ok(db
+        .session
+        .execute_unpaged(&db.ps_find_url, (id,))
+        .await?
+        .into_rows_result()?
+        .rows_num()
+        > 0)
+}
+
+/// Deletes rows of a table keyed by link ID whose link does not exist.
+async fn check_orphans(
+    db: &DB,
+    table: &'static str,
+    scan_query: &str,
+    delete: &PreparedStatement,
+    repair: bool,
+    repaired: &mut u64,
+) -> Result<u64> {
+    let mut orphans = 0;
+    let mut scan = Scan::new(scan_query);
+    while let Some(page) = scan.next_page::<(String,)>(&db.session).await? {
+        for (id,) in page {
+            if link_exists(db, &id).await? {
+                continue;
+            }
+            orphans += 1;
+            tracing::warn!(id = id.as_str(), table, "Row has no link");
+            if repair {
+                db.session.execute_unpaged(delete, (id.as_str(),)).await?;
+                *repaired += 1;
+            }
+        }
+    }
+    Ok(orphans)
+}
+
+async fn check_id_seq(db: &DB, repair: bool, repaired: &mut u64) -> Result<IdSeqCheck> {
+    let mut max_generated = None;
+    let mut scan = Scan::new(SCAN_LINK_IDS_QUERY);
+    while let Some(page) = scan.next_page::<(String,)>(&db.session).await? {
+        for (id,) in page {
+            if let Some(seq) = ID::new(id).generated_seq() {
+                max_generated = max_generated.max(Some(seq));
+            }
+        }
+    }
+
+    let (current,) = db
+        .session
+        .execute_unpaged(&db.ps_get_current_id, &[])
+        .await?
+        .into_rows_result()?
+        .first_row::<(i64,)>()?;
+    let check = IdSeqCheck {
+        current,
+        max_generated,
+    };
+
+    if let Some(max) = max_generated.filter(|_| check.is_behind()) {
+        tracing::warn!(
+            current,
+            max_generated = max,
+            "id_seq is behind a generated ID"
+        );
+        if repair {
+            // Conditional on the value just read, so a concurrent lease wins.
+            let result = db
+                .session
+                .execute_unpaged(&db.ps_get_next_id, (max, current))
+                .await?;
+            if lwt_applied(result)? {
+                *repaired += 1;
+            }
+        }
+    }
+    Ok(check)
+}
+
+/// Cross-checks the link tables. With `repair` unset nothing is written.
+///
+/// Custom IDs accepted under the `reserve` policy can decode to sequence
+/// values ahead of `id_seq`; advancing it past them only leaves a gap.
+pub async fn check(db: &DB, repair: bool) -> Result<CheckReport> {
+    let mut report = CheckReport {
+        links: reconcile_links(db, repair).await?,
+        ..Default::default()
+    };
+    let repaired = &mut report.repaired;
+
+    report.orphan_state = check_orphans(
+        db,
+        SHORT_URL_STATE_TABLE_NAME,
+        SCAN_STATE_IDS_QUERY,
+        &db.ps_delete_state,
+        repair,
+        repaired,
+    )
+    .await?;
+    report.orphan_last_access = check_orphans(
+        db,
+        SHORT_URL_LAST_ACCESS_TABLE_NAME,
+        SCAN_LAST_ACCESS_IDS_QUERY,
+        &db.ps_delete_last_access,
+        repair,
+        repaired,
+    )
+    .await?;
+    report.orphan_create_meta = check_orphans(
+        db,
+        SHORT_URL_CREATE_META_TABLE_NAME,
+        SCAN_CREATE_META_IDS_QUERY,
+        &db.ps_delete_create_meta,
+        repair,
+        repaired,
+    )
+    .await?;
+
+    let mut scan = Scan::new(SCAN_LISTING_QUERY);
+    while let Some(page) = scan
+        .next_page::<(String, DateTime<Utc>, String)>(&db.session)
+        .await?
+    {
+        for (bucket, created_at, id) in page {
+            if link_exists(db, &id).await? {
+                continue;
+            }
+            report.orphan_listing += 1;
+            tracing::warn!(
+                id = id.as_str(),
+                table = SHORT_URLS_BY_CREATED_AT_TABLE_NAME,
+                "Row has no link"
+            );
+            if repair {
+                db.session
+                    .execute_unpaged(&db.ps_delete_url_by_created_at, (bucket, created_at, id))
+                    .await?;
+                report.repaired += 1;
+            }
+        }
+    }
+
+    if generator().uses_sequence() {
+        report.id_seq = Some(check_id_seq(db, repair, &mut report.repaired).await?);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_seq_is_behind() {
+        let check = |current, max_generated| IdSeqCheck {
+            current,
+            max_generated,
+        };
+        assert!(!check(10, None).is_behind());
+        assert!(!check(10, Some(9)).is_behind());
+        assert!(!check(10, Some(10)).is_behind());
+        assert!(check(10, Some(11)).is_behind());
+    }
+
+    #[test]
+    fn test_check_report_issues() {
+        let clean = CheckReport {
+            links: ReconcileReport {
+                scanned: 3,
+                ..Default::default()
+            },
+            id_seq: Some(IdSeqCheck {
+                current: 10,
+                max_generated: Some(10),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(clean.issues(), 0);
+        assert_eq!(clean.exit_code(false), 0);
+
+        let report = CheckReport {
+            links: ReconcileReport {
+                missing_state: 1,
+                missing_listing: 2,
+                stale_listing: 3,
+                ..Default::default()
+            },
+            orphan_state: 4,
+            orphan_listing: 5,
+            orphan_last_access: 6,
+            orphan_create_meta: 7,
+            id_seq: Some(IdSeqCheck {
+                current: 10,
+                max_generated: Some(11),
+            }),
+            repaired: 0,
+        };
+        assert_eq!(report.issues(), 29);
+        assert_eq!(report.exit_code(false), 1);
+        assert_eq!(report.exit_code(true), 0);
+    }
+
+    #[test]
+    fn test_repair_requested() {
+        assert_eq!(repair_requested(false, false), Ok(false));
+        assert_eq!(repair_requested(true, false), Ok(false));
+        assert_eq!(repair_requested(false, true), Ok(true));
+        assert!(repair_requested(true, true).is_err());
+    }
+}
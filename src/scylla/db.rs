@@ -1,12 +1,23 @@
 use crate::{
     domain::{
-        id::ID,
-        models::{ShortUrlState, ShortenedURL},
-        repository::ShortenedURLRepository,
+        error::RepositoryError,
+        id::{ID, generator::generator},
+        models::{
+            AccessEvent, AccessLogFilter, AuditAction, AuditEntry, AuditFilter, CreateOutcome,
+            DestinationChange, ShortUrlState, ShortenedURL,
+        },
+        repository::{AccessLogRow, ShortenedURLRepository},
+    },
+    metrics::registry::time_statement,
+    scylla::{
+        buckets::{ListCursor, day_bucket, month_bucket},
+        config::Config,
+        id_lease::{IdAllocator, IdRangeSource},
+        migrations::{self, SHORT_URL_ACCESS_LOGS_TABLE_NAME},
+        retention::{self, Retention},
     },
-    scylla::config::Config,
 };
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
 use backon::ExponentialBuilder;
 use backon::Retryable;
 use chrono::DateTime;
@@ -17,9 +28,22 @@ use rustls::{
     pki_types::{CertificateDer, PrivateKeyDer},
 };
 use scylla::client::{Compression, session::Session};
+use scylla::errors::ExecutionError;
+use scylla::serialize::{batch::BatchValues, row::SerializeRow};
+use scylla::statement::batch::{Batch, BatchType};
 use scylla::{client::session_builder::SessionBuilder, statement::prepared::PreparedStatement};
-use scylla::{response::PagingState, statement::unprepared::Statement};
-use std::{fs::File, path::Path, time::Duration};
+use scylla::{response::query_result::QueryResult, value::Row};
+use scylla::{
+    response::{PagingState, PagingStateResponse},
+    statement::unprepared::Statement,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    ops::Range,
+    path::Path,
+    time::Duration,
+};
 use std::{io::BufReader, sync::Arc};
 use url::Url;
 
@@ -66,18 +90,16 @@ fn create_tls_config(config: &Config) -> Result<Option<Arc<ClientConfig>>> {
     Ok(Some(Arc::new(client_config)))
 }
 
-const SHORT_URL_TABLE_NAME: &str = "short_urls";
-const CREATE_SHORT_URL_TABLE_QUERY: &str = formatcp!(
-    r#"
-    CREATE TABLE IF NOT EXISTS {SHORT_URL_TABLE_NAME} (
-        id text,
-        original_url text,
-        created_at timestamp,
-        expires_at timestamp,
-        PRIMARY KEY (id)
-    )
-"#,
-);
+/// Returns the `[applied]` column of a lightweight transaction result.
+pub(crate) fn lwt_applied(result: QueryResult) -> Result<bool> {
+    let row = result.into_rows_result()?.maybe_first_row::<Row>()?;
+    Ok(row
+        .and_then(|row| row.columns.into_iter().next().flatten())
+        .and_then(|value| value.as_boolean())
+        .unwrap_or(false))
+}
+
+pub(crate) const SHORT_URL_TABLE_NAME: &str = "short_urls";
 const INSERT_URL_QUERY: &str = formatcp!(
     r#"
     INSERT INTO {SHORT_URL_TABLE_NAME} (id, original_url, created_at, expires_at)
@@ -89,31 +111,36 @@ const FIND_URL_QUERY: &str = formatcp!(
     SELECT original_url, created_at, expires_at FROM {SHORT_URL_TABLE_NAME} WHERE id = ?
 "#,
 );
-
-const LIST_ALL_URLS_QUERY: &str = formatcp!(
+const DELETE_URL_QUERY: &str = formatcp!(
     r#"
-    SELECT id, original_url, created_at, expires_at FROM {SHORT_URL_TABLE_NAME}
+    DELETE FROM {SHORT_URL_TABLE_NAME} WHERE id = ?
 "#,
 );
-
-const SHORT_URLS_BY_CREATED_AT_TABLE_NAME: &str = "short_urls_by_created_at";
-const SHORT_URLS_BY_CREATED_AT_BUCKET: &str = "all";
-const CREATE_SHORT_URLS_BY_CREATED_AT_TABLE_QUERY: &str = formatcp!(
+/// Conditional on the destination read before, so that concurrent edits each
+/// record the destination they actually replaced.
+const UPDATE_URL_DESTINATION_QUERY: &str = formatcp!(
     r#"
-    CREATE TABLE IF NOT EXISTS {SHORT_URLS_BY_CREATED_AT_TABLE_NAME} (
-        bucket text,
-        created_at timestamp,
-        id text,
-        original_url text,
-        expires_at timestamp,
-        PRIMARY KEY (bucket, created_at, id)
-    ) WITH CLUSTERING ORDER BY (created_at DESC, id ASC)
-"#
+    UPDATE {SHORT_URL_TABLE_NAME} SET original_url = ? WHERE id = ? IF original_url = ?
+"#,
 );
+
+pub(crate) const SHORT_URLS_BY_CREATED_AT_TABLE_NAME: &str = "short_urls_by_created_at";
 const INSERT_URL_BY_CREATED_AT_QUERY: &str = formatcp!(
     r#"
     INSERT INTO {SHORT_URLS_BY_CREATED_AT_TABLE_NAME} (bucket, created_at, id, original_url, expires_at)
-    VALUES (?, ?, ?, ?, ?) IF NOT EXISTS
+    VALUES (?, ?, ?, ?, ?)
+"#
+);
+/// Conditional, so that it cannot bring back the row of a deleted link.
+const UPDATE_URL_BY_CREATED_AT_DESTINATION_QUERY: &str = formatcp!(
+    r#"
+    UPDATE {SHORT_URLS_BY_CREATED_AT_TABLE_NAME} SET original_url = ?
+    WHERE bucket = ? AND created_at = ? AND id = ? IF EXISTS
+"#
+);
+const DELETE_URL_BY_CREATED_AT_QUERY: &str = formatcp!(
+    r#"
+    DELETE FROM {SHORT_URLS_BY_CREATED_AT_TABLE_NAME} WHERE bucket = ? AND created_at = ? AND id = ?
 "#
 );
 const LIST_BY_CREATED_AT_QUERY: &str = formatcp!(
@@ -121,24 +148,29 @@ const LIST_BY_CREATED_AT_QUERY: &str = formatcp!(
     SELECT created_at, id, original_url, expires_at FROM {SHORT_URLS_BY_CREATED_AT_TABLE_NAME} WHERE bucket = ?
 "#
 );
-const CHECK_BY_CREATED_AT_ANY_QUERY: &str = formatcp!(
+
+/// Every bucket of a bucketed table that has rows, newest first. `name`
+/// tells the registries apart.
+pub(crate) const SHORT_URL_CREATED_AT_BUCKETS_TABLE_NAME: &str = "short_url_created_at_buckets";
+pub(crate) const SHORT_URL_CREATED_AT_BUCKETS_KEY: &str = "short_urls";
+const INSERT_BUCKET_QUERY: &str = formatcp!(
     r#"
-    SELECT id FROM {SHORT_URLS_BY_CREATED_AT_TABLE_NAME} WHERE bucket = ? LIMIT 1
+    INSERT INTO {SHORT_URL_CREATED_AT_BUCKETS_TABLE_NAME} (name, bucket) VALUES (?, ?)
 "#
 );
-
-const SHORT_URL_STATE_TABLE_NAME: &str = "short_url_state";
-const CREATE_SHORT_URL_STATE_TABLE_QUERY: &str = formatcp!(
+const FIRST_BUCKET_QUERY: &str = formatcp!(
     r#"
-    CREATE TABLE IF NOT EXISTS {SHORT_URL_STATE_TABLE_NAME} (
-        id text,
-        enabled boolean,
-        disabled_at timestamp,
-        updated_at timestamp,
-        PRIMARY KEY (id)
-    )
+    SELECT bucket FROM {SHORT_URL_CREATED_AT_BUCKETS_TABLE_NAME} WHERE name = ? LIMIT 1
 "#
 );
+const NEXT_BUCKET_QUERY: &str = formatcp!(
+    r#"
+    SELECT bucket FROM {SHORT_URL_CREATED_AT_BUCKETS_TABLE_NAME}
+    WHERE name = ? AND bucket < ? LIMIT 1
+"#
+);
+
+pub(crate) const SHORT_URL_STATE_TABLE_NAME: &str = "short_url_state";
 const UPSERT_SHORT_URL_STATE_QUERY: &str = formatcp!(
     r#"
     INSERT INTO {SHORT_URL_STATE_TABLE_NAME} (id, enabled, disabled_at, updated_at)
@@ -150,18 +182,13 @@ const GET_SHORT_URL_STATE_QUERY: &str = formatcp!(
     SELECT enabled, disabled_at, updated_at FROM {SHORT_URL_STATE_TABLE_NAME} WHERE id = ?
 "#
 );
-
-const SHORT_URL_LAST_ACCESS_TABLE_NAME: &str = "short_url_last_access";
-const CREATE_SHORT_URL_LAST_ACCESS_TABLE_QUERY: &str = formatcp!(
+const DELETE_SHORT_URL_STATE_QUERY: &str = formatcp!(
     r#"
-    CREATE TABLE IF NOT EXISTS {SHORT_URL_LAST_ACCESS_TABLE_NAME} (
-        id text,
-        last_access_at timestamp,
-        last_status_code int,
-        PRIMARY KEY (id)
-    )
+    DELETE FROM {SHORT_URL_STATE_TABLE_NAME} WHERE id = ?
 "#
 );
+
+pub(crate) const SHORT_URL_LAST_ACCESS_TABLE_NAME: &str = "short_url_last_access";
 const UPSERT_SHORT_URL_LAST_ACCESS_QUERY: &str = formatcp!(
     r#"
     INSERT INTO {SHORT_URL_LAST_ACCESS_TABLE_NAME} (id, last_access_at, last_status_code)
@@ -173,68 +200,107 @@ const GET_SHORT_URL_LAST_ACCESS_QUERY: &str = formatcp!(
     SELECT last_access_at, last_status_code FROM {SHORT_URL_LAST_ACCESS_TABLE_NAME} WHERE id = ?
 "#
 );
-
-const SHORT_URL_CREATE_LOGS_TABLE_NAME: &str = "short_url_create_logs";
-const CREATE_SHORT_URL_CREATE_LOGS_TABLE_QUERY: &str = formatcp!(
+const DELETE_SHORT_URL_LAST_ACCESS_QUERY: &str = formatcp!(
     r#"
-    CREATE TABLE IF NOT EXISTS {SHORT_URL_CREATE_LOGS_TABLE_NAME} (
-        id text,
-        ts timestamp,
-        ip text,
-        user_agent text,
-        original_url text,
-        request_id text,
-        PRIMARY KEY (id, ts)
-    ) WITH CLUSTERING ORDER BY (ts DESC)
+    DELETE FROM {SHORT_URL_LAST_ACCESS_TABLE_NAME} WHERE id = ?
 "#
 );
+
+pub(crate) const SHORT_URL_CREATE_LOGS_TABLE_NAME: &str = "short_url_create_logs";
 const INSERT_CREATE_LOG_QUERY: &str = formatcp!(
     r#"
     INSERT INTO {SHORT_URL_CREATE_LOGS_TABLE_NAME} (id, ts, ip, user_agent, original_url, request_id)
     VALUES (?, ?, ?, ?, ?, ?) USING TTL ?
 "#
 );
-
-const SHORT_URL_ACCESS_LOGS_TABLE_NAME: &str = "short_url_access_logs";
-const CREATE_SHORT_URL_ACCESS_LOGS_TABLE_QUERY: &str = formatcp!(
+const DELETE_CREATE_LOGS_QUERY: &str = formatcp!(
     r#"
-    CREATE TABLE IF NOT EXISTS {SHORT_URL_ACCESS_LOGS_TABLE_NAME} (
-        id text,
-        ts timestamp,
-        ip text,
-        user_agent text,
-        request_id text,
-        status_code int,
-        PRIMARY KEY (id, ts)
-    ) WITH CLUSTERING ORDER BY (ts DESC)
+    DELETE FROM {SHORT_URL_CREATE_LOGS_TABLE_NAME} WHERE id = ?
 "#
 );
+
+/// Partitioned by `(id, day)` so a busy link spreads over many partitions.
+pub(crate) const SHORT_URL_ACCESS_LOGS_BY_DAY_TABLE_NAME: &str = "short_url_access_logs_by_day";
 const INSERT_ACCESS_LOG_QUERY: &str = formatcp!(
     r#"
-    INSERT INTO {SHORT_URL_ACCESS_LOGS_TABLE_NAME} (id, ts, ip, user_agent, request_id, status_code)
-    VALUES (?, ?, ?, ?, ?, ?) USING TTL ?
+    INSERT INTO {SHORT_URL_ACCESS_LOGS_BY_DAY_TABLE_NAME} (id, day, ts, ip, user_agent, request_id, status_code)
+    VALUES (?, ?, ?, ?, ?, ?, ?) USING TTL ?
 "#
 );
-
 const LIST_ACCESS_LOGS_QUERY: &str = formatcp!(
     r#"
-    SELECT ts, ip, user_agent, request_id, status_code FROM {SHORT_URL_ACCESS_LOGS_TABLE_NAME} WHERE id = ?
+    SELECT ts, ip, user_agent, request_id, status_code FROM {SHORT_URL_ACCESS_LOGS_BY_DAY_TABLE_NAME}
+    WHERE id = ? AND day = ? AND ts >= ? AND ts < ?
+"#
+);
+const DELETE_ACCESS_LOGS_DAY_QUERY: &str = formatcp!(
+    r#"
+    DELETE FROM {SHORT_URL_ACCESS_LOGS_BY_DAY_TABLE_NAME} WHERE id = ? AND day = ?
+"#
+);
+/// Rows written before migration 5 that have not expired yet.
+const DELETE_LEGACY_ACCESS_LOGS_QUERY: &str = formatcp!(
+    r#"
+    DELETE FROM {SHORT_URL_ACCESS_LOGS_TABLE_NAME} WHERE id = ?
+"#
+);
+
+/// The days each link has access log entries for, newest first.
+pub(crate) const SHORT_URL_ACCESS_LOG_DAYS_TABLE_NAME: &str = "short_url_access_log_days";
+const INSERT_ACCESS_LOG_DAY_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {SHORT_URL_ACCESS_LOG_DAYS_TABLE_NAME} (id, day) VALUES (?, ?) USING TTL ?
+"#
+);
+const FIRST_ACCESS_LOG_DAY_QUERY: &str = formatcp!(
+    r#"
+    SELECT day FROM {SHORT_URL_ACCESS_LOG_DAYS_TABLE_NAME}
+    WHERE id = ? AND day <= ? AND day >= ? LIMIT 1
+"#
+);
+const NEXT_ACCESS_LOG_DAY_QUERY: &str = formatcp!(
+    r#"
+    SELECT day FROM {SHORT_URL_ACCESS_LOG_DAYS_TABLE_NAME}
+    WHERE id = ? AND day < ? AND day >= ? LIMIT 1
 "#
 );
+const LIST_ACCESS_LOG_DAYS_QUERY: &str = formatcp!(
+    r#"
+    SELECT day FROM {SHORT_URL_ACCESS_LOG_DAYS_TABLE_NAME} WHERE id = ?
+"#
+);
+const DELETE_ACCESS_LOG_DAYS_QUERY: &str = formatcp!(
+    r#"
+    DELETE FROM {SHORT_URL_ACCESS_LOG_DAYS_TABLE_NAME} WHERE id = ?
+"#
+);
+/// Day bounds used when an access log filter leaves a side open.
+const MIN_DAY: &str = "";
+const MAX_DAY: &str = "9999-12-31";
 
-const SHORT_URL_CREATE_META_TABLE_NAME: &str = "short_url_create_meta";
-const CREATE_SHORT_URL_CREATE_META_TABLE_QUERY: &str = formatcp!(
+pub(crate) const SHORT_URL_DESTINATION_HISTORY_TABLE_NAME: &str = "short_url_destination_history";
+const INSERT_DESTINATION_CHANGE_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {SHORT_URL_DESTINATION_HISTORY_TABLE_NAME} (id, changed_at, previous_url, new_url, changed_by)
+    VALUES (?, ?, ?, ?, ?)
+"#
+);
+const LIST_DESTINATION_HISTORY_QUERY: &str = formatcp!(
     r#"
-    CREATE TABLE IF NOT EXISTS {SHORT_URL_CREATE_META_TABLE_NAME} (
-        id text,
-        created_at timestamp,
-        ip text,
-        user_agent text,
-        request_id text,
-        PRIMARY KEY (id)
-    )
+    SELECT changed_at, previous_url, new_url, changed_by FROM {SHORT_URL_DESTINATION_HISTORY_TABLE_NAME}
+    WHERE id = ?
 "#
 );
+const DELETE_DESTINATION_HISTORY_QUERY: &str = formatcp!(
+    r#"
+    DELETE FROM {SHORT_URL_DESTINATION_HISTORY_TABLE_NAME} WHERE id = ?
+"#
+);
+
+/// Attempts at an edit whose destination keeps changing underneath it.
+const MAX_DESTINATION_UPDATE_ATTEMPTS: usize = 5;
+
+pub(crate) const SHORT_URL_CREATE_META_TABLE_NAME: &str = "short_url_create_meta";
 const INSERT_CREATE_META_IF_ABSENT_QUERY: &str = formatcp!(
     r#"
     INSERT INTO {SHORT_URL_CREATE_META_TABLE_NAME} (id, created_at, ip, user_agent, request_id)
@@ -246,20 +312,103 @@ const GET_CREATE_META_QUERY: &str = formatcp!(
     SELECT created_at, ip, user_agent, request_id FROM {SHORT_URL_CREATE_META_TABLE_NAME} WHERE id = ?
 "#
 );
+const DELETE_CREATE_META_QUERY: &str = formatcp!(
+    r#"
+    DELETE FROM {SHORT_URL_CREATE_META_TABLE_NAME} WHERE id = ?
+"#
+);
 
-const LOG_TTL_SECONDS_30D: i32 = 60 * 60 * 24 * 30;
+/// IDs of deleted links, which are never handed out again.
+pub(crate) const SHORT_URL_TOMBSTONES_TABLE_NAME: &str = "short_url_tombstones";
+const INSERT_TOMBSTONE_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {SHORT_URL_TOMBSTONES_TABLE_NAME} (id, deleted_at, deleted_by)
+    VALUES (?, ?, ?) IF NOT EXISTS
+"#
+);
+const FIND_TOMBSTONE_QUERY: &str = formatcp!(
+    r#"
+    SELECT id FROM {SHORT_URL_TOMBSTONES_TABLE_NAME} WHERE id = ?
+"#
+);
 
-const ID_SEQ_TABLE_NAME: &str = "id_seq";
-const ID_SEQ_KEY_NAME: &str = "short_url_id";
-const CREATE_ID_SEQ_TABLE_QUERY: &str = formatcp!(
+/// Admin audit trail, bucketed by month. The `_by_link` and `_by_actor`
+/// copies serve the filtered listings.
+pub(crate) const ADMIN_AUDIT_LOG_TABLE_NAME: &str = "admin_audit_log";
+pub(crate) const ADMIN_AUDIT_LOG_BY_LINK_TABLE_NAME: &str = "admin_audit_log_by_link";
+pub(crate) const ADMIN_AUDIT_LOG_BY_ACTOR_TABLE_NAME: &str = "admin_audit_log_by_actor";
+const ADMIN_AUDIT_BUCKETS_KEY: &str = "admin_audit";
+const AUDIT_COLUMNS: &str =
+    "ts, entry_id, actor, action, link_id, reason, request_id, before_state, after_state";
+const INSERT_AUDIT_ENTRY_QUERY: &str = formatcp!(
     r#"
-    CREATE TABLE IF NOT EXISTS {ID_SEQ_TABLE_NAME} (
-        name text,
-        current_id bigint,
-        PRIMARY KEY (name)
-    )
-"#,
+    INSERT INTO {ADMIN_AUDIT_LOG_TABLE_NAME} (bucket, {AUDIT_COLUMNS})
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+"#
+);
+const INSERT_AUDIT_ENTRY_BY_LINK_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {ADMIN_AUDIT_LOG_BY_LINK_TABLE_NAME} (bucket, {AUDIT_COLUMNS})
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+"#
+);
+const INSERT_AUDIT_ENTRY_BY_ACTOR_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {ADMIN_AUDIT_LOG_BY_ACTOR_TABLE_NAME} (bucket, {AUDIT_COLUMNS})
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+"#
+);
+const LIST_AUDIT_QUERY: &str = formatcp!(
+    r#"
+    SELECT {AUDIT_COLUMNS} FROM {ADMIN_AUDIT_LOG_TABLE_NAME} WHERE bucket = ?
+"#
+);
+const LIST_AUDIT_BY_LINK_QUERY: &str = formatcp!(
+    r#"
+    SELECT {AUDIT_COLUMNS} FROM {ADMIN_AUDIT_LOG_BY_LINK_TABLE_NAME}
+    WHERE link_id = ? AND bucket = ?
+"#
+);
+const LIST_AUDIT_BY_ACTOR_QUERY: &str = formatcp!(
+    r#"
+    SELECT {AUDIT_COLUMNS} FROM {ADMIN_AUDIT_LOG_BY_ACTOR_TABLE_NAME}
+    WHERE actor = ? AND bucket = ?
+"#
+);
+const LIST_AUDIT_KEYS_BY_LINK_QUERY: &str = formatcp!(
+    r#"
+    SELECT ts, entry_id, actor FROM {ADMIN_AUDIT_LOG_BY_LINK_TABLE_NAME}
+    WHERE link_id = ? AND bucket = ?
+"#
+);
+const REDACT_AUDIT_ENTRY_QUERY: &str = formatcp!(
+    r#"
+    DELETE before_state, after_state FROM {ADMIN_AUDIT_LOG_TABLE_NAME}
+    WHERE bucket = ? AND ts = ? AND entry_id = ?
+"#
+);
+const REDACT_AUDIT_ENTRY_BY_LINK_QUERY: &str = formatcp!(
+    r#"
+    DELETE before_state, after_state FROM {ADMIN_AUDIT_LOG_BY_LINK_TABLE_NAME}
+    WHERE link_id = ? AND bucket = ? AND ts = ? AND entry_id = ?
+"#
 );
+const REDACT_AUDIT_ENTRY_BY_ACTOR_QUERY: &str = formatcp!(
+    r#"
+    DELETE before_state, after_state FROM {ADMIN_AUDIT_LOG_BY_ACTOR_TABLE_NAME}
+    WHERE actor = ? AND bucket = ? AND ts = ? AND entry_id = ?
+"#
+);
+
+/// Rows a filtered listing reads before returning a short page, so that a
+/// rare match does not scan a whole history in one request.
+const MAX_SCANNED_ROWS_PER_PAGE: usize = 5_000;
+
+/// Upper bound on taken sequence values skipped by a single `create`.
+const MAX_GENERATED_ID_SKIPS: usize = 16;
+
+pub(crate) const ID_SEQ_TABLE_NAME: &str = "id_seq";
+pub(crate) const ID_SEQ_KEY_NAME: &str = "short_url_id";
 
 const GET_CURRENT_ID_QUERY: &str = formatcp!(
     r#"
@@ -276,28 +425,70 @@ pub struct DB {
     pub session: Session,
     pub ps_insert_url: PreparedStatement,
     pub ps_find_url: PreparedStatement,
-    pub ps_list_all_urls: PreparedStatement,
+    pub ps_delete_url: PreparedStatement,
+    pub ps_update_url_destination: PreparedStatement,
+    pub ps_insert_destination_change: PreparedStatement,
+    pub ps_list_destination_history: PreparedStatement,
+    pub ps_delete_destination_history: PreparedStatement,
     pub ps_insert_url_by_created_at: PreparedStatement,
+    pub ps_update_url_by_created_at_destination: PreparedStatement,
+    pub ps_delete_url_by_created_at: PreparedStatement,
     pub ps_list_by_created_at: PreparedStatement,
+    pub ps_insert_bucket: PreparedStatement,
+    pub ps_first_bucket: PreparedStatement,
+    pub ps_next_bucket: PreparedStatement,
+    /// The last bucket this instance registered, to skip redundant writes.
+    registered_bucket: std::sync::Mutex<Option<String>>,
     pub ps_get_current_id: PreparedStatement,
     pub ps_get_next_id: PreparedStatement,
+    id_allocator: IdAllocator,
+    create_log_retention: Retention,
+    access_log_retention: Retention,
+    create_meta_retention: Retention,
+    /// When migration 9 stopped creator metadata from expiring after the
+    /// legacy TTL.
+    create_meta_cutover: Option<DateTime<Utc>>,
 
     pub ps_upsert_state: PreparedStatement,
     pub ps_get_state: PreparedStatement,
+    pub ps_delete_state: PreparedStatement,
 
     pub ps_upsert_last_access: PreparedStatement,
     pub ps_get_last_access: PreparedStatement,
+    pub ps_delete_last_access: PreparedStatement,
 
     pub ps_insert_create_log: PreparedStatement,
+    pub ps_delete_create_logs: PreparedStatement,
     pub ps_insert_access_log: PreparedStatement,
     pub ps_list_access_logs: PreparedStatement,
+    pub ps_delete_access_logs_day: PreparedStatement,
+    pub ps_delete_legacy_access_logs: PreparedStatement,
+    pub ps_insert_access_log_day: PreparedStatement,
+    pub ps_first_access_log_day: PreparedStatement,
+    pub ps_next_access_log_day: PreparedStatement,
+    pub ps_list_access_log_days: PreparedStatement,
+    pub ps_delete_access_log_days: PreparedStatement,
 
     pub ps_insert_create_meta_if_absent: PreparedStatement,
     pub ps_get_create_meta: PreparedStatement,
+    pub ps_delete_create_meta: PreparedStatement,
+    pub ps_insert_tombstone: PreparedStatement,
+    pub ps_find_tombstone: PreparedStatement,
+
+    pub ps_insert_audit_entry: PreparedStatement,
+    pub ps_insert_audit_entry_by_link: PreparedStatement,
+    pub ps_insert_audit_entry_by_actor: PreparedStatement,
+    pub ps_list_audit: PreparedStatement,
+    pub ps_list_audit_by_link: PreparedStatement,
+    pub ps_list_audit_by_actor: PreparedStatement,
+    pub ps_list_audit_keys_by_link: PreparedStatement,
+    pub ps_redact_audit_entry: PreparedStatement,
+    pub ps_redact_audit_entry_by_link: PreparedStatement,
+    pub ps_redact_audit_entry_by_actor: PreparedStatement,
 }
 
 impl DB {
-    async fn prepare_statement(
+    pub(crate) async fn prepare_statement(
         session: &Session,
         statement: Statement,
     ) -> Result<PreparedStatement> {
@@ -307,12 +498,16 @@ impl DB {
             .map_err(|e| anyhow!("Failed to prepare statement {}: {}", statement.contents, e))
     }
 
-    pub async fn new(config: Config) -> Result<Self> {
-        let tls_context = create_tls_config(&config)?;
+    async fn connect(config: &Config) -> Result<Session> {
+        let url = config
+            .url
+            .as_deref()
+            .ok_or_else(|| anyhow!("SCYLLA_URL is not set"))?;
+        let tls_context = create_tls_config(config)?;
 
         let session = SessionBuilder::new()
-            .known_node(config.url)
-            .user(config.user, config.password)
+            .known_node(url)
+            .user(&config.user, &config.password)
             .tls_context(tls_context)
             .compression(Some(Compression::Lz4))
             .build()
@@ -323,110 +518,66 @@ impl DB {
             .await
             .map_err(|e| anyhow!("Failed to use keyspace '{}': {}", &config.keyspace, e))?;
 
-        session
-            .query_unpaged(CREATE_SHORT_URL_TABLE_QUERY, &[])
-            .await
-            .map_err(|e| anyhow!("Failed to create table '{}': {}", SHORT_URL_TABLE_NAME, e))?;
-
-        session
-            .query_unpaged(CREATE_SHORT_URL_STATE_TABLE_QUERY, &[])
-            .await
-            .map_err(|e| {
-                anyhow!(
-                    "Failed to create table '{}': {}",
-                    SHORT_URL_STATE_TABLE_NAME,
-                    e
-                )
-            })?;
-
-        session
-            .query_unpaged(CREATE_SHORT_URL_LAST_ACCESS_TABLE_QUERY, &[])
-            .await
-            .map_err(|e| {
-                anyhow!(
-                    "Failed to create table '{}': {}",
-                    SHORT_URL_LAST_ACCESS_TABLE_NAME,
-                    e
-                )
-            })?;
-
-        session
-            .query_unpaged(CREATE_SHORT_URL_CREATE_LOGS_TABLE_QUERY, &[])
-            .await
-            .map_err(|e| {
-                anyhow!(
-                    "Failed to create table '{}': {}",
-                    SHORT_URL_CREATE_LOGS_TABLE_NAME,
-                    e
-                )
-            })?;
+        Ok(session)
+    }
 
-        session
-            .query_unpaged(CREATE_SHORT_URL_ACCESS_LOGS_TABLE_QUERY, &[])
-            .await
-            .map_err(|e| {
-                anyhow!(
-                    "Failed to create table '{}': {}",
-                    SHORT_URL_ACCESS_LOGS_TABLE_NAME,
-                    e
-                )
-            })?;
+    /// Applies pending schema migrations without preparing any statements.
+    pub async fn migrate(config: Config) -> Result<()> {
+        let session = Self::connect(&config).await?;
+        migrations::run(&session).await
+    }
 
-        session
-            .query_unpaged(CREATE_SHORT_URLS_BY_CREATED_AT_TABLE_QUERY, &[])
-            .await
-            .map_err(|e| {
-                anyhow!(
-                    "Failed to create table '{}': {}",
-                    SHORT_URLS_BY_CREATED_AT_TABLE_NAME,
-                    e
-                )
-            })?;
+    pub async fn new(config: Config) -> Result<Self> {
+        let id_allocator =
+            IdAllocator::new(config.id_lease_size).context("Invalid SCYLLA_ID_LEASE_SIZE")?;
+        let session = Self::connect(&config).await?;
 
-        session
-            .query_unpaged(CREATE_SHORT_URL_CREATE_META_TABLE_QUERY, &[])
-            .await
-            .map_err(|e| {
-                anyhow!(
-                    "Failed to create table '{}': {}",
-                    SHORT_URL_CREATE_META_TABLE_NAME,
-                    e
-                )
-            })?;
+        if config.migrate_on_startup {
+            migrations::run(&session).await?;
+        } else {
+            migrations::ensure_up_to_date(&session).await?;
+        }
+        let create_meta_cutover =
+            migrations::applied_at(&session, migrations::PERSIST_CREATE_META_VERSION).await?;
 
-        session
-            .query_unpaged(CREATE_ID_SEQ_TABLE_QUERY, &[])
-            .await
-            .map_err(|e| anyhow!("Failed to create table '{}': {}", ID_SEQ_TABLE_NAME, e))?;
-        session
-            .query_unpaged(
-                format!(
-                    "INSERT INTO {} (name, current_id) VALUES (?, 0) IF NOT EXISTS",
-                    ID_SEQ_TABLE_NAME
-                ),
-                (ID_SEQ_KEY_NAME,),
-            )
-            .await
-            .map_err(|e| {
-                anyhow!(
-                    "Failed to initialize ID sequence in table '{}': {}",
-                    ID_SEQ_TABLE_NAME,
-                    e
-                )
-            })?;
         let ps_insert_url =
             Self::prepare_statement(&session, Statement::new(INSERT_URL_QUERY)).await?;
         let ps_find_url = Self::prepare_statement(&session, Statement::new(FIND_URL_QUERY)).await?;
-        let ps_list_all_urls =
-            Self::prepare_statement(&session, Statement::new(LIST_ALL_URLS_QUERY)).await?;
+        let ps_delete_url =
+            Self::prepare_statement(&session, Statement::new(DELETE_URL_QUERY)).await?;
+        let ps_update_url_destination =
+            Self::prepare_statement(&session, Statement::new(UPDATE_URL_DESTINATION_QUERY)).await?;
+        let ps_insert_destination_change =
+            Self::prepare_statement(&session, Statement::new(INSERT_DESTINATION_CHANGE_QUERY))
+                .await?;
+        let ps_list_destination_history =
+            Self::prepare_statement(&session, Statement::new(LIST_DESTINATION_HISTORY_QUERY))
+                .await?;
+        let ps_delete_destination_history =
+            Self::prepare_statement(&session, Statement::new(DELETE_DESTINATION_HISTORY_QUERY))
+                .await?;
         let ps_insert_url_by_created_at =
             Self::prepare_statement(&session, Statement::new(INSERT_URL_BY_CREATED_AT_QUERY))
                 .await?;
+        let ps_update_url_by_created_at_destination = Self::prepare_statement(
+            &session,
+            Statement::new(UPDATE_URL_BY_CREATED_AT_DESTINATION_QUERY),
+        )
+        .await?;
+        let ps_delete_url_by_created_at =
+            Self::prepare_statement(&session, Statement::new(DELETE_URL_BY_CREATED_AT_QUERY))
+                .await?;
         let ps_list_by_created_at = Self::prepare_statement(
             &session,
-            Statement::new(LIST_BY_CREATED_AT_QUERY).with_page_size(20.try_into().unwrap()),
+            Statement::new(LIST_BY_CREATED_AT_QUERY).with_page_size(20),
         )
         .await?;
+        let ps_insert_bucket =
+            Self::prepare_statement(&session, Statement::new(INSERT_BUCKET_QUERY)).await?;
+        let ps_first_bucket =
+            Self::prepare_statement(&session, Statement::new(FIRST_BUCKET_QUERY)).await?;
+        let ps_next_bucket =
+            Self::prepare_statement(&session, Statement::new(NEXT_BUCKET_QUERY)).await?;
         let ps_get_current_id =
             Self::prepare_statement(&session, Statement::new(GET_CURRENT_ID_QUERY)).await?;
         let ps_get_next_id =
@@ -436,6 +587,8 @@ impl DB {
             Self::prepare_statement(&session, Statement::new(UPSERT_SHORT_URL_STATE_QUERY)).await?;
         let ps_get_state =
             Self::prepare_statement(&session, Statement::new(GET_SHORT_URL_STATE_QUERY)).await?;
+        let ps_delete_state =
+            Self::prepare_statement(&session, Statement::new(DELETE_SHORT_URL_STATE_QUERY)).await?;
 
         let ps_upsert_last_access =
             Self::prepare_statement(&session, Statement::new(UPSERT_SHORT_URL_LAST_ACCESS_QUERY))
@@ -443,88 +596,389 @@ impl DB {
         let ps_get_last_access =
             Self::prepare_statement(&session, Statement::new(GET_SHORT_URL_LAST_ACCESS_QUERY))
                 .await?;
+        let ps_delete_last_access =
+            Self::prepare_statement(&session, Statement::new(DELETE_SHORT_URL_LAST_ACCESS_QUERY))
+                .await?;
 
         let ps_insert_create_log =
             Self::prepare_statement(&session, Statement::new(INSERT_CREATE_LOG_QUERY)).await?;
+        let ps_delete_create_logs =
+            Self::prepare_statement(&session, Statement::new(DELETE_CREATE_LOGS_QUERY)).await?;
         let ps_insert_access_log =
             Self::prepare_statement(&session, Statement::new(INSERT_ACCESS_LOG_QUERY)).await?;
         let ps_list_access_logs =
             Self::prepare_statement(&session, Statement::new(LIST_ACCESS_LOGS_QUERY)).await?;
+        let ps_delete_access_logs_day =
+            Self::prepare_statement(&session, Statement::new(DELETE_ACCESS_LOGS_DAY_QUERY)).await?;
+        let ps_delete_legacy_access_logs =
+            Self::prepare_statement(&session, Statement::new(DELETE_LEGACY_ACCESS_LOGS_QUERY))
+                .await?;
+        let ps_insert_access_log_day =
+            Self::prepare_statement(&session, Statement::new(INSERT_ACCESS_LOG_DAY_QUERY)).await?;
+        let ps_first_access_log_day =
+            Self::prepare_statement(&session, Statement::new(FIRST_ACCESS_LOG_DAY_QUERY)).await?;
+        let ps_next_access_log_day =
+            Self::prepare_statement(&session, Statement::new(NEXT_ACCESS_LOG_DAY_QUERY)).await?;
+        let ps_list_access_log_days =
+            Self::prepare_statement(&session, Statement::new(LIST_ACCESS_LOG_DAYS_QUERY)).await?;
+        let ps_delete_access_log_days =
+            Self::prepare_statement(&session, Statement::new(DELETE_ACCESS_LOG_DAYS_QUERY)).await?;
 
         let ps_insert_create_meta_if_absent =
             Self::prepare_statement(&session, Statement::new(INSERT_CREATE_META_IF_ABSENT_QUERY))
                 .await?;
         let ps_get_create_meta =
             Self::prepare_statement(&session, Statement::new(GET_CREATE_META_QUERY)).await?;
-
-        let has_any = session
-            .query_unpaged(
-                CHECK_BY_CREATED_AT_ANY_QUERY,
-                (SHORT_URLS_BY_CREATED_AT_BUCKET,),
-            )
-            .await
-            .ok()
-            .and_then(|qr| qr.into_rows_result().ok())
-            .and_then(|rr| rr.maybe_first_row::<(String,)>().ok())
-            .flatten()
-            .is_some();
-
-        if !has_any {
-            if let Ok(qr) = session.execute_unpaged(&ps_list_all_urls, &[]).await {
-                if let Ok(rows) = qr.into_rows_result() {
-                    if let Ok(iter) =
-                        rows.rows::<(String, String, DateTime<Utc>, Option<DateTime<Utc>>)>()
-                    {
-                        for row in iter {
-                            if let Ok((id, original_url, created_at, expires_at)) = row {
-                                let _ = session
-                                    .execute_unpaged(
-                                        &ps_insert_url_by_created_at,
-                                        (
-                                            SHORT_URLS_BY_CREATED_AT_BUCKET,
-                                            created_at,
-                                            id.as_str(),
-                                            original_url.as_str(),
-                                            expires_at,
-                                        ),
-                                    )
-                                    .await;
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        let ps_delete_create_meta =
+            Self::prepare_statement(&session, Statement::new(DELETE_CREATE_META_QUERY)).await?;
+        let ps_insert_tombstone =
+            Self::prepare_statement(&session, Statement::new(INSERT_TOMBSTONE_QUERY)).await?;
+        let ps_find_tombstone =
+            Self::prepare_statement(&session, Statement::new(FIND_TOMBSTONE_QUERY)).await?;
+
+        let ps_insert_audit_entry =
+            Self::prepare_statement(&session, Statement::new(INSERT_AUDIT_ENTRY_QUERY)).await?;
+        let ps_insert_audit_entry_by_link =
+            Self::prepare_statement(&session, Statement::new(INSERT_AUDIT_ENTRY_BY_LINK_QUERY))
+                .await?;
+        let ps_insert_audit_entry_by_actor =
+            Self::prepare_statement(&session, Statement::new(INSERT_AUDIT_ENTRY_BY_ACTOR_QUERY))
+                .await?;
+        let ps_list_audit =
+            Self::prepare_statement(&session, Statement::new(LIST_AUDIT_QUERY)).await?;
+        let ps_list_audit_by_link =
+            Self::prepare_statement(&session, Statement::new(LIST_AUDIT_BY_LINK_QUERY)).await?;
+        let ps_list_audit_by_actor =
+            Self::prepare_statement(&session, Statement::new(LIST_AUDIT_BY_ACTOR_QUERY)).await?;
+        let ps_list_audit_keys_by_link =
+            Self::prepare_statement(&session, Statement::new(LIST_AUDIT_KEYS_BY_LINK_QUERY))
+                .await?;
+        let ps_redact_audit_entry =
+            Self::prepare_statement(&session, Statement::new(REDACT_AUDIT_ENTRY_QUERY)).await?;
+        let ps_redact_audit_entry_by_link =
+            Self::prepare_statement(&session, Statement::new(REDACT_AUDIT_ENTRY_BY_LINK_QUERY))
+                .await?;
+        let ps_redact_audit_entry_by_actor =
+            Self::prepare_statement(&session, Statement::new(REDACT_AUDIT_ENTRY_BY_ACTOR_QUERY))
+                .await?;
 
         Ok(Self {
             session,
             ps_insert_url,
             ps_find_url,
-            ps_list_all_urls,
+            ps_delete_url,
+            ps_update_url_destination,
+            ps_insert_destination_change,
+            ps_list_destination_history,
+            ps_delete_destination_history,
             ps_insert_url_by_created_at,
+            ps_update_url_by_created_at_destination,
+            ps_delete_url_by_created_at,
             ps_list_by_created_at,
+            ps_insert_bucket,
+            ps_first_bucket,
+            ps_next_bucket,
+            registered_bucket: std::sync::Mutex::new(None),
             ps_get_current_id,
             ps_get_next_id,
+            id_allocator,
+            create_log_retention: config.create_log_retention,
+            access_log_retention: config.access_log_retention,
+            create_meta_retention: config.create_meta_retention,
+            create_meta_cutover,
 
             ps_upsert_state,
             ps_get_state,
+            ps_delete_state,
 
             ps_upsert_last_access,
             ps_get_last_access,
+            ps_delete_last_access,
 
             ps_insert_create_log,
+            ps_delete_create_logs,
             ps_insert_access_log,
             ps_list_access_logs,
+            ps_delete_access_logs_day,
+            ps_delete_legacy_access_logs,
+            ps_insert_access_log_day,
+            ps_first_access_log_day,
+            ps_next_access_log_day,
+            ps_list_access_log_days,
+            ps_delete_access_log_days,
 
             ps_insert_create_meta_if_absent,
             ps_get_create_meta,
+            ps_delete_create_meta,
+            ps_insert_tombstone,
+            ps_find_tombstone,
+            ps_insert_audit_entry,
+            ps_insert_audit_entry_by_link,
+            ps_insert_audit_entry_by_actor,
+            ps_list_audit,
+            ps_list_audit_by_link,
+            ps_list_audit_by_actor,
+            ps_list_audit_keys_by_link,
+            ps_redact_audit_entry,
+            ps_redact_audit_entry_by_link,
+            ps_redact_audit_entry_by_actor,
         })
     }
 
-    async fn get_next_id(&self) -> Result<i64> {
+    async fn execute_unpaged(
+        &self,
+        statement: &'static str,
+        prepared: &PreparedStatement,
+        values: impl SerializeRow,
+    ) -> Result<QueryResult, ExecutionError> {
+        time_statement(statement, self.session.execute_unpaged(prepared, values)).await
+    }
+
+    async fn execute_single_page(
+        &self,
+        statement: &'static str,
+        prepared: &PreparedStatement,
+        values: impl SerializeRow,
+        paging_state: PagingState,
+    ) -> Result<(QueryResult, PagingStateResponse), ExecutionError> {
+        time_statement(
+            statement,
+            self.session
+                .execute_single_page(prepared, values, paging_state),
+        )
+        .await
+    }
+
+    async fn batch(
+        &self,
+        statement: &'static str,
+        batch: &Batch,
+        values: impl BatchValues,
+    ) -> Result<QueryResult, ExecutionError> {
+        time_statement(statement, self.session.batch(batch, values)).await
+    }
+
+    pub(crate) async fn register_created_at_bucket(&self, bucket: &str) -> Result<()> {
+        let registered = self
+            .registered_bucket
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_deref()
+            == Some(bucket);
+        if registered {
+            return Ok(());
+        }
+        self.execute_unpaged(
+            "insert_bucket",
+            &self.ps_insert_bucket,
+            (SHORT_URL_CREATED_AT_BUCKETS_KEY, bucket),
+        )
+        .await?;
+        *self
+            .registered_bucket
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(bucket.to_string());
+        Ok(())
+    }
+
+    /// Returns the newest bucket registered under `name`, or the newest one
+    /// older than `before`.
+    async fn next_bucket(&self, name: &str, before: Option<&str>) -> Result<Option<String>> {
+        let result = match before {
+            None => {
+                self.execute_unpaged("first_bucket", &self.ps_first_bucket, (name,))
+                    .await?
+            }
+            Some(before) => {
+                self.execute_unpaged("next_bucket", &self.ps_next_bucket, (name, before))
+                    .await?
+            }
+        };
+        Ok(result
+            .into_rows_result()?
+            .maybe_first_row::<(String,)>()?
+            .map(|(bucket,)| bucket))
+    }
+
+    /// Clears the before/after snapshots of every audit entry about `link_id`,
+    /// in all three copies of the audit log.
+    async fn redact_audit_entries(&self, link_id: &str) -> Result<()> {
+        let mut batch = Batch::new(BatchType::Logged);
+        batch.append_statement(self.ps_redact_audit_entry.clone());
+        batch.append_statement(self.ps_redact_audit_entry_by_link.clone());
+        batch.append_statement(self.ps_redact_audit_entry_by_actor.clone());
+
+        let mut bucket = self.next_bucket(ADMIN_AUDIT_BUCKETS_KEY, None).await?;
+        while let Some(current) = bucket {
+            let keys = self
+                .execute_unpaged(
+                    "list_audit_keys_by_link",
+                    &self.ps_list_audit_keys_by_link,
+                    (link_id, current.as_str()),
+                )
+                .await?
+                .into_rows_result()?
+                .rows::<(DateTime<Utc>, String, String)>()?
+                .collect::<Result<Vec<_>, _>>()?;
+            for (ts, entry_id, actor) in keys {
+                let entry_id = entry_id.as_str();
+                let bucket = current.as_str();
+                self.batch(
+                    "redact_audit_entry",
+                    &batch,
+                    (
+                        (bucket, ts, entry_id),
+                        (link_id, bucket, ts, entry_id),
+                        (actor.as_str(), bucket, ts, entry_id),
+                    ),
+                )
+                .await?;
+            }
+            bucket = self
+                .next_bucket(ADMIN_AUDIT_BUCKETS_KEY, Some(&current))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the newest day within the filter's range that has entries for
+    /// `id`, optionally only among days older than `before`.
+    async fn next_access_log_day(
+        &self,
+        id: &str,
+        filter: &AccessLogFilter,
+        before: Option<&str>,
+    ) -> Result<Option<String>> {
+        let min_day = filter.from.map(day_bucket);
+        let min_day = min_day.as_deref().unwrap_or(MIN_DAY);
+        let result = match before {
+            None => {
+                let max_day = filter.to.map(day_bucket);
+                let max_day = max_day.as_deref().unwrap_or(MAX_DAY);
+                self.execute_unpaged(
+                    "first_access_log_day",
+                    &self.ps_first_access_log_day,
+                    (id, max_day, min_day),
+                )
+                .await?
+            }
+            Some(before) => {
+                self.execute_unpaged(
+                    "next_access_log_day",
+                    &self.ps_next_access_log_day,
+                    (id, before, min_day),
+                )
+                .await?
+            }
+        };
+        Ok(result
+            .into_rows_result()?
+            .maybe_first_row::<(String,)>()?
+            .map(|(day,)| day))
+    }
+
+    async fn is_tombstoned(&self, id: &str) -> Result<bool> {
+        Ok(self
+            .execute_unpaged("find_tombstone", &self.ps_find_tombstone, (id,))
+            .await?
+            .into_rows_result()?
+            .rows_num()
+            > 0)
+    }
+
+    /// Inserts the link unless the ID is taken, in which case the existing
+    /// link is returned instead.
+    async fn insert_url(
+        &self,
+        id: &ID,
+        original_url: &Url,
+        created_at: DateTime<Utc>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<Option<ShortenedURL>> {
+        let insert_row = self
+            .execute_unpaged(
+                "insert_url",
+                &self.ps_insert_url,
+                (
+                    id.0.as_str(),
+                    original_url.to_string(),
+                    created_at,
+                    expires_at,
+                ),
+            )
+            .await?
+            .into_rows_result()?
+            .maybe_first_row::<(
+                bool,
+                Option<String>,
+                Option<DateTime<Utc>>,
+                Option<DateTime<Utc>>,
+                Option<String>,
+            )>()?;
+
+        let Some((false, _id, existing_created_at, existing_expires_at, existing_original_url)) =
+            insert_row
+        else {
+            return Ok(None);
+        };
+        let existing_original_url = existing_original_url
+            .as_deref()
+            .ok_or_else(|| anyhow!("Failed to decode existing original_url"))?;
+        let existing_created_at =
+            existing_created_at.ok_or_else(|| anyhow!("Failed to decode existing created_at"))?;
+        Ok(Some(ShortenedURL {
+            id: id.clone(),
+            original_url: Url::parse(existing_original_url)?,
+            created_at: existing_created_at,
+            expires_at: existing_expires_at,
+        }))
+    }
+
+    /// Inserts the link under the next free generated ID. IDs that are
+    /// already taken, e.g. claimed as a custom ID, are skipped.
+    async fn insert_generated_url(
+        self: &Arc<Self>,
+        original_url: &Url,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(ID, DateTime<Utc>)> {
+        for _ in 0..=MAX_GENERATED_ID_SKIPS {
+            let id = if generator().uses_sequence() {
+                ID::generate(self.id_allocator.next(self).await?)?
+            } else {
+                generator().random()?
+            };
+            if self.is_tombstoned(&id.0).await? {
+                tracing::info!(
+                    id = id.0.as_str(),
+                    "Skipping generated ID of a deleted link"
+                );
+                continue;
+            }
+            let created_at = Utc::now();
+            match self
+                .insert_url(&id, original_url, created_at, expires_at)
+                .await?
+            {
+                None => return Ok((id, created_at)),
+                Some(_) => {
+                    tracing::info!(
+                        id = id.0.as_str(),
+                        "Skipping generated ID that is already taken"
+                    );
+                }
+            }
+        }
+        Err(anyhow!(
+            "No free generated ID after skipping {} taken ones",
+            MAX_GENERATED_ID_SKIPS
+        ))
+    }
+
+    /// Moves `id_seq` forward by `size` with a single LWT and returns the
+    /// values skipped over.
+    async fn lease_id_range(&self, size: i64) -> Result<Range<i64>> {
         let current_id_row = self
-            .session
-            .execute_unpaged(&self.ps_get_current_id, &[])
+            .execute_unpaged("get_current_id", &self.ps_get_current_id, &[])
             .await?
             .into_rows_result()?
             .first_row::<(i64,)>()?;
@@ -532,130 +986,123 @@ impl DB {
         let current_id = current_id_row.0;
 
         let result = self
-            .session
-            .execute_unpaged(&self.ps_get_next_id, (current_id + 1, current_id))
+            .execute_unpaged(
+                "get_next_id",
+                &self.ps_get_next_id,
+                (current_id + size, current_id),
+            )
             .await?
             .into_rows_result()?
             .maybe_first_row::<(bool, i64)>()?;
-        tracing::debug!(result = ?result, "Get next ID result");
+        tracing::debug!(result = ?result, size, "Lease ID range result");
 
-        if let Some((applied, _)) = result {
-            if applied {
-                return Ok(current_id + 1);
-            }
+        if let Some((true, _)) = result {
+            return Ok(current_id + 1..current_id + size + 1);
         }
-        Err(anyhow::anyhow!("Failed to get next ID"))
+        Err(anyhow::anyhow!("Failed to lease an ID range"))
+    }
+}
+
+impl IdRangeSource for Arc<DB> {
+    async fn lease(&self, size: i64) -> Result<Range<i64>> {
+        let retry_policy = ExponentialBuilder::default()
+            .with_min_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_secs(5))
+            .with_factor(2.0)
+            .with_jitter()
+            .with_max_times(5);
+
+        let a = || async { self.lease_id_range(size).await };
+
+        a.retry(retry_policy).sleep(tokio::time::sleep).await
     }
 }
 
 impl ShortenedURLRepository for Arc<DB> {
+    async fn ping(&self) -> Result<()> {
+        // Goes through a prepared statement against our own keyspace, so a
+        // session that lost its keyspace or prepared statements fails too.
+        self.execute_unpaged("get_current_id", &self.ps_get_current_id, &[])
+            .await?
+            .into_rows_result()?
+            .first_row::<(i64,)>()?;
+        Ok(())
+    }
+
     async fn create(
         &self,
         original_url: Url,
         custom_id: Option<&str>,
         expires_at: Option<DateTime<Utc>>,
-    ) -> Result<ShortenedURL> {
-        let id = match custom_id {
-            Some(cid) => ID::new(cid.to_string()),
-            None => {
-                let retry_policy = ExponentialBuilder::default()
-                    .with_min_delay(Duration::from_millis(100))
-                    .with_max_delay(Duration::from_secs(5))
-                    .with_factor(2.0)
-                    .with_jitter()
-                    .with_max_times(5);
-
-                let a = || async {
-                    return self.get_next_id().await;
-                };
-
-                let seq = a.retry(retry_policy).sleep(tokio::time::sleep).await?;
-
-                ID::generate(seq)?
+    ) -> Result<CreateOutcome> {
+        let (id, created_at) = match custom_id {
+            Some(cid) => {
+                let id = ID::new(cid.to_string());
+                if self.is_tombstoned(&id.0).await? {
+                    return Err(RepositoryError::IdDeleted(id.0).into());
+                }
+                let created_at = Utc::now();
+                if let Some(existing) = self
+                    .insert_url(&id, &original_url, created_at, expires_at)
+                    .await?
+                {
+                    return Ok(CreateOutcome::from_existing(existing, &original_url)?);
+                }
+                // `delete` writes the tombstone before removing the link, so a
+                // delete that raced the check above is visible by now.
+                if self.is_tombstoned(&id.0).await? {
+                    self.execute_unpaged("delete_url", &self.ps_delete_url, (id.0.as_str(),))
+                        .await?;
+                    return Err(RepositoryError::IdDeleted(id.0).into());
+                }
+                (id, created_at)
             }
+            None => self.insert_generated_url(&original_url, expires_at).await?,
         };
 
-        let created_at = Utc::now();
-
-        let insert_res = self
-            .session
-            .execute_unpaged(
-                &self.ps_insert_url,
+        // The link already resolves at this point. If anything below fails
+        // the error is returned and `reconcile::reconcile_links` restores the
+        // missing rows.
+        let bucket = month_bucket(created_at);
+        self.register_created_at_bucket(&bucket).await?;
+
+        // A logged batch, so the state and listing rows land together or not
+        // at all.
+        let mut secondary = Batch::new(BatchType::Logged);
+        secondary.append_statement(self.ps_upsert_state.clone());
+        secondary.append_statement(self.ps_insert_url_by_created_at.clone());
+        self.batch(
+            "insert_link_secondary_batch",
+            &secondary,
+            (
                 (
                     id.0.as_str(),
-                    original_url.to_string(),
+                    true,
+                    Option::<DateTime<Utc>>::None,
                     created_at,
-                    expires_at,
                 ),
-            )
-            .await?;
-
-        let insert_row = insert_res.into_rows_result()?.maybe_first_row::<(
-            bool,
-            Option<String>,
-            Option<DateTime<Utc>>,
-            Option<DateTime<Utc>>,
-            Option<String>,
-        )>()?;
-
-        let (applied, final_original_url, final_created_at, final_expires_at) = match insert_row {
-            Some((true, _, _, _, _)) => (true, original_url, created_at, expires_at),
-            Some((false, _id, created_at, expires_at, original_url)) => {
-                let existing_original_url = original_url
-                    .as_deref()
-                    .ok_or_else(|| anyhow!("Failed to decode existing original_url"))?;
-                let existing_created_at =
-                    created_at.ok_or_else(|| anyhow!("Failed to decode existing created_at"))?;
                 (
-                    false,
-                    Url::parse(existing_original_url)?,
-                    existing_created_at,
+                    bucket.as_str(),
+                    created_at,
+                    id.0.as_str(),
+                    original_url.as_str(),
                     expires_at,
-                )
-            }
-            None => (true, original_url, created_at, expires_at),
-        };
-
-        if applied {
-            self.session
-                .execute_unpaged(
-                    &self.ps_upsert_state,
-                    (
-                        id.0.as_str(),
-                        true,
-                        Option::<DateTime<Utc>>::None,
-                        final_created_at,
-                    ),
-                )
-                .await?;
-
-            let _ = self
-                .session
-                .execute_unpaged(
-                    &self.ps_insert_url_by_created_at,
-                    (
-                        SHORT_URLS_BY_CREATED_AT_BUCKET,
-                        final_created_at,
-                        id.0.as_str(),
-                        final_original_url.to_string(),
-                        final_expires_at,
-                    ),
-                )
-                .await;
-        }
+                ),
+            ),
+        )
+        .await?;
 
-        Ok(ShortenedURL {
+        Ok(CreateOutcome::Created(ShortenedURL {
             id,
-            original_url: final_original_url,
-            created_at: final_created_at,
-            expires_at: final_expires_at,
-        })
+            original_url,
+            created_at,
+            expires_at,
+        }))
     }
 
     async fn find_by_id(&self, id: ID) -> Result<Option<ShortenedURL>> {
         let result = self
-            .session
-            .execute_unpaged(&self.ps_find_url, (id.0.as_str(),))
+            .execute_unpaged("find_url", &self.ps_find_url, (id.0.as_str(),))
             .await?
             .into_rows_result()?
             .maybe_first_row::<(String, DateTime<Utc>, Option<DateTime<Utc>>)>()?;
@@ -672,52 +1119,383 @@ impl ShortenedURLRepository for Arc<DB> {
         }
     }
 
-    async fn list_by_created_at_page(
+    async fn update_destination(
         &self,
-        limit: i32,
-        paging_state: Option<Vec<u8>>,
-    ) -> Result<(Vec<ShortenedURL>, Option<Vec<u8>>)> {
-        use scylla::response::PagingStateResponse;
+        id: &str,
+        new_url: Url,
+        changed_by: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<ShortenedURL>> {
+        let id = ID::new(id.to_string());
+        let id = id.0.as_str();
+        for _ in 0..MAX_DESTINATION_UPDATE_ATTEMPTS {
+            let Some(url) = self.find_by_id(ID(id.to_string())).await? else {
+                return Ok(None);
+            };
+            if url.original_url == new_url {
+                return Ok(Some(url));
+            }
 
-        let page_size = limit.clamp(1, 100);
-        let mut stmt = self.ps_list_by_created_at.clone();
-        stmt.set_page_size(page_size);
+            let result = self
+                .execute_unpaged(
+                    "update_url_destination",
+                    &self.ps_update_url_destination,
+                    (new_url.as_str(), id, url.original_url.as_str()),
+                )
+                .await?;
+            if !lwt_applied(result)? {
+                continue;
+            }
 
-        let paging_state = match paging_state {
-            Some(raw) => PagingState::new_from_raw_bytes(raw),
-            None => PagingState::start(),
-        };
+            // The link already redirects to `new_url`. Should either write
+            // below fail, the error is returned; a listing row left with the
+            // old destination is fixed by `walnuk-admin check --repair`.
+            self.execute_unpaged(
+                "insert_destination_change",
+                &self.ps_insert_destination_change,
+                (
+                    id,
+                    now,
+                    url.original_url.as_str(),
+                    new_url.as_str(),
+                    changed_by,
+                ),
+            )
+            .await?;
+            self.execute_unpaged(
+                "update_url_by_created_at_destination",
+                &self.ps_update_url_by_created_at_destination,
+                (
+                    new_url.as_str(),
+                    month_bucket(url.created_at),
+                    url.created_at,
+                    id,
+                ),
+            )
+            .await?;
 
-        let (res, paging_state_response) = self
-            .session
-            .execute_single_page(&stmt, (SHORT_URLS_BY_CREATED_AT_BUCKET,), paging_state)
+            return Ok(Some(ShortenedURL {
+                original_url: new_url,
+                ..url
+            }));
+        }
+        Err(anyhow!(
+            "The destination of {} changed concurrently {} times",
+            id,
+            MAX_DESTINATION_UPDATE_ATTEMPTS
+        ))
+    }
+
+    async fn delete(&self, id: &str, deleted_by: &str, now: DateTime<Utc>) -> Result<bool> {
+        let id = ID::new(id.to_string());
+        let id = id.0.as_str();
+        let url = self.find_by_id(ID(id.to_string())).await?;
+        if url.is_none() && !self.is_tombstoned(id).await? {
+            return Ok(false);
+        }
+
+        // Keeps the first deletion's time and subject when purging again.
+        self.execute_unpaged(
+            "insert_tombstone",
+            &self.ps_insert_tombstone,
+            (id, now, deleted_by),
+        )
+        .await?;
+        // The link stops resolving first. Should anything below fail, the
+        // error is returned and deleting again finishes the purge; a listing
+        // row left behind is removed by `walnuk-admin check --repair`.
+        self.execute_unpaged("delete_url", &self.ps_delete_url, (id,))
+            .await?;
+        if let Some(url) = &url {
+            self.execute_unpaged(
+                "delete_url_by_created_at",
+                &self.ps_delete_url_by_created_at,
+                (month_bucket(url.created_at), url.created_at, id),
+            )
             .await?;
+        }
 
-        let rows = res.into_rows_result()?;
-        let mut out = Vec::new();
-        let iter = rows
-            .rows::<(DateTime<Utc>, String, String, Option<DateTime<Utc>>)>()
-            .map_err(|e| anyhow!("Failed to decode rows for list_by_created_at_page: {}", e))?;
-
-        for row in iter {
-            let (created_at, id, original_url, expires_at) = row
-                .map_err(|e| anyhow!("Failed to decode row for list_by_created_at_page: {}", e))?;
-            out.push(ShortenedURL {
-                id: ID::new(id),
-                original_url: Url::parse(&original_url)?,
-                created_at,
-                expires_at,
+        let days = self
+            .execute_unpaged("list_access_log_days", &self.ps_list_access_log_days, (id,))
+            .await?
+            .into_rows_result()?
+            .rows::<(String,)>()?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (day,) in days {
+            self.execute_unpaged(
+                "delete_access_logs_day",
+                &self.ps_delete_access_logs_day,
+                (id, day),
+            )
+            .await?;
+        }
+
+        let by_id: [(&'static str, &PreparedStatement); 7] = [
+            ("delete_access_log_days", &self.ps_delete_access_log_days),
+            (
+                "delete_legacy_access_logs",
+                &self.ps_delete_legacy_access_logs,
+            ),
+            ("delete_create_logs", &self.ps_delete_create_logs),
+            ("delete_state", &self.ps_delete_state),
+            ("delete_last_access", &self.ps_delete_last_access),
+            ("delete_create_meta", &self.ps_delete_create_meta),
+            (
+                "delete_destination_history",
+                &self.ps_delete_destination_history,
+            ),
+        ];
+        for (statement, prepared) in by_id {
+            self.execute_unpaged(statement, prepared, (id,)).await?;
+        }
+        self.redact_audit_entries(id).await?;
+        Ok(true)
+    }
+
+    async fn is_deleted(&self, id: &str) -> Result<bool> {
+        self.is_tombstoned(&ID::new(id.to_string()).0).await
+    }
+
+    async fn list_destination_history(&self, id: &str) -> Result<Vec<DestinationChange>> {
+        let rows = self
+            .execute_unpaged(
+                "list_destination_history",
+                &self.ps_list_destination_history,
+                (id,),
+            )
+            .await?
+            .into_rows_result()?;
+
+        let mut changes = Vec::with_capacity(rows.rows_num());
+        for row in rows.rows::<(DateTime<Utc>, String, String, String)>()? {
+            let (changed_at, previous_url, new_url, changed_by) =
+                row.map_err(|e| anyhow!("Failed to decode destination change: {}", e))?;
+            changes.push(DestinationChange {
+                changed_at,
+                previous_url: Url::parse(&previous_url)?,
+                new_url: Url::parse(&new_url)?,
+                changed_by,
             });
         }
+        Ok(changes)
+    }
+
+    async fn record_audit(&self, entry: &AuditEntry) -> Result<()> {
+        let bucket = month_bucket(entry.ts);
+        let action = entry.action.to_string();
+        let before = entry.before.as_ref().map(|v| v.to_string());
+        let after = entry.after.as_ref().map(|v| v.to_string());
+        let values = (
+            bucket.as_str(),
+            entry.ts,
+            entry.id.as_str(),
+            entry.actor.as_str(),
+            action.as_str(),
+            entry.link_id.as_str(),
+            entry.reason.as_deref(),
+            entry.request_id.as_deref(),
+            before.as_deref(),
+            after.as_deref(),
+        );
+
+        let mut batch = Batch::new(BatchType::Logged);
+        batch.append_statement(self.ps_insert_audit_entry.clone());
+        batch.append_statement(self.ps_insert_audit_entry_by_link.clone());
+        batch.append_statement(self.ps_insert_audit_entry_by_actor.clone());
+        batch.append_statement(self.ps_insert_bucket.clone());
+        self.batch(
+            "insert_audit_entry",
+            &batch,
+            (
+                values,
+                values,
+                values,
+                (ADMIN_AUDIT_BUCKETS_KEY, bucket.as_str()),
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn list_audit_page(
+        &self,
+        filter: &AuditFilter,
+        limit: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> Result<(Vec<AuditEntry>, Option<Vec<u8>>)> {
+        let page_size = limit.clamp(1, 100) as usize;
+
+        // The link copy is narrower than the actor one, so it wins when both
+        // filters are set and the actor is checked on each row instead.
+        let (statement, prepared, key) = match (&filter.link_id, &filter.actor) {
+            (Some(link_id), _) => (
+                "list_audit_by_link",
+                &self.ps_list_audit_by_link,
+                Some(link_id.as_str()),
+            ),
+            (None, Some(actor)) => (
+                "list_audit_by_actor",
+                &self.ps_list_audit_by_actor,
+                Some(actor.as_str()),
+            ),
+            (None, None) => ("list_audit", &self.ps_list_audit, None),
+        };
+
+        let mut cursor = match paging_state {
+            Some(raw) => ListCursor::decode(&raw)?,
+            None => match self.next_bucket(ADMIN_AUDIT_BUCKETS_KEY, None).await? {
+                Some(bucket) => ListCursor::start_of(bucket),
+                None => return Ok((Vec::new(), None)),
+            },
+        };
 
-        let next_page_state = match paging_state_response {
-            PagingStateResponse::NoMorePages => None,
-            PagingStateResponse::HasMorePages { state } => {
-                state.as_bytes_slice().map(|arc| arc.as_ref().to_vec())
+        let mut out = Vec::with_capacity(page_size);
+        let mut scanned = 0;
+        loop {
+            let mut stmt = prepared.clone();
+            stmt.set_page_size((page_size - out.len()) as i32);
+            let paging_state = match cursor.paging_state.take() {
+                Some(raw) => PagingState::new_from_raw_bytes(raw),
+                None => PagingState::start(),
+            };
+            let values: Vec<&str> = key
+                .into_iter()
+                .chain(std::iter::once(cursor.bucket.as_str()))
+                .collect();
+
+            let (res, paging_state_response) = self
+                .execute_single_page(statement, &stmt, values, paging_state)
+                .await?;
+
+            let rows = res.into_rows_result()?;
+            scanned += rows.rows_num();
+            let iter = rows
+                .rows::<(
+                    DateTime<Utc>,
+                    String,
+                    String,
+                    String,
+                    String,
+                    Option<String>,
+                    Option<String>,
+                    Option<String>,
+                    Option<String>,
+                )>()
+                .map_err(|e| anyhow!("Failed to decode rows for list_audit_page: {}", e))?;
+
+            for row in iter {
+                let (ts, id, actor, action, link_id, reason, request_id, before, after) =
+                    row.map_err(|e| anyhow!("Failed to decode row for list_audit_page: {}", e))?;
+                let entry = AuditEntry {
+                    id,
+                    ts,
+                    actor,
+                    action: action.parse::<AuditAction>()?,
+                    link_id,
+                    reason,
+                    request_id,
+                    before: before.as_deref().map(serde_json::from_str).transpose()?,
+                    after: after.as_deref().map(serde_json::from_str).transpose()?,
+                };
+                if filter.matches(&entry) {
+                    out.push(entry);
+                }
+            }
+
+            cursor.paging_state = match paging_state_response {
+                PagingStateResponse::NoMorePages => None,
+                PagingStateResponse::HasMorePages { state } => {
+                    state.as_bytes_slice().map(|arc| arc.as_ref().to_vec())
+                }
+            };
+            if cursor.paging_state.is_none() {
+                match self
+                    .next_bucket(ADMIN_AUDIT_BUCKETS_KEY, Some(&cursor.bucket))
+                    .await?
+                {
+                    Some(bucket) => cursor = ListCursor::start_of(bucket),
+                    None => return Ok((out, None)),
+                }
+            }
+            if out.len() >= page_size || scanned >= MAX_SCANNED_ROWS_PER_PAGE {
+                return Ok((out, Some(cursor.encode())));
             }
+        }
+    }
+
+    async fn list_by_created_at_page(
+        &self,
+        limit: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> Result<(Vec<ShortenedURL>, Option<Vec<u8>>)> {
+        let page_size = limit.clamp(1, 100) as usize;
+
+        let mut cursor = match paging_state {
+            Some(raw) => ListCursor::decode(&raw)?,
+            None => match self
+                .next_bucket(SHORT_URL_CREATED_AT_BUCKETS_KEY, None)
+                .await?
+            {
+                Some(bucket) => ListCursor::start_of(bucket),
+                None => return Ok((Vec::new(), None)),
+            },
         };
 
-        Ok((out, next_page_state))
+        let mut out = Vec::with_capacity(page_size);
+        loop {
+            let mut stmt = self.ps_list_by_created_at.clone();
+            stmt.set_page_size((page_size - out.len()) as i32);
+            let paging_state = match cursor.paging_state.take() {
+                Some(raw) => PagingState::new_from_raw_bytes(raw),
+                None => PagingState::start(),
+            };
+
+            let (res, paging_state_response) = self
+                .execute_single_page(
+                    "list_by_created_at",
+                    &stmt,
+                    (cursor.bucket.as_str(),),
+                    paging_state,
+                )
+                .await?;
+
+            let rows = res.into_rows_result()?;
+            let iter = rows
+                .rows::<(DateTime<Utc>, String, String, Option<DateTime<Utc>>)>()
+                .map_err(|e| anyhow!("Failed to decode rows for list_by_created_at_page: {}", e))?;
+
+            for row in iter {
+                let (created_at, id, original_url, expires_at) = row.map_err(|e| {
+                    anyhow!("Failed to decode row for list_by_created_at_page: {}", e)
+                })?;
+                out.push(ShortenedURL {
+                    id: ID::new(id),
+                    original_url: Url::parse(&original_url)?,
+                    created_at,
+                    expires_at,
+                });
+            }
+
+            cursor.paging_state = match paging_state_response {
+                PagingStateResponse::NoMorePages => None,
+                PagingStateResponse::HasMorePages { state } => {
+                    state.as_bytes_slice().map(|arc| arc.as_ref().to_vec())
+                }
+            };
+            if cursor.paging_state.is_none() {
+                // This bucket is exhausted; carry on with the next older one.
+                match self
+                    .next_bucket(SHORT_URL_CREATED_AT_BUCKETS_KEY, Some(&cursor.bucket))
+                    .await?
+                {
+                    Some(bucket) => cursor = ListCursor::start_of(bucket),
+                    None => return Ok((out, None)),
+                }
+            }
+            if out.len() >= page_size {
+                return Ok((out, Some(cursor.encode())));
+            }
+        }
     }
 
     async fn save_create_meta_if_absent(
@@ -729,8 +1507,8 @@ impl ShortenedURLRepository for Arc<DB> {
         request_id: Option<&str>,
     ) -> Result<()> {
         let _ = self
-            .session
             .execute_unpaged(
+                "insert_create_meta_if_absent",
                 &self.ps_insert_create_meta_if_absent,
                 (
                     id,
@@ -738,20 +1516,28 @@ impl ShortenedURLRepository for Arc<DB> {
                     ip.unwrap_or(""),
                     user_agent.unwrap_or(""),
                     request_id.unwrap_or(""),
-                    LOG_TTL_SECONDS_30D,
+                    self.create_meta_retention.ttl_seconds(),
                 ),
             )
             .await?;
         Ok(())
     }
 
+    fn create_meta_retention(&self) -> Option<chrono::Duration> {
+        self.create_meta_retention.as_duration()
+    }
+
+    fn create_meta_aged_out(&self, created_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        let retention = self.create_meta_retention();
+        retention::create_meta_aged_out(created_at, retention, self.create_meta_cutover, now)
+    }
+
     async fn get_create_meta(
         &self,
         id: &str,
     ) -> Result<Option<(DateTime<Utc>, String, String, String)>> {
         let result = self
-            .session
-            .execute_unpaged(&self.ps_get_create_meta, (id,))
+            .execute_unpaged("get_create_meta", &self.ps_get_create_meta, (id,))
             .await?
             .into_rows_result()?
             .maybe_first_row::<(DateTime<Utc>, String, String, String)>()?;
@@ -761,8 +1547,7 @@ impl ShortenedURLRepository for Arc<DB> {
 
     async fn get_state(&self, id: &str) -> Result<Option<ShortUrlState>> {
         let result = self
-            .session
-            .execute_unpaged(&self.ps_get_state, (id,))
+            .execute_unpaged("get_state", &self.ps_get_state, (id,))
             .await?
             .into_rows_result()?
             .maybe_first_row::<(Option<bool>, Option<DateTime<Utc>>, Option<DateTime<Utc>>)>()?;
@@ -781,9 +1566,12 @@ impl ShortenedURLRepository for Arc<DB> {
 
     async fn set_enabled(&self, id: &str, enabled: bool, now: DateTime<Utc>) -> Result<()> {
         let disabled_at = if enabled { None } else { Some(now) };
-        self.session
-            .execute_unpaged(&self.ps_upsert_state, (id, enabled, disabled_at, now))
-            .await?;
+        self.execute_unpaged(
+            "upsert_state",
+            &self.ps_upsert_state,
+            (id, enabled, disabled_at, now),
+        )
+        .await?;
         Ok(())
     }
 
@@ -796,20 +1584,20 @@ impl ShortenedURLRepository for Arc<DB> {
         original_url: &str,
         request_id: Option<&str>,
     ) -> Result<()> {
-        self.session
-            .execute_unpaged(
-                &self.ps_insert_create_log,
-                (
-                    id,
-                    ts,
-                    ip.unwrap_or(""),
-                    user_agent.unwrap_or(""),
-                    original_url,
-                    request_id.unwrap_or(""),
-                    LOG_TTL_SECONDS_30D,
-                ),
-            )
-            .await?;
+        self.execute_unpaged(
+            "insert_create_log",
+            &self.ps_insert_create_log,
+            (
+                id,
+                ts,
+                ip.unwrap_or(""),
+                user_agent.unwrap_or(""),
+                original_url,
+                request_id.unwrap_or(""),
+                self.create_log_retention.ttl_seconds(),
+            ),
+        )
+        .await?;
         Ok(())
     }
 
@@ -822,71 +1610,190 @@ impl ShortenedURLRepository for Arc<DB> {
         request_id: Option<&str>,
         status_code: i32,
     ) -> Result<()> {
-        self.session
-            .execute_unpaged(
-                &self.ps_insert_access_log,
-                (
-                    id,
-                    ts,
-                    ip.unwrap_or(""),
-                    user_agent.unwrap_or(""),
-                    request_id.unwrap_or(""),
-                    status_code,
-                    LOG_TTL_SECONDS_30D,
-                ),
-            )
+        let day = day_bucket(ts);
+        self.execute_unpaged(
+            "insert_access_log_day",
+            &self.ps_insert_access_log_day,
+            (id, day.as_str(), self.access_log_retention.ttl_seconds()),
+        )
+        .await?;
+        self.execute_unpaged(
+            "insert_access_log",
+            &self.ps_insert_access_log,
+            (
+                id,
+                day.as_str(),
+                ts,
+                ip.unwrap_or(""),
+                user_agent.unwrap_or(""),
+                request_id.unwrap_or(""),
+                status_code,
+                self.access_log_retention.ttl_seconds(),
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn log_access_batch(&self, events: &[AccessEvent]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        // The rows live in different partitions, so these batches are unlogged:
+        // they only save round trips and are not atomic. Days are indexed
+        // first so that a listing never misses a written entry.
+        let days: HashSet<(&str, String)> = events
+            .iter()
+            .map(|event| (event.id.as_str(), day_bucket(event.ts)))
+            .collect();
+        let mut day_index = Batch::new(BatchType::Unlogged);
+        let mut day_index_values = Vec::with_capacity(days.len());
+        for (id, day) in &days {
+            day_index.append_statement(self.ps_insert_access_log_day.clone());
+            day_index_values.push((*id, day.as_str(), self.access_log_retention.ttl_seconds()));
+        }
+        self.batch("insert_access_log_day_batch", &day_index, day_index_values)
+            .await?;
+
+        let mut logs = Batch::new(BatchType::Unlogged);
+        let mut log_values = Vec::with_capacity(events.len());
+        for event in events {
+            logs.append_statement(self.ps_insert_access_log.clone());
+            log_values.push((
+                event.id.as_str(),
+                day_bucket(event.ts),
+                event.ts,
+                event.ip.as_deref().unwrap_or(""),
+                event.user_agent.as_deref().unwrap_or(""),
+                event.request_id.as_deref().unwrap_or(""),
+                event.status_code,
+                self.access_log_retention.ttl_seconds(),
+            ));
+        }
+        self.batch("insert_access_log_batch", &logs, log_values)
+            .await?;
+
+        // Statements in one batch share a write timestamp, so only the newest
+        // access per ID may be written or the winner would be arbitrary.
+        let mut latest: HashMap<&str, (DateTime<Utc>, i32)> = HashMap::new();
+        for event in events.iter().filter(|e| e.update_last_access) {
+            let entry = latest
+                .entry(event.id.as_str())
+                .or_insert((event.ts, event.status_code));
+            if event.ts > entry.0 {
+                *entry = (event.ts, event.status_code);
+            }
+        }
+        if latest.is_empty() {
+            return Ok(());
+        }
+
+        let mut last_access = Batch::new(BatchType::Unlogged);
+        let mut last_access_values = Vec::with_capacity(latest.len());
+        for (id, (ts, status_code)) in latest {
+            last_access.append_statement(self.ps_upsert_last_access.clone());
+            last_access_values.push((id, ts, status_code));
+        }
+        self.batch("upsert_last_access_batch", &last_access, last_access_values)
             .await?;
         Ok(())
     }
 
-    async fn list_access_logs_recent(
+    async fn list_access_logs_page(
         &self,
         id: &str,
+        filter: &AccessLogFilter,
         limit: i32,
-    ) -> Result<Vec<(DateTime<Utc>, String, String, String, i32)>> {
-        let page_size = limit.clamp(1, 500);
-        let mut stmt = self.ps_list_access_logs.clone();
-        stmt.set_page_size(page_size);
-
-        let (res, _paging_state_response) = self
-            .session
-            .execute_single_page(&stmt, (id,), PagingState::start())
-            .await?;
+        paging_state: Option<Vec<u8>>,
+    ) -> Result<(Vec<AccessLogRow>, Option<Vec<u8>>)> {
+        let page_size = limit.clamp(1, 500) as usize;
+        let from = filter.from.unwrap_or(DateTime::UNIX_EPOCH);
+        let to = filter.to.unwrap_or(DateTime::<Utc>::MAX_UTC);
+
+        let mut cursor = match paging_state {
+            Some(raw) => ListCursor::decode(&raw)?,
+            None => match self.next_access_log_day(id, filter, None).await? {
+                Some(day) => ListCursor::start_of(day),
+                None => return Ok((Vec::new(), None)),
+            },
+        };
 
-        let rows = res.into_rows_result()?;
-        let iter = rows
-            .rows::<(DateTime<Utc>, String, String, String, i32)>()
-            .map_err(|e| anyhow!("Failed to decode rows for list_access_logs_recent: {}", e))?;
+        let mut out = Vec::with_capacity(page_size);
+        let mut scanned = 0;
+        loop {
+            let mut stmt = self.ps_list_access_logs.clone();
+            stmt.set_page_size((page_size - out.len()) as i32);
+            let paging_state = match cursor.paging_state.take() {
+                Some(raw) => PagingState::new_from_raw_bytes(raw),
+                None => PagingState::start(),
+            };
+
+            let (res, paging_state_response) = self
+                .execute_single_page(
+                    "list_access_logs",
+                    &stmt,
+                    (id, cursor.bucket.as_str(), from, to),
+                    paging_state,
+                )
+                .await?;
 
-        let mut out = Vec::new();
-        for row in iter {
-            let (ts, ip, ua, rid, status_code) = row
-                .map_err(|e| anyhow!("Failed to decode row for list_access_logs_recent: {}", e))?;
-            out.push((ts, ip, ua, rid, status_code));
+            let rows = res.into_rows_result()?;
+            scanned += rows.rows_num();
+            let iter = rows
+                .rows::<AccessLogRow>()
+                .map_err(|e| anyhow!("Failed to decode rows for list_access_logs_page: {}", e))?;
+            for row in iter {
+                let row = row.map_err(|e| {
+                    anyhow!("Failed to decode row for list_access_logs_page: {}", e)
+                })?;
+                // Status codes are not part of the key, so they are filtered here.
+                if filter.matches(row.0, row.4) {
+                    out.push(row);
+                }
+            }
+
+            cursor.paging_state = match paging_state_response {
+                PagingStateResponse::NoMorePages => None,
+                PagingStateResponse::HasMorePages { state } => {
+                    state.as_bytes_slice().map(|arc| arc.as_ref().to_vec())
+                }
+            };
+            if cursor.paging_state.is_none() {
+                match self
+                    .next_access_log_day(id, filter, Some(&cursor.bucket))
+                    .await?
+                {
+                    Some(day) => cursor = ListCursor::start_of(day),
+                    None => return Ok((out, None)),
+                }
+            }
+            if out.len() >= page_size || scanned >= MAX_SCANNED_ROWS_PER_PAGE {
+                return Ok((out, Some(cursor.encode())));
+            }
         }
-        Ok(out)
     }
 
     async fn get_last_access(&self, id: &str) -> Result<Option<(DateTime<Utc>, i32)>> {
         let result = self
-            .session
-            .execute_unpaged(&self.ps_get_last_access, (id,))
+            .execute_unpaged("get_last_access", &self.ps_get_last_access, (id,))
             .await?
             .into_rows_result()?
             .maybe_first_row::<(Option<DateTime<Utc>>, Option<i32>)>()?;
 
-        if let Some((ts, sc)) = result {
-            if let Some(ts) = ts {
-                return Ok(Some((ts, sc.unwrap_or(0))));
-            }
+        if let Some((Some(ts), sc)) = result {
+            return Ok(Some((ts, sc.unwrap_or(0))));
         }
         Ok(None)
     }
 
     async fn set_last_access(&self, id: &str, ts: DateTime<Utc>, status_code: i32) -> Result<()> {
-        self.session
-            .execute_unpaged(&self.ps_upsert_last_access, (id, ts, status_code))
-            .await?;
+        self.execute_unpaged(
+            "upsert_last_access",
+            &self.ps_upsert_last_access,
+            (id, ts, status_code),
+        )
+        .await?;
         Ok(())
     }
 }
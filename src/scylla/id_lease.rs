@@ -0,0 +1,151 @@
+use std::{future::Future, ops::Range};
+
+use anyhow::{Result, bail};
+use tokio::{sync::Mutex, task::JoinHandle};
+
+/// Hands out blocks of the shared ID sequence.
+pub trait IdRangeSource: Clone + Send + Sync + 'static {
+    /// Reserves `size` consecutive sequence values for this instance.
+    fn lease(&self, size: i64) -> impl Future<Output = Result<Range<i64>>> + Send;
+}
+
+struct LeaseState {
+    current: Range<i64>,
+    refill: Option<JoinHandle<Result<Range<i64>>>>,
+}
+
+/// Serves sequence values from a locally leased block and leases the next
+/// block in the background once the current one runs low.
+///
+/// Values left in a block when the process exits are never used, so generated
+/// IDs have gaps.
+pub struct IdAllocator {
+    lease_size: i64,
+    refill_below: i64,
+    state: Mutex<LeaseState>,
+}
+
+impl IdAllocator {
+    pub fn new(lease_size: i64) -> Result<Self> {
+        if lease_size < 1 {
+            bail!("The ID lease size must be at least 1, got {}", lease_size);
+        }
+        Ok(Self {
+            lease_size,
+            refill_below: lease_size / 5,
+            state: Mutex::new(LeaseState {
+                current: 0..0,
+                refill: None,
+            }),
+        })
+    }
+
+    pub async fn next<S: IdRangeSource>(&self, source: &S) -> Result<i64> {
+        let mut state = self.state.lock().await;
+
+        loop {
+            if let Some(seq) = state.current.next() {
+                if state.refill.is_none()
+                    && state.current.end - state.current.start <= self.refill_below
+                {
+                    let source = source.clone();
+                    let size = self.lease_size;
+                    state.refill = Some(tokio::spawn(async move { source.lease(size).await }));
+                }
+                return Ok(seq);
+            }
+
+            state.current = match state.refill.take() {
+                Some(refill) => match refill.await {
+                    Ok(Ok(range)) => range,
+                    Ok(Err(e)) => {
+                        tracing::warn!(error = %e, "Background ID lease failed, retrying");
+                        source.lease(self.lease_size).await?
+                    }
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Background ID lease task failed, retrying");
+                        source.lease(self.lease_size).await?
+                    }
+                },
+                None => source.lease(self.lease_size).await?,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        sync::{
+            Arc,
+            atomic::{AtomicI64, AtomicUsize, Ordering},
+        },
+    };
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct FakeSequence {
+        current: Arc<AtomicI64>,
+        leases: Arc<AtomicUsize>,
+    }
+
+    impl IdRangeSource for FakeSequence {
+        async fn lease(&self, size: i64) -> Result<Range<i64>> {
+            self.leases.fetch_add(1, Ordering::SeqCst);
+            let start = self.current.fetch_add(size, Ordering::SeqCst) + 1;
+            Ok(start..start + size)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hands_out_leased_ranges() {
+        let source = FakeSequence::default();
+        let allocator = IdAllocator::new(10).unwrap();
+
+        let mut seqs = Vec::new();
+        for _ in 0..25 {
+            seqs.push(allocator.next(&source).await.unwrap());
+        }
+
+        assert_eq!(seqs, (1..=25).collect::<Vec<_>>());
+        // Three blocks in use, plus at most one leased ahead in the background.
+        assert!((3..=4).contains(&source.leases.load(Ordering::SeqCst)));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_allocations_are_unique() {
+        let source = FakeSequence::default();
+        let allocator = Arc::new(IdAllocator::new(7).unwrap());
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let allocator = Arc::clone(&allocator);
+                let source = source.clone();
+                tokio::spawn(async move {
+                    let mut seqs = Vec::new();
+                    for _ in 0..50 {
+                        seqs.push(allocator.next(&source).await.unwrap());
+                    }
+                    seqs
+                })
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        for task in tasks {
+            for seq in task.await.unwrap() {
+                assert!(seen.insert(seq), "{seq} was handed out twice");
+            }
+        }
+        assert_eq!(seen.len(), 400);
+    }
+
+    #[test]
+    fn test_rejects_empty_leases() {
+        assert!(IdAllocator::new(0).is_err());
+        assert!(IdAllocator::new(-5).is_err());
+        assert!(IdAllocator::new(1).is_ok());
+    }
+}
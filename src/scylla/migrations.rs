@@ -0,0 +1,1027 @@
+use crate::scylla::{
+    buckets::{day_bucket, month_bucket},
+    db::{
+        ADMIN_AUDIT_LOG_BY_ACTOR_TABLE_NAME, ADMIN_AUDIT_LOG_BY_LINK_TABLE_NAME,
+        ADMIN_AUDIT_LOG_TABLE_NAME, ID_SEQ_KEY_NAME, ID_SEQ_TABLE_NAME,
+        SHORT_URL_ACCESS_LOG_DAYS_TABLE_NAME, SHORT_URL_ACCESS_LOGS_BY_DAY_TABLE_NAME,
+        SHORT_URL_CREATE_LOGS_TABLE_NAME, SHORT_URL_CREATE_META_TABLE_NAME,
+        SHORT_URL_CREATED_AT_BUCKETS_KEY, SHORT_URL_CREATED_AT_BUCKETS_TABLE_NAME,
+        SHORT_URL_DESTINATION_HISTORY_TABLE_NAME, SHORT_URL_LAST_ACCESS_TABLE_NAME,
+        SHORT_URL_STATE_TABLE_NAME, SHORT_URL_TABLE_NAME, SHORT_URL_TOMBSTONES_TABLE_NAME,
+        SHORT_URLS_BY_CREATED_AT_TABLE_NAME, lwt_applied,
+    },
+    rate_limit::RATE_LIMIT_BUCKETS_TABLE_NAME,
+};
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use const_format::formatcp;
+use scylla::client::session::Session;
+use scylla::response::{PagingState, PagingStateResponse};
+use scylla::statement::unprepared::Statement;
+use std::{
+    collections::{BTreeSet, HashSet},
+    future::Future,
+    pin::Pin,
+    time::Duration,
+};
+
+type MigrationFn = for<'a> fn(&'a Session) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+pub enum Step {
+    /// A single CQL statement. Schema changes must be idempotent
+    /// (`IF NOT EXISTS`), since a replica can die between applying a step and
+    /// recording the migration.
+    Cql(&'static str),
+    /// A data migration that cannot be expressed as a single statement.
+    Run(MigrationFn),
+}
+
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    pub steps: &'static [Step],
+}
+
+/// Applied in ascending `version` order, each exactly once per keyspace.
+/// Never edit or reorder an entry once it has shipped; add a new one instead.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        steps: &[
+            Step::Cql(CREATE_SHORT_URL_TABLE_QUERY),
+            Step::Cql(CREATE_SHORT_URL_STATE_TABLE_QUERY),
+            Step::Cql(CREATE_SHORT_URL_LAST_ACCESS_TABLE_QUERY),
+            Step::Cql(CREATE_SHORT_URL_CREATE_LOGS_TABLE_QUERY),
+            Step::Cql(CREATE_SHORT_URL_ACCESS_LOGS_TABLE_QUERY),
+            Step::Cql(CREATE_SHORT_URLS_BY_CREATED_AT_TABLE_QUERY),
+            Step::Cql(CREATE_SHORT_URL_CREATE_META_TABLE_QUERY),
+            Step::Cql(CREATE_ID_SEQ_TABLE_QUERY),
+            Step::Cql(INIT_ID_SEQ_QUERY),
+        ],
+    },
+    Migration {
+        version: 2,
+        name: "backfill_short_urls_by_created_at",
+        steps: &[Step::Run(|session| {
+            Box::pin(backfill_short_urls_by_created_at(session))
+        })],
+    },
+    Migration {
+        version: 3,
+        name: "rate_limit_buckets",
+        steps: &[Step::Cql(CREATE_RATE_LIMIT_BUCKETS_TABLE_QUERY)],
+    },
+    Migration {
+        version: 4,
+        name: "monthly_short_urls_by_created_at_buckets",
+        steps: &[
+            Step::Cql(CREATE_SHORT_URL_CREATED_AT_BUCKETS_TABLE_QUERY),
+            Step::Run(|session| Box::pin(split_short_urls_by_created_at_into_months(session))),
+        ],
+    },
+    Migration {
+        version: 5,
+        name: "access_logs_by_day",
+        steps: &[
+            Step::Cql(CREATE_SHORT_URL_ACCESS_LOGS_BY_DAY_TABLE_QUERY),
+            Step::Cql(CREATE_SHORT_URL_ACCESS_LOG_DAYS_TABLE_QUERY),
+            Step::Run(|session| Box::pin(copy_access_logs_into_days(session))),
+        ],
+    },
+    Migration {
+        version: 6,
+        name: "destination_history",
+        steps: &[Step::Cql(CREATE_SHORT_URL_DESTINATION_HISTORY_TABLE_QUERY)],
+    },
+    Migration {
+        version: 7,
+        name: "tombstones",
+        steps: &[Step::Cql(CREATE_SHORT_URL_TOMBSTONES_TABLE_QUERY)],
+    },
+    Migration {
+        version: 8,
+        name: "admin_audit_log",
+        steps: &[
+            Step::Cql(CREATE_ADMIN_AUDIT_LOG_TABLE_QUERY),
+            Step::Cql(CREATE_ADMIN_AUDIT_LOG_BY_LINK_TABLE_QUERY),
+            Step::Cql(CREATE_ADMIN_AUDIT_LOG_BY_ACTOR_TABLE_QUERY),
+        ],
+    },
+    Migration {
+        version: PERSIST_CREATE_META_VERSION,
+        name: "persist_create_meta",
+        steps: &[Step::Run(|session| Box::pin(persist_create_meta(session)))],
+    },
+];
+
+const CREATE_SHORT_URL_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {SHORT_URL_TABLE_NAME} (
+        id text,
+        original_url text,
+        created_at timestamp,
+        expires_at timestamp,
+        PRIMARY KEY (id)
+    )
+"#,
+);
+const CREATE_SHORT_URL_STATE_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {SHORT_URL_STATE_TABLE_NAME} (
+        id text,
+        enabled boolean,
+        disabled_at timestamp,
+        updated_at timestamp,
+        PRIMARY KEY (id)
+    )
+"#
+);
+const CREATE_SHORT_URL_LAST_ACCESS_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {SHORT_URL_LAST_ACCESS_TABLE_NAME} (
+        id text,
+        last_access_at timestamp,
+        last_status_code int,
+        PRIMARY KEY (id)
+    )
+"#
+);
+const CREATE_SHORT_URL_CREATE_LOGS_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {SHORT_URL_CREATE_LOGS_TABLE_NAME} (
+        id text,
+        ts timestamp,
+        ip text,
+        user_agent text,
+        original_url text,
+        request_id text,
+        PRIMARY KEY (id, ts)
+    ) WITH CLUSTERING ORDER BY (ts DESC)
+"#
+);
+/// Superseded by `short_url_access_logs_by_day` in migration 5. The table is
+/// left in place; its rows expire through their TTL.
+pub(crate) const SHORT_URL_ACCESS_LOGS_TABLE_NAME: &str = "short_url_access_logs";
+const CREATE_SHORT_URL_ACCESS_LOGS_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {SHORT_URL_ACCESS_LOGS_TABLE_NAME} (
+        id text,
+        ts timestamp,
+        ip text,
+        user_agent text,
+        request_id text,
+        status_code int,
+        PRIMARY KEY (id, ts)
+    ) WITH CLUSTERING ORDER BY (ts DESC)
+"#
+);
+const CREATE_SHORT_URLS_BY_CREATED_AT_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {SHORT_URLS_BY_CREATED_AT_TABLE_NAME} (
+        bucket text,
+        created_at timestamp,
+        id text,
+        original_url text,
+        expires_at timestamp,
+        PRIMARY KEY (bucket, created_at, id)
+    ) WITH CLUSTERING ORDER BY (created_at DESC, id ASC)
+"#
+);
+const CREATE_SHORT_URL_CREATE_META_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {SHORT_URL_CREATE_META_TABLE_NAME} (
+        id text,
+        created_at timestamp,
+        ip text,
+        user_agent text,
+        request_id text,
+        PRIMARY KEY (id)
+    )
+"#
+);
+const CREATE_ID_SEQ_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {ID_SEQ_TABLE_NAME} (
+        name text,
+        current_id bigint,
+        PRIMARY KEY (name)
+    )
+"#,
+);
+const INIT_ID_SEQ_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {ID_SEQ_TABLE_NAME} (name, current_id) VALUES ('{ID_SEQ_KEY_NAME}', 0) IF NOT EXISTS
+"#,
+);
+const CREATE_RATE_LIMIT_BUCKETS_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {RATE_LIMIT_BUCKETS_TABLE_NAME} (
+        scope text,
+        key text,
+        tokens double,
+        updated_at timestamp,
+        PRIMARY KEY ((scope, key))
+    )
+"#
+);
+
+/// The bucket used by `short_urls_by_created_at` when migration 2 shipped.
+const BACKFILL_BUCKET: &str = "all";
+const BACKFILL_PAGE_SIZE: i32 = 500;
+const BACKFILL_LIST_URLS_QUERY: &str = formatcp!(
+    r#"
+    SELECT id, original_url, created_at, expires_at FROM {SHORT_URL_TABLE_NAME}
+"#,
+);
+const BACKFILL_INSERT_URL_BY_CREATED_AT_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {SHORT_URLS_BY_CREATED_AT_TABLE_NAME} (bucket, created_at, id, original_url, expires_at)
+    VALUES (?, ?, ?, ?, ?) IF NOT EXISTS
+"#
+);
+
+async fn backfill_short_urls_by_created_at(session: &Session) -> Result<()> {
+    let list = Statement::new(BACKFILL_LIST_URLS_QUERY).with_page_size(BACKFILL_PAGE_SIZE);
+    let insert = session
+        .prepare(BACKFILL_INSERT_URL_BY_CREATED_AT_QUERY)
+        .await?;
+
+    let mut paging_state = PagingState::start();
+    loop {
+        let (result, paging_state_response) = session
+            .query_single_page(list.clone(), &[], paging_state)
+            .await?;
+        let rows = result.into_rows_result()?;
+        for row in rows.rows::<(String, String, DateTime<Utc>, Option<DateTime<Utc>>)>()? {
+            let (id, original_url, created_at, expires_at) = row?;
+            session
+                .execute_unpaged(
+                    &insert,
+                    (BACKFILL_BUCKET, created_at, id, original_url, expires_at),
+                )
+                .await?;
+        }
+        match paging_state_response {
+            PagingStateResponse::HasMorePages { state } => paging_state = state,
+            PagingStateResponse::NoMorePages => return Ok(()),
+        }
+    }
+}
+
+const CREATE_SHORT_URL_CREATED_AT_BUCKETS_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {SHORT_URL_CREATED_AT_BUCKETS_TABLE_NAME} (
+        name text,
+        bucket text,
+        PRIMARY KEY (name, bucket)
+    ) WITH CLUSTERING ORDER BY (bucket DESC)
+"#
+);
+const SPLIT_LIST_UNBUCKETED_QUERY: &str = formatcp!(
+    r#"
+    SELECT created_at, id, original_url, expires_at FROM {SHORT_URLS_BY_CREATED_AT_TABLE_NAME}
+    WHERE bucket = '{BACKFILL_BUCKET}'
+"#
+);
+const SPLIT_INSERT_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {SHORT_URLS_BY_CREATED_AT_TABLE_NAME} (bucket, created_at, id, original_url, expires_at)
+    VALUES (?, ?, ?, ?, ?)
+"#
+);
+const SPLIT_REGISTER_BUCKET_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {SHORT_URL_CREATED_AT_BUCKETS_TABLE_NAME} (name, bucket)
+    VALUES ('{SHORT_URL_CREATED_AT_BUCKETS_KEY}', ?)
+"#
+);
+const SPLIT_DELETE_UNBUCKETED_ROW_QUERY: &str = formatcp!(
+    r#"
+    DELETE FROM {SHORT_URLS_BY_CREATED_AT_TABLE_NAME}
+    WHERE bucket = '{BACKFILL_BUCKET}' AND created_at = ? AND id = ?
+"#
+);
+
+/// Moves every row of the single `all` partition into its monthly bucket.
+/// Only rows that were copied are deleted, so links that older replicas
+/// write to `all` during a rolling deploy stay there until `reconcile`
+/// restores their bucketed rows.
+async fn split_short_urls_by_created_at_into_months(session: &Session) -> Result<()> {
+    let list = Statement::new(SPLIT_LIST_UNBUCKETED_QUERY).with_page_size(BACKFILL_PAGE_SIZE);
+    let insert = session.prepare(SPLIT_INSERT_QUERY).await?;
+    let register = session.prepare(SPLIT_REGISTER_BUCKET_QUERY).await?;
+    let delete = session.prepare(SPLIT_DELETE_UNBUCKETED_ROW_QUERY).await?;
+
+    let mut buckets = BTreeSet::new();
+    let mut paging_state = PagingState::start();
+    loop {
+        let (result, paging_state_response) = session
+            .query_single_page(list.clone(), &[], paging_state)
+            .await?;
+        let rows = result.into_rows_result()?;
+        for row in rows.rows::<(DateTime<Utc>, String, String, Option<DateTime<Utc>>)>()? {
+            let (created_at, id, original_url, expires_at) = row?;
+            let bucket = month_bucket(created_at);
+            // Register the bucket before the row leaves `all`, so that the
+            // listing can always reach it.
+            if !buckets.contains(&bucket) {
+                session
+                    .execute_unpaged(&register, (bucket.as_str(),))
+                    .await?;
+                buckets.insert(bucket.clone());
+            }
+            session
+                .execute_unpaged(
+                    &insert,
+                    (
+                        bucket.as_str(),
+                        created_at,
+                        id.as_str(),
+                        original_url,
+                        expires_at,
+                    ),
+                )
+                .await?;
+            session
+                .execute_unpaged(&delete, (created_at, id.as_str()))
+                .await?;
+        }
+        match paging_state_response {
+            PagingStateResponse::HasMorePages { state } => paging_state = state,
+            PagingStateResponse::NoMorePages => return Ok(()),
+        }
+    }
+}
+
+const CREATE_SHORT_URL_ACCESS_LOGS_BY_DAY_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {SHORT_URL_ACCESS_LOGS_BY_DAY_TABLE_NAME} (
+        id text,
+        day text,
+        ts timestamp,
+        ip text,
+        user_agent text,
+        request_id text,
+        status_code int,
+        PRIMARY KEY ((id, day), ts)
+    ) WITH CLUSTERING ORDER BY (ts DESC)
+"#
+);
+const CREATE_SHORT_URL_ACCESS_LOG_DAYS_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {SHORT_URL_ACCESS_LOG_DAYS_TABLE_NAME} (
+        id text,
+        day text,
+        PRIMARY KEY (id, day)
+    ) WITH CLUSTERING ORDER BY (day DESC)
+"#
+);
+const COPY_LIST_ACCESS_LOGS_QUERY: &str = formatcp!(
+    r#"
+    SELECT id, ts, ip, user_agent, request_id, status_code, TTL(ip)
+    FROM {SHORT_URL_ACCESS_LOGS_TABLE_NAME}
+"#
+);
+const COPY_INSERT_ACCESS_LOG_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {SHORT_URL_ACCESS_LOGS_BY_DAY_TABLE_NAME} (id, day, ts, ip, user_agent, request_id, status_code)
+    VALUES (?, ?, ?, ?, ?, ?, ?) USING TTL ?
+"#
+);
+const COPY_INSERT_ACCESS_LOG_DAY_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {SHORT_URL_ACCESS_LOG_DAYS_TABLE_NAME} (id, day) VALUES (?, ?) USING TTL ?
+"#
+);
+
+/// The access log TTL in effect when migration 5 shipped.
+const LEGACY_LOG_TTL_SECONDS: i32 = 60 * 60 * 24 * 30;
+
+/// Copies the legacy access log into day partitions, keeping each entry's
+/// remaining TTL.
+async fn copy_access_logs_into_days(session: &Session) -> Result<()> {
+    let list = Statement::new(COPY_LIST_ACCESS_LOGS_QUERY).with_page_size(BACKFILL_PAGE_SIZE);
+    let insert = session.prepare(COPY_INSERT_ACCESS_LOG_QUERY).await?;
+    let insert_day = session.prepare(COPY_INSERT_ACCESS_LOG_DAY_QUERY).await?;
+
+    let mut days = HashSet::new();
+    let mut paging_state = PagingState::start();
+    loop {
+        let (result, paging_state_response) = session
+            .query_single_page(list.clone(), &[], paging_state)
+            .await?;
+        let rows = result.into_rows_result()?;
+        for row in rows.rows::<(
+            String,
+            DateTime<Utc>,
+            String,
+            String,
+            String,
+            i32,
+            Option<i32>,
+        )>()? {
+            let (id, ts, ip, user_agent, request_id, status_code, ttl) = row?;
+            let day = day_bucket(ts);
+            let ttl = ttl.unwrap_or(0);
+            session
+                .execute_unpaged(
+                    &insert,
+                    (
+                        id.as_str(),
+                        day.as_str(),
+                        ts,
+                        ip,
+                        user_agent,
+                        request_id,
+                        status_code,
+                        ttl,
+                    ),
+                )
+                .await?;
+            days.insert((id, day));
+        }
+        match paging_state_response {
+            PagingStateResponse::HasMorePages { state } => paging_state = state,
+            PagingStateResponse::NoMorePages => break,
+        }
+    }
+
+    // The index outlives its entries at worst, which costs an empty read.
+    for (id, day) in &days {
+        session
+            .execute_unpaged(&insert_day, (id, day, LEGACY_LOG_TTL_SECONDS))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Creator metadata was written with this TTL until migration 9 made its
+/// retention configurable, defaulting to forever.
+pub(crate) const LEGACY_CREATE_META_TTL_SECONDS: i64 = 60 * 60 * 24 * 30;
+pub(crate) const PERSIST_CREATE_META_VERSION: i32 = 9;
+const PERSIST_LIST_CREATE_META_QUERY: &str = formatcp!(
+    r#"
+    SELECT id, created_at, ip, user_agent, request_id, TTL(created_at)
+    FROM {SHORT_URL_CREATE_META_TABLE_NAME}
+"#
+);
+const PERSIST_INSERT_CREATE_META_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {SHORT_URL_CREATE_META_TABLE_NAME} (id, created_at, ip, user_agent, request_id)
+    VALUES (?, ?, ?, ?, ?)
+"#
+);
+
+/// Rewrites creator metadata still carrying the legacy TTL without one, so
+/// that it no longer vanishes. Rows that expired before this ran are gone;
+/// `DB::create_meta_aged_out` accounts for them.
+async fn persist_create_meta(session: &Session) -> Result<()> {
+    let list = Statement::new(PERSIST_LIST_CREATE_META_QUERY).with_page_size(BACKFILL_PAGE_SIZE);
+    let insert = session.prepare(PERSIST_INSERT_CREATE_META_QUERY).await?;
+
+    let mut paging_state = PagingState::start();
+    loop {
+        let (result, paging_state_response) = session
+            .query_single_page(list.clone(), &[], paging_state)
+            .await?;
+        let rows = result.into_rows_result()?;
+        for row in rows.rows::<(
+            String,
+            DateTime<Utc>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<i32>,
+        )>()? {
+            let (id, created_at, ip, user_agent, request_id, ttl) = row?;
+            if ttl.is_none() {
+                continue;
+            }
+            session
+                .execute_unpaged(&insert, (id, created_at, ip, user_agent, request_id))
+                .await?;
+        }
+        match paging_state_response {
+            PagingStateResponse::HasMorePages { state } => paging_state = state,
+            PagingStateResponse::NoMorePages => return Ok(()),
+        }
+    }
+}
+
+const SCHEMA_MIGRATIONS_TABLE_NAME: &str = "schema_migrations";
+const CREATE_SCHEMA_MIGRATIONS_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {SCHEMA_MIGRATIONS_TABLE_NAME} (
+        version int,
+        name text,
+        applied_at timestamp,
+        PRIMARY KEY (version)
+    )
+"#
+);
+const LIST_APPLIED_MIGRATIONS_QUERY: &str = formatcp!(
+    r#"
+    SELECT version FROM {SCHEMA_MIGRATIONS_TABLE_NAME}
+"#
+);
+const GET_MIGRATION_APPLIED_AT_QUERY: &str = formatcp!(
+    r#"
+    SELECT applied_at FROM {SCHEMA_MIGRATIONS_TABLE_NAME} WHERE version = ?
+"#
+);
+const RECORD_MIGRATION_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {SCHEMA_MIGRATIONS_TABLE_NAME} (version, name, applied_at) VALUES (?, ?, ?)
+"#
+);
+
+const SCHEMA_MIGRATION_LOCK_TABLE_NAME: &str = "schema_migration_lock";
+const SCHEMA_MIGRATION_LOCK_NAME: &str = "schema";
+const CREATE_SCHEMA_MIGRATION_LOCK_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {SCHEMA_MIGRATION_LOCK_TABLE_NAME} (
+        name text,
+        owner text,
+        acquired_at timestamp,
+        PRIMARY KEY (name)
+    )
+"#
+);
+const ACQUIRE_LOCK_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {SCHEMA_MIGRATION_LOCK_TABLE_NAME} (name, owner, acquired_at)
+    VALUES ('{SCHEMA_MIGRATION_LOCK_NAME}', ?, ?) IF NOT EXISTS USING TTL ?
+"#
+);
+const RENEW_LOCK_QUERY: &str = formatcp!(
+    r#"
+    UPDATE {SCHEMA_MIGRATION_LOCK_TABLE_NAME} USING TTL ?
+    SET owner = ?, acquired_at = ?
+    WHERE name = '{SCHEMA_MIGRATION_LOCK_NAME}' IF owner = ?
+"#
+);
+const RELEASE_LOCK_QUERY: &str = formatcp!(
+    r#"
+    DELETE FROM {SCHEMA_MIGRATION_LOCK_TABLE_NAME} WHERE name = '{SCHEMA_MIGRATION_LOCK_NAME}' IF owner = ?
+"#
+);
+
+/// The lock expires on its own so a replica that dies mid-migration does not
+/// block the others for long. The holder renews it every
+/// `LOCK_RENEW_INTERVAL` for as long as migrations run.
+const LOCK_TTL_SECONDS: i32 = 60;
+const LOCK_RENEW_INTERVAL: Duration = Duration::from_secs(15);
+const LOCK_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Data migrations scan whole tables, so waiters are patient. One that gives
+/// up exits and retries when it is restarted.
+const LOCK_WAIT_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+async fn await_schema_agreement(session: &Session) -> Result<()> {
+    session
+        .await_schema_agreement()
+        .await
+        .map_err(|e| anyhow!("Schema agreement not reached: {}", e))?;
+    Ok(())
+}
+
+async fn applied_versions(session: &Session) -> Result<HashSet<i32>> {
+    let rows = session
+        .query_unpaged(LIST_APPLIED_MIGRATIONS_QUERY, &[])
+        .await
+        .map_err(|e| {
+            anyhow!(
+                "Failed to read table '{}': {}",
+                SCHEMA_MIGRATIONS_TABLE_NAME,
+                e
+            )
+        })?
+        .into_rows_result()?;
+    rows.rows::<(i32,)>()?.map(|row| Ok(row?.0)).collect()
+}
+
+/// When `version` was applied, or `None` if it has not been.
+pub(crate) async fn applied_at(session: &Session, version: i32) -> Result<Option<DateTime<Utc>>> {
+    Ok(session
+        .query_unpaged(GET_MIGRATION_APPLIED_AT_QUERY, (version,))
+        .await?
+        .into_rows_result()?
+        .maybe_first_row::<(DateTime<Utc>,)>()?
+        .map(|(applied_at,)| applied_at))
+}
+
+fn pending(applied: &HashSet<i32>) -> Vec<&'static Migration> {
+    MIGRATIONS
+        .iter()
+        .filter(|m| !applied.contains(&m.version))
+        .collect()
+}
+
+struct MigrationLock {
+    owner: String,
+}
+
+impl MigrationLock {
+    async fn try_acquire(session: &Session) -> Result<Option<Self>> {
+        let owner = format!("{}-{:016x}", std::process::id(), rand::random::<u64>());
+        let result = session
+            .query_unpaged(
+                ACQUIRE_LOCK_QUERY,
+                (owner.as_str(), Utc::now(), LOCK_TTL_SECONDS),
+            )
+            .await?;
+        Ok(lwt_applied(result)?.then_some(Self { owner }))
+    }
+
+    /// Whether the lock is still ours; its TTL starts over if so.
+    async fn renew(&self, session: &Session) -> Result<bool> {
+        let result = session
+            .query_unpaged(
+                RENEW_LOCK_QUERY,
+                (
+                    LOCK_TTL_SECONDS,
+                    self.owner.as_str(),
+                    Utc::now(),
+                    self.owner.as_str(),
+                ),
+            )
+            .await?;
+        lwt_applied(result)
+    }
+
+    async fn release(self, session: &Session) {
+        if let Err(e) = session
+            .query_unpaged(RELEASE_LOCK_QUERY, (self.owner.as_str(),))
+            .await
+        {
+            tracing::warn!(error = %e, "Failed to release schema migration lock");
+        }
+    }
+}
+
+async fn apply(session: &Session, migration: &Migration) -> Result<()> {
+    for step in migration.steps {
+        match step {
+            Step::Cql(query) => {
+                session.query_unpaged(*query, &[]).await.map_err(|e| {
+                    anyhow!(
+                        "Migration {} ({}) failed: {}",
+                        migration.version,
+                        migration.name,
+                        e
+                    )
+                })?;
+                await_schema_agreement(session).await?;
+            }
+            Step::Run(run) => run(session).await.map_err(|e| {
+                anyhow!(
+                    "Migration {} ({}) failed: {}",
+                    migration.version,
+                    migration.name,
+                    e
+                )
+            })?,
+        }
+    }
+    session
+        .query_unpaged(
+            RECORD_MIGRATION_QUERY,
+            (migration.version, migration.name, Utc::now()),
+        )
+        .await?;
+    tracing::info!(
+        version = migration.version,
+        name = migration.name,
+        "Applied ScyllaDB migration"
+    );
+    Ok(())
+}
+
+/// Brings the keyspace up to date. Replicas starting together serialize on a
+/// lock row; the ones that wait find nothing left to do once it is released.
+pub async fn run(session: &Session) -> Result<()> {
+    for query in [
+        CREATE_SCHEMA_MIGRATIONS_TABLE_QUERY,
+        CREATE_SCHEMA_MIGRATION_LOCK_TABLE_QUERY,
+    ] {
+        session
+            .query_unpaged(query, &[])
+            .await
+            .map_err(|e| anyhow!("Failed to create migration tables: {}", e))?;
+    }
+    await_schema_agreement(session).await?;
+
+    let lock = acquire_unless_done(
+        async || Ok(pending(&applied_versions(session).await?).is_empty()),
+        async || MigrationLock::try_acquire(session).await,
+        LOCK_POLL_INTERVAL,
+        LOCK_WAIT_TIMEOUT,
+    )
+    .await?;
+    let Some(lock) = lock else {
+        return Ok(());
+    };
+
+    let migrate = async {
+        // Re-read under the lock: another replica may have finished meanwhile.
+        for migration in pending(&applied_versions(session).await?) {
+            apply(session, migration).await?;
+        }
+        Ok(())
+    };
+    let result = while_renewed(
+        migrate,
+        async || lock.renew(session).await,
+        LOCK_RENEW_INTERVAL,
+        Duration::from_secs(LOCK_TTL_SECONDS as u64),
+    )
+    .await;
+    lock.release(session).await;
+    result
+}
+
+/// Polls until there is nothing left to migrate, returning `None`, or until
+/// `try_acquire` takes the lock.
+async fn acquire_unless_done<L>(
+    mut done: impl AsyncFnMut() -> Result<bool>,
+    mut try_acquire: impl AsyncFnMut() -> Result<Option<L>>,
+    poll_interval: Duration,
+    wait_timeout: Duration,
+) -> Result<Option<L>> {
+    let started = tokio::time::Instant::now();
+    loop {
+        if done().await? {
+            return Ok(None);
+        }
+        if let Some(lock) = try_acquire().await? {
+            return Ok(Some(lock));
+        }
+        if started.elapsed() > wait_timeout {
+            bail!("Timed out waiting for the schema migration lock");
+        }
+        tracing::info!("Waiting for another replica to finish schema migrations");
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+/// Runs `work` while renewing the lock every `renew_interval`. `work` is
+/// dropped, and so stops at its next await, once another replica owns the
+/// lock or renewals have failed for long enough that the lock may have
+/// expired.
+async fn while_renewed<T>(
+    work: impl Future<Output = Result<T>>,
+    mut renew: impl AsyncFnMut() -> Result<bool>,
+    renew_interval: Duration,
+    ttl: Duration,
+) -> Result<T> {
+    let heartbeat = async {
+        let mut renewed_at = tokio::time::Instant::now();
+        loop {
+            tokio::time::sleep(renew_interval).await;
+            match renew().await {
+                Ok(true) => renewed_at = tokio::time::Instant::now(),
+                Ok(false) => return anyhow!("Lost the schema migration lock to another replica"),
+                Err(e) if renewed_at.elapsed() + renew_interval >= ttl => {
+                    return anyhow!("Failed to renew the schema migration lock: {}", e);
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to renew schema migration lock; retrying");
+                }
+            }
+        }
+    };
+    tokio::select! {
+        result = work => result,
+        e = heartbeat => Err(e),
+    }
+}
+
+/// Fails if any migration has not been applied, for deployments that run
+/// migrations as a separate step.
+pub async fn ensure_up_to_date(session: &Session) -> Result<()> {
+    let applied = applied_versions(session)
+        .await
+        .map_err(|e| anyhow!("{}; run with --migrate-only first", e))?;
+    let pending = pending(&applied);
+    if !pending.is_empty() {
+        let versions = pending
+            .iter()
+            .map(|m| m.version.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        bail!(
+            "ScyllaDB migrations not applied: {}; run with --migrate-only first",
+            versions
+        );
+    }
+    Ok(())
+}
+
+const CREATE_SHORT_URL_DESTINATION_HISTORY_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {SHORT_URL_DESTINATION_HISTORY_TABLE_NAME} (
+        id text,
+        changed_at timestamp,
+        previous_url text,
+        new_url text,
+        changed_by text,
+        PRIMARY KEY (id, changed_at)
+    ) WITH CLUSTERING ORDER BY (changed_at DESC)
+"#
+);
+
+const CREATE_SHORT_URL_TOMBSTONES_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {SHORT_URL_TOMBSTONES_TABLE_NAME} (
+        id text,
+        deleted_at timestamp,
+        deleted_by text,
+        PRIMARY KEY (id)
+    )
+"#
+);
+
+const CREATE_ADMIN_AUDIT_LOG_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {ADMIN_AUDIT_LOG_TABLE_NAME} (
+        bucket text,
+        ts timestamp,
+        entry_id text,
+        actor text,
+        action text,
+        link_id text,
+        reason text,
+        request_id text,
+        before_state text,
+        after_state text,
+        PRIMARY KEY (bucket, ts, entry_id)
+    ) WITH CLUSTERING ORDER BY (ts DESC, entry_id DESC)
+"#
+);
+const CREATE_ADMIN_AUDIT_LOG_BY_LINK_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {ADMIN_AUDIT_LOG_BY_LINK_TABLE_NAME} (
+        bucket text,
+        ts timestamp,
+        entry_id text,
+        actor text,
+        action text,
+        link_id text,
+        reason text,
+        request_id text,
+        before_state text,
+        after_state text,
+        PRIMARY KEY ((link_id, bucket), ts, entry_id)
+    ) WITH CLUSTERING ORDER BY (ts DESC, entry_id DESC)
+"#
+);
+const CREATE_ADMIN_AUDIT_LOG_BY_ACTOR_TABLE_QUERY: &str = formatcp!(
+    r#"
+    CREATE TABLE IF NOT EXISTS {ADMIN_AUDIT_LOG_BY_ACTOR_TABLE_NAME} (
+        bucket text,
+        ts timestamp,
+        entry_id text,
+        actor text,
+        action text,
+        link_id text,
+        reason text,
+        request_id text,
+        before_state text,
+        after_state text,
+        PRIMARY KEY ((actor, bucket), ts, entry_id)
+    ) WITH CLUSTERING ORDER BY (ts DESC, entry_id DESC)
+"#
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        assert!(MIGRATIONS.windows(2).all(|w| w[0].version < w[1].version));
+        assert_eq!(MIGRATIONS[0].version, 1);
+
+        let applied = HashSet::from([1, 3]);
+        let pending = pending(&applied);
+        assert_eq!(pending.len(), MIGRATIONS.len() - 2);
+        assert_eq!(pending[0].version, 2);
+    }
+
+    const TICK: Duration = Duration::from_millis(5);
+
+    #[tokio::test]
+    async fn test_acquire_unless_done() {
+        // Nothing pending: the lock is never touched.
+        let mut attempts = 0;
+        let lock = acquire_unless_done(
+            async || Ok(true),
+            async || {
+                attempts += 1;
+                Ok(Some(()))
+            },
+            TICK,
+            Duration::from_secs(1),
+        )
+        .await
+        .unwrap();
+        assert_eq!(lock, None);
+        assert_eq!(attempts, 0);
+
+        // Busy twice, then free.
+        let mut attempts = 0;
+        let lock = acquire_unless_done(
+            async || Ok(false),
+            async || {
+                attempts += 1;
+                Ok((attempts == 3).then_some(attempts))
+            },
+            TICK,
+            Duration::from_secs(1),
+        )
+        .await
+        .unwrap();
+        assert_eq!(lock, Some(3));
+
+        // The holder finishes while we wait.
+        let mut checks = 0;
+        let lock = acquire_unless_done(
+            async || {
+                checks += 1;
+                Ok(checks > 2)
+            },
+            async || Ok(None::<()>),
+            TICK,
+            Duration::from_secs(1),
+        )
+        .await
+        .unwrap();
+        assert_eq!(lock, None);
+
+        let err = acquire_unless_done(async || Ok(false), async || Ok(None::<()>), TICK, TICK * 3)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_while_renewed() {
+        let mut renewals = 0;
+        let value = while_renewed(
+            async {
+                tokio::time::sleep(TICK * 5).await;
+                Ok(7)
+            },
+            async || {
+                renewals += 1;
+                Ok(true)
+            },
+            TICK,
+            TICK * 4,
+        )
+        .await
+        .unwrap();
+        assert_eq!(value, 7);
+        assert!(renewals > 0);
+
+        let err = while_renewed(
+            std::future::pending::<Result<()>>(),
+            async || Ok(false),
+            TICK,
+            TICK * 4,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("Lost"));
+
+        // A failed renewal is retried while the lock may still be alive.
+        let mut renewals = 0;
+        let value = while_renewed(
+            async {
+                tokio::time::sleep(TICK * 5).await;
+                Ok(())
+            },
+            async || {
+                renewals += 1;
+                if renewals == 1 {
+                    bail!("timeout")
+                }
+                Ok(true)
+            },
+            TICK,
+            TICK * 4,
+        )
+        .await;
+        assert!(value.is_ok());
+
+        let err = while_renewed(
+            std::future::pending::<Result<()>>(),
+            async || bail!("timeout"),
+            TICK,
+            TICK * 4,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("Failed to renew"));
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{
+    rate_limit::limiter::{Bucket, Decision, Quota},
+    scylla::db::{DB, lwt_applied},
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use const_format::formatcp;
+use scylla::statement::{prepared::PreparedStatement, unprepared::Statement};
+use std::{sync::Arc, time::Duration};
+
+pub(crate) const RATE_LIMIT_BUCKETS_TABLE_NAME: &str = "rate_limit_buckets";
+const GET_BUCKET_QUERY: &str = formatcp!(
+    r#"
+    SELECT tokens, updated_at FROM {RATE_LIMIT_BUCKETS_TABLE_NAME} WHERE scope = ? AND key = ?
+"#
+);
+const INSERT_BUCKET_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {RATE_LIMIT_BUCKETS_TABLE_NAME} (scope, key, tokens, updated_at)
+    VALUES (?, ?, ?, ?) IF NOT EXISTS USING TTL ?
+"#
+);
+const UPDATE_BUCKET_QUERY: &str = formatcp!(
+    r#"
+    UPDATE {RATE_LIMIT_BUCKETS_TABLE_NAME} USING TTL ? SET tokens = ?, updated_at = ?
+    WHERE scope = ? AND key = ? IF updated_at = ?
+"#
+);
+
+const MAX_CAS_ATTEMPTS: usize = 3;
+
+/// Token buckets shared by every replica. Each state change is a
+/// compare-and-set on `updated_at`, so concurrent takes cannot both spend the
+/// same token.
+pub struct ScyllaBuckets {
+    db: Arc<DB>,
+    ps_get_bucket: PreparedStatement,
+    ps_insert_bucket: PreparedStatement,
+    ps_update_bucket: PreparedStatement,
+}
+
+impl ScyllaBuckets {
+    pub async fn new(db: Arc<DB>) -> Result<Self> {
+        let ps_get_bucket =
+            DB::prepare_statement(&db.session, Statement::new(GET_BUCKET_QUERY)).await?;
+        let ps_insert_bucket =
+            DB::prepare_statement(&db.session, Statement::new(INSERT_BUCKET_QUERY)).await?;
+        let ps_update_bucket =
+            DB::prepare_statement(&db.session, Statement::new(UPDATE_BUCKET_QUERY)).await?;
+
+        Ok(Self {
+            db,
+            ps_get_bucket,
+            ps_insert_bucket,
+            ps_update_bucket,
+        })
+    }
+
+    pub async fn acquire(
+        &self,
+        scope: &str,
+        key: &str,
+        quota: &Quota,
+        now: DateTime<Utc>,
+    ) -> Result<Decision> {
+        let ttl = quota.idle_expiry().as_secs().saturating_add(60) as i32;
+
+        for _ in 0..MAX_CAS_ATTEMPTS {
+            let current = self
+                .db
+                .session
+                .execute_unpaged(&self.ps_get_bucket, (scope, key))
+                .await?
+                .into_rows_result()?
+                .maybe_first_row::<(f64, DateTime<Utc>)>()?;
+
+            let mut bucket = match current {
+                Some((tokens, updated_at)) => Bucket { tokens, updated_at },
+                None => Bucket::full(quota, now),
+            };
+            let decision = bucket.take(quota, now);
+            if decision != Decision::Allowed {
+                return Ok(decision);
+            }
+
+            let result = match current {
+                Some((_, previous_updated_at)) => {
+                    self.db
+                        .session
+                        .execute_unpaged(
+                            &self.ps_update_bucket,
+                            (
+                                ttl,
+                                bucket.tokens,
+                                bucket.updated_at,
+                                scope,
+                                key,
+                                previous_updated_at,
+                            ),
+                        )
+                        .await?
+                }
+                None => {
+                    self.db
+                        .session
+                        .execute_unpaged(
+                            &self.ps_insert_bucket,
+                            (scope, key, bucket.tokens, bucket.updated_at, ttl),
+                        )
+                        .await?
+                }
+            };
+            if lwt_applied(result)? {
+                return Ok(Decision::Allowed);
+            }
+        }
+
+        // Heavy contention on a single key is itself a sign of abuse.
+        Ok(Decision::Limited {
+            retry_after: Duration::from_secs(1),
+        })
+    }
+}
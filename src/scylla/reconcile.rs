@@ -0,0 +1,145 @@
+use crate::scylla::{
+    buckets::month_bucket,
+    db::{
+        DB, SHORT_URL_STATE_TABLE_NAME, SHORT_URL_TABLE_NAME, SHORT_URLS_BY_CREATED_AT_TABLE_NAME,
+    },
+};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use const_format::formatcp;
+use scylla::response::{PagingState, PagingStateResponse};
+use scylla::statement::unprepared::Statement;
+
+const SCAN_PAGE_SIZE: i32 = 500;
+const SCAN_URLS_QUERY: &str = formatcp!(
+    r#"
+    SELECT id, original_url, created_at, expires_at FROM {SHORT_URL_TABLE_NAME}
+"#
+);
+const FIND_LISTING_ROW_QUERY: &str = formatcp!(
+    r#"
+    SELECT original_url FROM {SHORT_URLS_BY_CREATED_AT_TABLE_NAME} WHERE bucket = ? AND created_at = ? AND id = ?
+"#
+);
+/// Conditional, so a link disabled while the scan runs is not re-enabled.
+const INSERT_STATE_IF_ABSENT_QUERY: &str = formatcp!(
+    r#"
+    INSERT INTO {SHORT_URL_STATE_TABLE_NAME} (id, enabled, disabled_at, updated_at)
+    VALUES (?, true, null, ?) IF NOT EXISTS
+"#
+);
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReconcileReport {
+    pub scanned: u64,
+    /// Links with no `short_url_state` row.
+    pub missing_state: u64,
+    /// Links absent from `short_urls_by_created_at`, and so from the admin list.
+    pub missing_listing: u64,
+    /// Links whose listing row shows another destination than `short_urls`,
+    /// left by a destination edit that failed halfway.
+    pub stale_listing: u64,
+    pub repaired: u64,
+}
+
+/// Scans every link for rows `create` writes after `short_urls`, and for
+/// listing rows with an outdated destination. When `repair` is set, writes
+/// the missing rows and fixes the outdated ones. Safe to run while serving.
+pub async fn reconcile_links(db: &DB, repair: bool) -> Result<ReconcileReport> {
+    let scan = Statement::new(SCAN_URLS_QUERY).with_page_size(SCAN_PAGE_SIZE);
+    let find_listing_row =
+        DB::prepare_statement(&db.session, Statement::new(FIND_LISTING_ROW_QUERY)).await?;
+    let insert_state =
+        DB::prepare_statement(&db.session, Statement::new(INSERT_STATE_IF_ABSENT_QUERY)).await?;
+
+    let mut report = ReconcileReport::default();
+    let mut paging_state = PagingState::start();
+    loop {
+        let (result, paging_state_response) = db
+            .session
+            .query_single_page(scan.clone(), &[], paging_state)
+            .await?;
+        let rows = result.into_rows_result()?;
+        for row in rows.rows::<(String, String, DateTime<Utc>, Option<DateTime<Utc>>)>()? {
+            let (id, original_url, created_at, expires_at) =
+                row.map_err(|e| anyhow!("Failed to decode link during reconciliation: {}", e))?;
+            report.scanned += 1;
+
+            let has_state = db
+                .session
+                .execute_unpaged(&db.ps_get_state, (id.as_str(),))
+                .await?
+                .into_rows_result()?
+                .rows_num()
+                > 0;
+            if !has_state {
+                report.missing_state += 1;
+                tracing::warn!(id = id.as_str(), "Link is missing its state row");
+                if repair {
+                    db.session
+                        .execute_unpaged(&insert_state, (id.as_str(), created_at))
+                        .await?;
+                    report.repaired += 1;
+                }
+            }
+
+            let bucket = month_bucket(created_at);
+            let listed_url = db
+                .session
+                .execute_unpaged(
+                    &find_listing_row,
+                    (bucket.as_str(), created_at, id.as_str()),
+                )
+                .await?
+                .into_rows_result()?
+                .maybe_first_row::<(String,)>()?
+                .map(|(url,)| url);
+            if let Some(listed_url) = listed_url {
+                if listed_url != original_url {
+                    report.stale_listing += 1;
+                    tracing::warn!(
+                        id = id.as_str(),
+                        "Admin listing shows an outdated destination"
+                    );
+                    if repair {
+                        db.session
+                            .execute_unpaged(
+                                &db.ps_update_url_by_created_at_destination,
+                                (
+                                    original_url.as_str(),
+                                    bucket.as_str(),
+                                    created_at,
+                                    id.as_str(),
+                                ),
+                            )
+                            .await?;
+                        report.repaired += 1;
+                    }
+                }
+            } else {
+                report.missing_listing += 1;
+                tracing::warn!(id = id.as_str(), "Link is missing from the admin listing");
+                if repair {
+                    db.register_created_at_bucket(&bucket).await?;
+                    db.session
+                        .execute_unpaged(
+                            &db.ps_insert_url_by_created_at,
+                            (
+                                bucket.as_str(),
+                                created_at,
+                                id.as_str(),
+                                original_url,
+                                expires_at,
+                            ),
+                        )
+                        .await?;
+                    report.repaired += 1;
+                }
+            }
+        }
+        match paging_state_response {
+            PagingStateResponse::HasMorePages { state } => paging_state = state,
+            PagingStateResponse::NoMorePages => return Ok(report),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+use valuable::Valuable;
+
+use crate::scylla::migrations::LEGACY_CREATE_META_TTL_SECONDS;
+
+/// The largest TTL ScyllaDB accepts (20 years).
+const MAX_TTL_SECONDS: u32 = 20 * 365 * 24 * 60 * 60;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RetentionError {
+    #[error("invalid retention '{0}', expected 'forever' or a number followed by s, m, h or d")]
+    Invalid(String),
+    #[error("retention must be positive; use 'forever' to keep rows indefinitely")]
+    Zero,
+    #[error("retention exceeds the maximum TTL of {MAX_TTL_SECONDS} seconds")]
+    TooLong,
+}
+
+/// How long rows of a table are kept, written as `forever` or e.g. `30d`.
+#[derive(Debug, Valuable, Clone, Copy, PartialEq, Eq)]
+pub enum Retention {
+    Forever,
+    Seconds(u32),
+}
+
+impl Retention {
+    /// The value for `USING TTL`, where 0 means the row never expires.
+    pub fn ttl_seconds(self) -> i32 {
+        match self {
+            Retention::Forever => 0,
+            Retention::Seconds(seconds) => seconds as i32,
+        }
+    }
+
+    pub fn as_duration(self) -> Option<chrono::Duration> {
+        match self {
+            Retention::Forever => None,
+            Retention::Seconds(seconds) => Some(chrono::Duration::seconds(seconds.into())),
+        }
+    }
+}
+
+impl FromStr for Retention {
+    type Err = RetentionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("forever") {
+            return Ok(Retention::Forever);
+        }
+
+        let invalid = || RetentionError::Invalid(s.to_string());
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (amount, unit) = s.split_at(split);
+        let amount = amount.parse::<u64>().map_err(|_| invalid())?;
+        let multiplier = match unit {
+            "" | "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+
+        let seconds = amount
+            .checked_mul(multiplier)
+            .ok_or(RetentionError::TooLong)?;
+        if seconds == 0 {
+            return Err(RetentionError::Zero);
+        }
+        if seconds > MAX_TTL_SECONDS.into() {
+            return Err(RetentionError::TooLong);
+        }
+        Ok(Retention::Seconds(seconds as u32))
+    }
+}
+
+/// Whether creator metadata of a link created at `created_at` has expired:
+/// under `retention`, or under the legacy 30-day TTL by the time `cutover`
+/// stopped it from applying.
+pub fn create_meta_aged_out(
+    created_at: DateTime<Utc>,
+    retention: Option<chrono::Duration>,
+    cutover: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> bool {
+    let legacy_ttl = chrono::Duration::seconds(LEGACY_CREATE_META_TTL_SECONDS);
+    retention.is_some_and(|retention| created_at + retention <= now)
+        || cutover.is_some_and(|cutover| created_at + legacy_ttl <= cutover)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_retention() {
+        assert_eq!("forever".parse(), Ok(Retention::Forever));
+        assert_eq!("30d".parse(), Ok(Retention::Seconds(30 * 24 * 60 * 60)));
+        assert_eq!("90".parse(), Ok(Retention::Seconds(90)));
+        assert_eq!(Retention::Forever.ttl_seconds(), 0);
+
+        assert_eq!("0d".parse::<Retention>(), Err(RetentionError::Zero));
+        assert_eq!("7301d".parse::<Retention>(), Err(RetentionError::TooLong));
+        assert!(matches!(
+            "1w".parse::<Retention>(),
+            Err(RetentionError::Invalid(_))
+        ));
+        assert!(matches!(
+            "d".parse::<Retention>(),
+            Err(RetentionError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_create_meta_aged_out() {
+        let now = Utc::now();
+        let cutover = now - chrono::Duration::days(10);
+        let days_ago = |days| now - chrono::Duration::days(days);
+
+        // Forever: only rows that had already expired before the cutover.
+        assert!(create_meta_aged_out(days_ago(45), None, Some(cutover), now));
+        assert!(!create_meta_aged_out(
+            days_ago(35),
+            None,
+            Some(cutover),
+            now
+        ));
+        assert!(!create_meta_aged_out(days_ago(45), None, None, now));
+
+        let week = Some(chrono::Duration::days(7));
+        assert!(create_meta_aged_out(days_ago(8), week, Some(cutover), now));
+        assert!(!create_meta_aged_out(days_ago(6), week, Some(cutover), now));
+    }
+}
@@ -0,0 +1,11 @@
+use envconfig::Envconfig;
+use valuable::Valuable;
+
+#[derive(Envconfig, Debug, Valuable, Clone)]
+pub struct Config {
+    #[envconfig(from = "SQLITE_PATH", default = "walnuk.db")]
+    pub path: String,
+
+    #[envconfig(from = "SQLITE_BUSY_TIMEOUT_MS", default = "5000")]
+    pub busy_timeout_ms: u64,
+}
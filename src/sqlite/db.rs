@@ -0,0 +1,931 @@
+use crate::{
+    domain::{
+        error::RepositoryError,
+        id::{ID, generator::generator},
+        models::{
+            AccessEvent, AccessLogFilter, AuditAction, AuditEntry, AuditFilter, CreateOutcome,
+            DestinationChange, ShortUrlState, ShortenedURL,
+        },
+        repository::{AccessLogRow, CreateMeta, ShortenedURLRepository},
+    },
+    sqlite::config::Config,
+};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use url::Url;
+
+/// Each entry is applied exactly once, in order. `PRAGMA user_version` records
+/// how many of them have been applied to the database file.
+const MIGRATIONS: &[&str] = &[
+    r#"
+    CREATE TABLE short_urls (
+        id TEXT PRIMARY KEY,
+        original_url TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        expires_at INTEGER
+    );
+    CREATE INDEX short_urls_by_created_at ON short_urls (created_at DESC, id ASC);
+
+    CREATE TABLE short_url_state (
+        id TEXT PRIMARY KEY,
+        enabled INTEGER NOT NULL,
+        disabled_at INTEGER,
+        updated_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE short_url_last_access (
+        id TEXT PRIMARY KEY,
+        last_access_at INTEGER NOT NULL,
+        last_status_code INTEGER NOT NULL
+    );
+
+    CREATE TABLE short_url_create_logs (
+        id TEXT NOT NULL,
+        ts INTEGER NOT NULL,
+        ip TEXT NOT NULL,
+        user_agent TEXT NOT NULL,
+        original_url TEXT NOT NULL,
+        request_id TEXT NOT NULL
+    );
+    CREATE INDEX short_url_create_logs_by_id ON short_url_create_logs (id, ts DESC);
+
+    CREATE TABLE short_url_access_logs (
+        id TEXT NOT NULL,
+        ts INTEGER NOT NULL,
+        ip TEXT NOT NULL,
+        user_agent TEXT NOT NULL,
+        request_id TEXT NOT NULL,
+        status_code INTEGER NOT NULL
+    );
+    CREATE INDEX short_url_access_logs_by_id ON short_url_access_logs (id, ts DESC);
+
+    CREATE TABLE short_url_create_meta (
+        id TEXT PRIMARY KEY,
+        created_at INTEGER NOT NULL,
+        ip TEXT NOT NULL,
+        user_agent TEXT NOT NULL,
+        request_id TEXT NOT NULL
+    );
+
+    CREATE TABLE id_seq (
+        name TEXT PRIMARY KEY,
+        current_id INTEGER NOT NULL
+    );
+    INSERT INTO id_seq (name, current_id) VALUES ('short_url_id', 0);
+"#,
+    r#"
+    CREATE TABLE short_url_destination_history (
+        id TEXT NOT NULL,
+        changed_at INTEGER NOT NULL,
+        previous_url TEXT NOT NULL,
+        new_url TEXT NOT NULL,
+        changed_by TEXT NOT NULL
+    );
+    CREATE INDEX short_url_destination_history_by_id
+        ON short_url_destination_history (id, changed_at DESC);
+"#,
+    r#"
+    CREATE TABLE short_url_tombstones (
+        id TEXT PRIMARY KEY,
+        deleted_at INTEGER NOT NULL,
+        deleted_by TEXT NOT NULL
+    );
+"#,
+    r#"
+    CREATE TABLE admin_audit_log (
+        entry_id TEXT PRIMARY KEY,
+        ts INTEGER NOT NULL,
+        actor TEXT NOT NULL,
+        action TEXT NOT NULL,
+        link_id TEXT NOT NULL,
+        reason TEXT,
+        request_id TEXT,
+        before_state TEXT,
+        after_state TEXT
+    );
+    CREATE INDEX admin_audit_log_by_ts ON admin_audit_log (ts DESC, entry_id DESC);
+    CREATE INDEX admin_audit_log_by_link ON admin_audit_log (link_id, ts DESC, entry_id DESC);
+    CREATE INDEX admin_audit_log_by_actor ON admin_audit_log (actor, ts DESC, entry_id DESC);
+"#,
+];
+
+const INSERT_URL_QUERY: &str = r#"
+    INSERT OR IGNORE INTO short_urls (id, original_url, created_at, expires_at)
+    VALUES (?1, ?2, ?3, ?4)
+"#;
+const FIND_URL_QUERY: &str = r#"
+    SELECT original_url, created_at, expires_at FROM short_urls WHERE id = ?1
+"#;
+const UPDATE_URL_DESTINATION_QUERY: &str = r#"
+    UPDATE short_urls SET original_url = ?2 WHERE id = ?1
+"#;
+const INSERT_DESTINATION_CHANGE_QUERY: &str = r#"
+    INSERT INTO short_url_destination_history (id, changed_at, previous_url, new_url, changed_by)
+    VALUES (?1, ?2, ?3, ?4, ?5)
+"#;
+const LIST_DESTINATION_HISTORY_QUERY: &str = r#"
+    SELECT changed_at, previous_url, new_url, changed_by FROM short_url_destination_history
+    WHERE id = ?1
+    ORDER BY changed_at DESC, rowid DESC
+"#;
+const FIND_TOMBSTONE_QUERY: &str = r#"
+    SELECT 1 FROM short_url_tombstones WHERE id = ?1
+"#;
+const INSERT_TOMBSTONE_QUERY: &str = r#"
+    INSERT OR IGNORE INTO short_url_tombstones (id, deleted_at, deleted_by)
+    VALUES (?1, ?2, ?3)
+"#;
+/// Every table with rows for a link, `short_urls` first so it stops resolving.
+const DELETE_LINK_QUERIES: &[&str] = &[
+    "DELETE FROM short_urls WHERE id = ?1",
+    "DELETE FROM short_url_state WHERE id = ?1",
+    "DELETE FROM short_url_last_access WHERE id = ?1",
+    "DELETE FROM short_url_create_logs WHERE id = ?1",
+    "DELETE FROM short_url_access_logs WHERE id = ?1",
+    "DELETE FROM short_url_create_meta WHERE id = ?1",
+    "DELETE FROM short_url_destination_history WHERE id = ?1",
+    // Audit entries stay, but not the destinations they captured.
+    "UPDATE admin_audit_log SET before_state = NULL, after_state = NULL WHERE link_id = ?1",
+];
+const LIST_BY_CREATED_AT_QUERY: &str = r#"
+    SELECT id, original_url, created_at, expires_at FROM short_urls
+    WHERE ?1 IS NULL OR created_at < ?1 OR (created_at = ?1 AND id > ?2)
+    ORDER BY created_at DESC, id ASC
+    LIMIT ?3
+"#;
+const NEXT_ID_QUERY: &str = r#"
+    UPDATE id_seq SET current_id = current_id + 1 WHERE name = 'short_url_id'
+    RETURNING current_id
+"#;
+const UPSERT_SHORT_URL_STATE_QUERY: &str = r#"
+    INSERT OR REPLACE INTO short_url_state (id, enabled, disabled_at, updated_at)
+    VALUES (?1, ?2, ?3, ?4)
+"#;
+const GET_SHORT_URL_STATE_QUERY: &str = r#"
+    SELECT enabled, disabled_at, updated_at FROM short_url_state WHERE id = ?1
+"#;
+const UPSERT_SHORT_URL_LAST_ACCESS_QUERY: &str = r#"
+    INSERT OR REPLACE INTO short_url_last_access (id, last_access_at, last_status_code)
+    VALUES (?1, ?2, ?3)
+"#;
+const GET_SHORT_URL_LAST_ACCESS_QUERY: &str = r#"
+    SELECT last_access_at, last_status_code FROM short_url_last_access WHERE id = ?1
+"#;
+const INSERT_CREATE_LOG_QUERY: &str = r#"
+    INSERT INTO short_url_create_logs (id, ts, ip, user_agent, original_url, request_id)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+"#;
+const INSERT_ACCESS_LOG_QUERY: &str = r#"
+    INSERT INTO short_url_access_logs (id, ts, ip, user_agent, request_id, status_code)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+"#;
+const LIST_ACCESS_LOGS_QUERY: &str = r#"
+    SELECT rowid, ts, ip, user_agent, request_id, status_code FROM short_url_access_logs
+    WHERE id = ?1
+        AND (?2 IS NULL OR ts >= ?2)
+        AND (?3 IS NULL OR ts < ?3)
+        AND (?4 IS NULL OR status_code = ?4)
+        AND (?5 IS NULL OR ts < ?5 OR (ts = ?5 AND rowid < ?6))
+    ORDER BY ts DESC, rowid DESC
+    LIMIT ?7
+"#;
+const INSERT_CREATE_META_IF_ABSENT_QUERY: &str = r#"
+    INSERT OR IGNORE INTO short_url_create_meta (id, created_at, ip, user_agent, request_id)
+    VALUES (?1, ?2, ?3, ?4, ?5)
+"#;
+const GET_CREATE_META_QUERY: &str = r#"
+    SELECT created_at, ip, user_agent, request_id FROM short_url_create_meta WHERE id = ?1
+"#;
+
+const INSERT_AUDIT_ENTRY_QUERY: &str = r#"
+    INSERT INTO admin_audit_log
+        (entry_id, ts, actor, action, link_id, reason, request_id, before_state, after_state)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+"#;
+const LIST_AUDIT_QUERY: &str = r#"
+    SELECT entry_id, ts, actor, action, link_id, reason, request_id, before_state, after_state
+    FROM admin_audit_log
+    WHERE (?1 IS NULL OR link_id = ?1)
+        AND (?2 IS NULL OR actor = ?2)
+        AND (?3 IS NULL OR ts < ?3 OR (ts = ?3 AND entry_id < ?4))
+    ORDER BY ts DESC, entry_id DESC
+    LIMIT ?5
+"#;
+
+/// Timestamps are stored as milliseconds since the epoch, matching the
+/// precision of the ScyllaDB `timestamp` type.
+fn to_millis(ts: DateTime<Utc>) -> i64 {
+    ts.timestamp_millis()
+}
+
+fn from_millis(millis: i64) -> Result<DateTime<Utc>> {
+    DateTime::from_timestamp_millis(millis).ok_or_else(|| anyhow!("Invalid timestamp {}", millis))
+}
+
+fn encode_paging_state(created_at: i64, id: &str) -> Vec<u8> {
+    format!("{}:{}", created_at, id).into_bytes()
+}
+
+fn decode_paging_state(raw: &[u8]) -> Result<(i64, String)> {
+    let raw = std::str::from_utf8(raw).map_err(|_| anyhow!("Invalid paging state"))?;
+    let (created_at, id) = raw
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Invalid paging state"))?;
+    let created_at = created_at
+        .parse::<i64>()
+        .map_err(|_| anyhow!("Invalid paging state"))?;
+    Ok((created_at, id.to_string()))
+}
+
+pub struct DB {
+    conn: Mutex<Connection>,
+}
+
+impl DB {
+    fn migrate(conn: &mut Connection) -> Result<()> {
+        let applied: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        for (version, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)
+                .map_err(|e| anyhow!("Failed to apply SQLite migration {}: {}", version + 1, e))?;
+            tx.pragma_update(None, "user_version", version + 1)?;
+            tx.commit()?;
+            tracing::info!(version = version + 1, "Applied SQLite migration");
+        }
+        Ok(())
+    }
+
+    pub fn open(config: Config) -> Result<Self> {
+        let mut conn = Connection::open(&config.path)
+            .map_err(|e| anyhow!("Failed to open SQLite database '{}': {}", config.path, e))?;
+        conn.busy_timeout(Duration::from_millis(config.busy_timeout_ms))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+
+        Self::migrate(&mut conn)?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Runs `f` on the blocking thread pool, since rusqlite calls block.
+    async fn with_conn<F, R>(self: &Arc<Self>, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut Connection) -> Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let db = Arc::clone(self);
+        tokio::task::spawn_blocking(move || {
+            let mut conn = db.conn.lock().unwrap_or_else(|e| e.into_inner());
+            f(&mut conn)
+        })
+        .await?
+    }
+}
+
+impl ShortenedURLRepository for Arc<DB> {
+    async fn ping(&self) -> Result<()> {
+        self.with_conn(|conn| {
+            conn.query_row("SELECT 1", [], |_| Ok(()))?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn create(
+        &self,
+        original_url: Url,
+        custom_id: Option<&str>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<CreateOutcome> {
+        let custom_id = custom_id.map(|cid| ID::new(cid.to_string()));
+
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+
+            let created_at = Utc::now();
+            let tombstoned = |id: &ID| {
+                tx.query_row(FIND_TOMBSTONE_QUERY, params![id.0], |_| Ok(()))
+                    .optional()
+                    .map(|row| row.is_some())
+            };
+            let insert = |id: &ID| {
+                tx.execute(
+                    INSERT_URL_QUERY,
+                    params![
+                        id.0,
+                        original_url.as_str(),
+                        to_millis(created_at),
+                        expires_at.map(to_millis)
+                    ],
+                )
+            };
+
+            let (id, inserted) = match custom_id {
+                Some(id) => {
+                    if tombstoned(&id)? {
+                        return Err(RepositoryError::IdDeleted(id.0).into());
+                    }
+                    let inserted = insert(&id)?;
+                    (id, inserted)
+                }
+                // Skips IDs that are already taken, e.g. claimed as a custom ID.
+                None => loop {
+                    let id = if generator().uses_sequence() {
+                        let seq: i64 = tx.query_row(NEXT_ID_QUERY, [], |row| row.get(0))?;
+                        ID::generate(seq)?
+                    } else {
+                        generator().random()?
+                    };
+                    if tombstoned(&id)? {
+                        continue;
+                    }
+                    let inserted = insert(&id)?;
+                    if inserted > 0 {
+                        break (id, inserted);
+                    }
+                },
+            };
+
+            let outcome = if inserted == 0 {
+                let (existing_original_url, existing_created_at, existing_expires_at) = tx
+                    .query_row(FIND_URL_QUERY, params![id.0], |row| {
+                        Ok((
+                            row.get::<_, String>(0)?,
+                            row.get::<_, i64>(1)?,
+                            row.get::<_, Option<i64>>(2)?,
+                        ))
+                    })?;
+                let existing = ShortenedURL {
+                    id,
+                    original_url: Url::parse(&existing_original_url)?,
+                    created_at: from_millis(existing_created_at)?,
+                    expires_at: existing_expires_at.map(from_millis).transpose()?,
+                };
+                CreateOutcome::from_existing(existing, &original_url)?
+            } else {
+                tx.execute(
+                    UPSERT_SHORT_URL_STATE_QUERY,
+                    params![id.0, true, Option::<i64>::None, to_millis(created_at)],
+                )?;
+                CreateOutcome::Created(ShortenedURL {
+                    id,
+                    original_url,
+                    created_at,
+                    expires_at,
+                })
+            };
+
+            tx.commit()?;
+            Ok(outcome)
+        })
+        .await
+    }
+
+    async fn find_by_id(&self, id: ID) -> Result<Option<ShortenedURL>> {
+        self.with_conn(move |conn| {
+            let row = conn
+                .query_row(FIND_URL_QUERY, params![id.0], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, Option<i64>>(2)?,
+                    ))
+                })
+                .optional()?;
+
+            let Some((original_url, created_at, expires_at)) = row else {
+                return Ok(None);
+            };
+            Ok(Some(ShortenedURL {
+                id,
+                original_url: Url::parse(&original_url)?,
+                created_at: from_millis(created_at)?,
+                expires_at: expires_at.map(from_millis).transpose()?,
+            }))
+        })
+        .await
+    }
+
+    async fn update_destination(
+        &self,
+        id: &str,
+        new_url: Url,
+        changed_by: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<ShortenedURL>> {
+        let id = ID::new(id.to_string());
+        let changed_by = changed_by.to_string();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            let row = tx
+                .query_row(FIND_URL_QUERY, params![id.0], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, Option<i64>>(2)?,
+                    ))
+                })
+                .optional()?;
+            let Some((previous_url, created_at, expires_at)) = row else {
+                return Ok(None);
+            };
+
+            if previous_url != new_url.as_str() {
+                tx.execute(
+                    UPDATE_URL_DESTINATION_QUERY,
+                    params![id.0, new_url.as_str()],
+                )?;
+                tx.execute(
+                    INSERT_DESTINATION_CHANGE_QUERY,
+                    params![
+                        id.0,
+                        to_millis(now),
+                        previous_url,
+                        new_url.as_str(),
+                        changed_by
+                    ],
+                )?;
+            }
+            tx.commit()?;
+
+            Ok(Some(ShortenedURL {
+                id,
+                original_url: new_url,
+                created_at: from_millis(created_at)?,
+                expires_at: expires_at.map(from_millis).transpose()?,
+            }))
+        })
+        .await
+    }
+
+    async fn delete(&self, id: &str, deleted_by: &str, now: DateTime<Utc>) -> Result<bool> {
+        let id = ID::new(id.to_string());
+        let deleted_by = deleted_by.to_string();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            let exists = tx
+                .query_row(FIND_URL_QUERY, params![id.0], |_| Ok(()))
+                .optional()?
+                .is_some();
+            let tombstoned = tx
+                .query_row(FIND_TOMBSTONE_QUERY, params![id.0], |_| Ok(()))
+                .optional()?
+                .is_some();
+            if !exists && !tombstoned {
+                return Ok(false);
+            }
+
+            tx.execute(
+                INSERT_TOMBSTONE_QUERY,
+                params![id.0, to_millis(now), deleted_by],
+            )?;
+            for query in DELETE_LINK_QUERIES {
+                tx.execute(query, params![id.0])?;
+            }
+            tx.commit()?;
+            Ok(true)
+        })
+        .await
+    }
+
+    async fn is_deleted(&self, id: &str) -> Result<bool> {
+        let id = ID::new(id.to_string());
+        self.with_conn(move |conn| {
+            Ok(conn
+                .query_row(FIND_TOMBSTONE_QUERY, params![id.0], |_| Ok(()))
+                .optional()?
+                .is_some())
+        })
+        .await
+    }
+
+    async fn list_destination_history(&self, id: &str) -> Result<Vec<DestinationChange>> {
+        let id = id.to_string();
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare_cached(LIST_DESTINATION_HISTORY_QUERY)?;
+            let rows = stmt
+                .query_map(params![id], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            let mut changes = Vec::with_capacity(rows.len());
+            for (changed_at, previous_url, new_url, changed_by) in rows {
+                changes.push(DestinationChange {
+                    changed_at: from_millis(changed_at)?,
+                    previous_url: Url::parse(&previous_url)?,
+                    new_url: Url::parse(&new_url)?,
+                    changed_by,
+                });
+            }
+            Ok(changes)
+        })
+        .await
+    }
+
+    async fn list_by_created_at_page(
+        &self,
+        limit: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> Result<(Vec<ShortenedURL>, Option<Vec<u8>>)> {
+        let page_size = limit.clamp(1, 100) as usize;
+        let (after_created_at, after_id) = match paging_state.as_deref() {
+            Some(raw) => {
+                let (created_at, id) = decode_paging_state(raw)?;
+                (Some(created_at), id)
+            }
+            None => (None, String::new()),
+        };
+
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare_cached(LIST_BY_CREATED_AT_QUERY)?;
+            // Fetch one extra row to find out whether there is a next page.
+            let rows = stmt
+                .query_map(
+                    params![after_created_at, after_id, page_size as i64 + 1],
+                    |row| {
+                        Ok((
+                            row.get::<_, String>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, i64>(2)?,
+                            row.get::<_, Option<i64>>(3)?,
+                        ))
+                    },
+                )?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            let has_more = rows.len() > page_size;
+            let mut out = Vec::with_capacity(page_size);
+            let mut next_page_state = None;
+            for (id, original_url, created_at, expires_at) in rows.into_iter().take(page_size) {
+                if has_more {
+                    next_page_state = Some(encode_paging_state(created_at, &id));
+                }
+                out.push(ShortenedURL {
+                    id: ID(id),
+                    original_url: Url::parse(&original_url)?,
+                    created_at: from_millis(created_at)?,
+                    expires_at: expires_at.map(from_millis).transpose()?,
+                });
+            }
+
+            Ok((out, next_page_state))
+        })
+        .await
+    }
+
+    async fn save_create_meta_if_absent(
+        &self,
+        id: &str,
+        created_at: DateTime<Utc>,
+        ip: Option<&str>,
+        user_agent: Option<&str>,
+        request_id: Option<&str>,
+    ) -> Result<()> {
+        let values = (
+            id.to_string(),
+            to_millis(created_at),
+            ip.unwrap_or("").to_string(),
+            user_agent.unwrap_or("").to_string(),
+            request_id.unwrap_or("").to_string(),
+        );
+        self.with_conn(move |conn| {
+            conn.execute(INSERT_CREATE_META_IF_ABSENT_QUERY, values)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_create_meta(&self, id: &str) -> Result<Option<CreateMeta>> {
+        let id = id.to_string();
+        self.with_conn(move |conn| {
+            let row = conn
+                .query_row(GET_CREATE_META_QUERY, params![id], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                })
+                .optional()?;
+            row.map(|(created_at, ip, ua, rid)| Ok((from_millis(created_at)?, ip, ua, rid)))
+                .transpose()
+        })
+        .await
+    }
+
+    async fn get_state(&self, id: &str) -> Result<Option<ShortUrlState>> {
+        let id = id.to_string();
+        self.with_conn(move |conn| {
+            let row = conn
+                .query_row(GET_SHORT_URL_STATE_QUERY, params![id], |row| {
+                    Ok((
+                        row.get::<_, bool>(0)?,
+                        row.get::<_, Option<i64>>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                })
+                .optional()?;
+
+            let Some((enabled, disabled_at, updated_at)) = row else {
+                return Ok(None);
+            };
+            Ok(Some(ShortUrlState {
+                id: ID::new(id),
+                enabled,
+                disabled_at: disabled_at.map(from_millis).transpose()?,
+                updated_at: from_millis(updated_at)?,
+            }))
+        })
+        .await
+    }
+
+    async fn set_enabled(&self, id: &str, enabled: bool, now: DateTime<Utc>) -> Result<()> {
+        let id = id.to_string();
+        let disabled_at = if enabled { None } else { Some(to_millis(now)) };
+        self.with_conn(move |conn| {
+            conn.execute(
+                UPSERT_SHORT_URL_STATE_QUERY,
+                params![id, enabled, disabled_at, to_millis(now)],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn log_create(
+        &self,
+        id: &str,
+        ts: DateTime<Utc>,
+        ip: Option<&str>,
+        user_agent: Option<&str>,
+        original_url: &str,
+        request_id: Option<&str>,
+    ) -> Result<()> {
+        let values = (
+            id.to_string(),
+            to_millis(ts),
+            ip.unwrap_or("").to_string(),
+            user_agent.unwrap_or("").to_string(),
+            original_url.to_string(),
+            request_id.unwrap_or("").to_string(),
+        );
+        self.with_conn(move |conn| {
+            conn.execute(INSERT_CREATE_LOG_QUERY, values)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn log_access(
+        &self,
+        id: &str,
+        ts: DateTime<Utc>,
+        ip: Option<&str>,
+        user_agent: Option<&str>,
+        request_id: Option<&str>,
+        status_code: i32,
+    ) -> Result<()> {
+        let values = (
+            id.to_string(),
+            to_millis(ts),
+            ip.unwrap_or("").to_string(),
+            user_agent.unwrap_or("").to_string(),
+            request_id.unwrap_or("").to_string(),
+            status_code,
+        );
+        self.with_conn(move |conn| {
+            conn.execute(INSERT_ACCESS_LOG_QUERY, values)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn log_access_batch(&self, events: &[AccessEvent]) -> Result<()> {
+        let events = events.to_vec();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut insert = tx.prepare_cached(INSERT_ACCESS_LOG_QUERY)?;
+                let mut upsert = tx.prepare_cached(UPSERT_SHORT_URL_LAST_ACCESS_QUERY)?;
+                for event in &events {
+                    insert.execute(params![
+                        event.id,
+                        to_millis(event.ts),
+                        event.ip.as_deref().unwrap_or(""),
+                        event.user_agent.as_deref().unwrap_or(""),
+                        event.request_id.as_deref().unwrap_or(""),
+                        event.status_code,
+                    ])?;
+                    if event.update_last_access {
+                        upsert.execute(params![
+                            event.id,
+                            to_millis(event.ts),
+                            event.status_code
+                        ])?;
+                    }
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn list_access_logs_page(
+        &self,
+        id: &str,
+        filter: &AccessLogFilter,
+        limit: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> Result<(Vec<AccessLogRow>, Option<Vec<u8>>)> {
+        let id = id.to_string();
+        let filter = *filter;
+        let page_size = limit.clamp(1, 500) as usize;
+        // Paging state is the `(ts, rowid)` key of the last returned row.
+        let after = paging_state
+            .map(|raw| {
+                let (ts, rowid) = decode_paging_state(&raw)?;
+                let rowid = rowid
+                    .parse::<i64>()
+                    .map_err(|_| anyhow!("Invalid paging state"))?;
+                Ok::<_, anyhow::Error>((ts, rowid))
+            })
+            .transpose()?;
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare_cached(LIST_ACCESS_LOGS_QUERY)?;
+            let mut rows = stmt
+                .query_map(
+                    params![
+                        id,
+                        filter.from.map(to_millis),
+                        filter.to.map(to_millis),
+                        filter.status_code,
+                        after.map(|(ts, _)| ts),
+                        after.map(|(_, rowid)| rowid),
+                        (page_size + 1) as i64,
+                    ],
+                    |row| {
+                        Ok((
+                            row.get::<_, i64>(0)?,
+                            row.get::<_, i64>(1)?,
+                            row.get::<_, String>(2)?,
+                            row.get::<_, String>(3)?,
+                            row.get::<_, String>(4)?,
+                            row.get::<_, i32>(5)?,
+                        ))
+                    },
+                )?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            let has_more = rows.len() > page_size;
+            rows.truncate(page_size);
+            let next_page_state = has_more
+                .then(|| {
+                    rows.last()
+                        .map(|(rowid, ts, ..)| encode_paging_state(*ts, &rowid.to_string()))
+                })
+                .flatten();
+
+            let rows = rows
+                .into_iter()
+                .map(|(_, ts, ip, ua, rid, status_code)| {
+                    Ok((from_millis(ts)?, ip, ua, rid, status_code))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok((rows, next_page_state))
+        })
+        .await
+    }
+
+    async fn record_audit(&self, entry: &AuditEntry) -> Result<()> {
+        let entry = entry.clone();
+        self.with_conn(move |conn| {
+            conn.execute(
+                INSERT_AUDIT_ENTRY_QUERY,
+                params![
+                    entry.id,
+                    to_millis(entry.ts),
+                    entry.actor,
+                    entry.action.to_string(),
+                    entry.link_id,
+                    entry.reason,
+                    entry.request_id,
+                    entry.before.map(|v| v.to_string()),
+                    entry.after.map(|v| v.to_string()),
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn list_audit_page(
+        &self,
+        filter: &AuditFilter,
+        limit: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> Result<(Vec<AuditEntry>, Option<Vec<u8>>)> {
+        let filter = filter.clone();
+        let page_size = limit.clamp(1, 100) as usize;
+        // Paging state is the `(ts, entry_id)` key of the last returned row.
+        let after = paging_state
+            .as_deref()
+            .map(decode_paging_state)
+            .transpose()?;
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare_cached(LIST_AUDIT_QUERY)?;
+            let mut rows = stmt
+                .query_map(
+                    params![
+                        filter.link_id,
+                        filter.actor,
+                        after.as_ref().map(|(ts, _)| *ts),
+                        after.as_ref().map(|(_, id)| id.as_str()),
+                        (page_size + 1) as i64,
+                    ],
+                    |row| {
+                        Ok((
+                            row.get::<_, String>(0)?,
+                            row.get::<_, i64>(1)?,
+                            row.get::<_, String>(2)?,
+                            row.get::<_, String>(3)?,
+                            row.get::<_, String>(4)?,
+                            row.get::<_, Option<String>>(5)?,
+                            row.get::<_, Option<String>>(6)?,
+                            row.get::<_, Option<String>>(7)?,
+                            row.get::<_, Option<String>>(8)?,
+                        ))
+                    },
+                )?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            let has_more = rows.len() > page_size;
+            rows.truncate(page_size);
+            let next_page_state = has_more
+                .then(|| rows.last().map(|(id, ts, ..)| encode_paging_state(*ts, id)))
+                .flatten();
+
+            let entries = rows
+                .into_iter()
+                .map(
+                    |(id, ts, actor, action, link_id, reason, request_id, before, after)| {
+                        Ok(AuditEntry {
+                            id,
+                            ts: from_millis(ts)?,
+                            actor,
+                            action: action
+                                .parse::<AuditAction>()
+                                .map_err(|e| anyhow!("Invalid audit action '{}': {}", action, e))?,
+                            link_id,
+                            reason,
+                            request_id,
+                            before: before.as_deref().map(serde_json::from_str).transpose()?,
+                            after: after.as_deref().map(serde_json::from_str).transpose()?,
+                        })
+                    },
+                )
+                .collect::<Result<Vec<_>>>()?;
+            Ok((entries, next_page_state))
+        })
+        .await
+    }
+
+    async fn get_last_access(&self, id: &str) -> Result<Option<(DateTime<Utc>, i32)>> {
+        let id = id.to_string();
+        self.with_conn(move |conn| {
+            let row = conn
+                .query_row(GET_SHORT_URL_LAST_ACCESS_QUERY, params![id], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, i32>(1)?))
+                })
+                .optional()?;
+            row.map(|(ts, sc)| Ok((from_millis(ts)?, sc))).transpose()
+        })
+        .await
+    }
+
+    async fn set_last_access(&self, id: &str, ts: DateTime<Utc>, status_code: i32) -> Result<()> {
+        let id = id.to_string();
+        self.with_conn(move |conn| {
+            conn.execute(
+                UPSERT_SHORT_URL_LAST_ACCESS_QUERY,
+                params![id, to_millis(ts), status_code],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+}
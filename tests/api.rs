@@ -0,0 +1,1325 @@
+use std::{collections::HashMap, sync::Arc};
+
+use actix_web::{
+    App, Error,
+    body::MessageBody,
+    dev::{ServiceFactory, ServiceRequest, ServiceResponse},
+    http::{StatusCode, header},
+    middleware::from_fn,
+    test, web,
+};
+use chrono::{Duration, Utc};
+use envconfig::Envconfig;
+use serde_json::{Value, json};
+use url::Url;
+use walnuk::{
+    auth::{authenticator::AdminAuth, config::Config as AuthConfig},
+    domain::{id::ID, repository::ShortenedURLRepository},
+    handler::{
+        config::Config as HandlerConfig,
+        handlers::{self, Handler},
+        routes,
+    },
+    memory::repository::InMemoryRepository,
+    metrics::middleware::track_http,
+    rate_limit::limiter::{LocalBuckets, Quota, RateLimiter, Store},
+};
+
+const VIEWER_TOKEN: &str = "viewer-token";
+const OPERATOR_TOKEN: &str = "operator-token";
+
+fn app(
+    repo: Arc<InMemoryRepository>,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = Error,
+        InitError = (),
+    >,
+> {
+    app_with_env(repo, &[])
+}
+
+fn app_with_env(
+    repo: Arc<InMemoryRepository>,
+    handler_env: &[(&str, &str)],
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = Error,
+        InitError = (),
+    >,
+> {
+    let handler_config = HandlerConfig::init_from_hashmap(
+        &handler_env
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+    )
+    .unwrap();
+    let auth_config = AuthConfig::init_from_hashmap(&HashMap::from([(
+        "ADMIN_STATIC_TOKENS".to_string(),
+        format!("alice:viewer:{VIEWER_TOKEN},bob:operator:{OPERATOR_TOKEN}"),
+    )]))
+    .unwrap();
+
+    App::new()
+        .wrap(from_fn(track_http))
+        .app_data(web::Data::new(Handler::new(repo, handler_config)))
+        .app_data(web::Data::new(
+            AdminAuth::from_config(&auth_config).unwrap(),
+        ))
+        .configure(routes::configure::<Arc<InMemoryRepository>>)
+}
+
+fn bearer(token: &str) -> (header::HeaderName, String) {
+    (header::AUTHORIZATION, format!("Bearer {token}"))
+}
+
+#[actix_web::test]
+async fn test_health() {
+    let app = test::init_service(app(Arc::new(InMemoryRepository::new()))).await;
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get().uri("/health/livez").to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let req = test::TestRequest::get().uri("/health/readyz").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["status"], "ready");
+    assert_eq!(body["checks"][0]["name"], "storage");
+    assert_eq!(body["checks"][0]["ok"], true);
+}
+
+#[actix_web::test]
+async fn test_readyz_fails_during_shutdown() {
+    let handler_config = HandlerConfig::init_from_hashmap(&HashMap::new()).unwrap();
+    let handler = web::Data::new(Handler::new(
+        Arc::new(InMemoryRepository::new()),
+        handler_config,
+    ));
+    let app = test::init_service(
+        App::new()
+            .app_data(handler.clone())
+            .configure(routes::configure::<Arc<InMemoryRepository>>),
+    )
+    .await;
+
+    handler.mark_shutting_down();
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get().uri("/health/readyz").to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["status"], "not_ready");
+    assert_eq!(body["checks"][0]["name"], "shutdown");
+    assert_eq!(body["checks"][0]["ok"], false);
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get().uri("/health/livez").to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_shorten_and_redirect() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let app = test::init_service(app(Arc::clone(&repo))).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/shorten")
+        .insert_header(("x-real-ip", "192.0.2.1"))
+        .set_json(json!({ "url": "https://example.com/a" }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let id = body["id"].as_str().unwrap().to_string();
+    assert!(body["expires_at"].is_null());
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get().uri(&format!("/{id}")).to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(
+        resp.headers().get(header::LOCATION).unwrap(),
+        "https://example.com/a"
+    );
+
+    let logs = repo.create_logs();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].id, id);
+    assert_eq!(logs[0].ip, "192.0.2.1");
+}
+
+#[actix_web::test]
+async fn test_shorten_rejects_invalid_urls() {
+    let app = test::init_service(app(Arc::new(InMemoryRepository::new()))).await;
+
+    for url in ["", "not a url", "ftp://example.com/"] {
+        let req = test::TestRequest::post()
+            .uri("/api/v1/shorten")
+            .set_json(json!({ "url": url }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "url: {url:?}");
+    }
+}
+
+#[actix_web::test]
+async fn test_shorten_with_custom_id() {
+    let app = test::init_service(app(Arc::new(InMemoryRepository::new()))).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/shorten")
+        .set_json(json!({ "url": "https://example.com/", "custom_id": "docs" }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["id"], "docs");
+
+    // Confusable characters are normalized on lookup.
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/d0Cs").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+}
+
+#[actix_web::test]
+async fn test_shorten_rejects_invalid_custom_ids() {
+    let app = test::init_service(app(Arc::new(InMemoryRepository::new()))).await;
+
+    for (custom_id, message) in [
+        ("api", "Custom ID 'api' is reserved."),
+        ("Metrics", "Custom ID 'Metrics' is reserved."),
+        ("favicon.ico", "Custom ID 'favicon.ico' is reserved."),
+        ("ab", "Custom ID must be at least 3 characters long."),
+        (
+            "sale/2024",
+            "Custom ID contains a character that is not allowed: '/'.",
+        ),
+    ] {
+        let req = test::TestRequest::post()
+            .uri("/api/v1/shorten")
+            .set_json(json!({ "url": "https://example.com/", "custom_id": custom_id }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{custom_id}");
+        assert_eq!(test::read_body(resp).await, message);
+    }
+}
+
+#[actix_web::test]
+async fn test_shorten_custom_id_that_looks_generated() {
+    let shorten = |url: &str, custom_id: Option<&str>| {
+        test::TestRequest::post()
+            .uri("/api/v1/shorten")
+            .set_json(json!({ "url": url, "custom_id": custom_id }))
+            .to_request()
+    };
+    let next_generated = ID::generate(1).unwrap().0;
+
+    // Rejected by default.
+    let app = test::init_service(app(Arc::new(InMemoryRepository::new()))).await;
+    let resp = test::call_service(
+        &app,
+        shorten("https://example.com/custom", Some(&next_generated)),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // With `reserve` it is accepted and the generator skips over it.
+    let app = test::init_service(app_with_env(
+        Arc::new(InMemoryRepository::new()),
+        &[("CUSTOM_ID_GENERATED_POLICY", "reserve")],
+    ))
+    .await;
+    let body: Value = test::call_and_read_body_json(
+        &app,
+        shorten("https://example.com/custom", Some(&next_generated)),
+    )
+    .await;
+    assert_eq!(body["id"], next_generated.as_str());
+
+    let body: Value =
+        test::call_and_read_body_json(&app, shorten("https://example.com/generated", None)).await;
+    assert_eq!(body["id"], ID::generate(2).unwrap().0);
+}
+
+#[actix_web::test]
+async fn test_shorten_custom_id_conflict() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let app = test::init_service(app(Arc::clone(&repo))).await;
+
+    let shorten = |url: &str| {
+        test::TestRequest::post()
+            .uri("/api/v1/shorten")
+            .set_json(json!({ "url": url, "custom_id": "sale" }))
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, shorten("https://example.com/sale")).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // Retrying with the same destination succeeds without logging a second creation.
+    let resp = test::call_service(&app, shorten("https://example.com/sale")).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(repo.create_logs().len(), 1);
+
+    let resp = test::call_service(&app, shorten("https://example.org/other")).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/sale").to_request()).await;
+    assert_eq!(
+        resp.headers().get(header::LOCATION).unwrap(),
+        "https://example.com/sale"
+    );
+}
+
+#[actix_web::test]
+async fn test_redirect_unknown_id() {
+    let app = test::init_service(app(Arc::new(InMemoryRepository::new()))).await;
+
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/nope").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_redirect_cache_forgets_unknown_id_on_create() {
+    let app = test::init_service(app(Arc::new(InMemoryRepository::new()))).await;
+
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/docs").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/shorten")
+        .set_json(json!({ "url": "https://example.com/docs", "custom_id": "docs" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/docs").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+}
+
+#[actix_web::test]
+async fn test_shorten_expiration_bounds() {
+    let app = test::init_service(app(Arc::new(InMemoryRepository::new()))).await;
+
+    let cases = [
+        (
+            json!({ "url": "https://example.com/", "expires_in": 3600 }),
+            StatusCode::OK,
+        ),
+        (
+            json!({ "url": "https://example.com/", "expires_in": 1 }),
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            json!({ "url": "https://example.com/", "expires_in": 3600, "expires_at": Utc::now() + Duration::hours(1) }),
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            json!({ "url": "https://example.com/", "expires_at": Utc::now() - Duration::hours(1) }),
+            StatusCode::BAD_REQUEST,
+        ),
+    ];
+    for (params, status) in cases {
+        let req = test::TestRequest::post()
+            .uri("/api/v1/shorten")
+            .set_json(&params)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), status, "params: {params}");
+    }
+}
+
+#[actix_web::test]
+async fn test_shared_link_validation() {
+    // walnuk-admin create validates with these same functions.
+    let config = HandlerConfig::init_from_hashmap(&HashMap::new()).unwrap();
+    let now = Utc::now();
+
+    assert!(handlers::parse_destination(" https://example.com/ ").is_ok());
+    assert!(handlers::parse_destination("ftp://example.com/").is_err());
+    assert!(handlers::parse_destination("").is_err());
+
+    assert_eq!(
+        handlers::resolve_expires_at(&config, None, Some(3600), now).unwrap(),
+        Some(now + Duration::hours(1))
+    );
+    assert_eq!(
+        handlers::resolve_expires_at(&config, None, None, now).unwrap(),
+        None
+    );
+    assert!(handlers::resolve_expires_at(&config, None, Some(1), now).is_err());
+    assert!(
+        handlers::resolve_expires_at(&config, Some(now - Duration::hours(1)), None, now).is_err()
+    );
+    assert!(
+        handlers::resolve_expires_at(&config, Some(now + Duration::hours(1)), Some(3600), now)
+            .is_err()
+    );
+}
+
+#[actix_web::test]
+async fn test_redirect_expired() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let url = repo
+        .create(
+            Url::parse("https://example.com/").unwrap(),
+            None,
+            Some(Utc::now() - Duration::seconds(1)),
+        )
+        .await
+        .unwrap()
+        .into_url();
+    let app = test::init_service(app(repo)).await;
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/{}", url.id.0))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::GONE);
+    assert_eq!(test::read_body(resp).await, "URL expired");
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/admin/links/{}", url.id.0))
+        .insert_header(bearer(VIEWER_TOKEN))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["expired"], true);
+    assert_eq!(body["state"]["enabled"], true);
+}
+
+#[actix_web::test]
+async fn test_admin_requires_auth() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let url = repo
+        .create(Url::parse("https://example.com/").unwrap(), None, None)
+        .await
+        .unwrap()
+        .into_url();
+    let app = test::init_service(app(repo)).await;
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri("/api/v1/admin/links")
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri("/api/v1/admin/links")
+            .insert_header(bearer("wrong"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/links/{}/disable", url.id.0))
+            .insert_header(bearer(VIEWER_TOKEN))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn test_admin_disable_and_restore() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let url = repo
+        .create(Url::parse("https://example.com/").unwrap(), None, None)
+        .await
+        .unwrap()
+        .into_url();
+    let id = url.id.0;
+    let app = test::init_service(app(repo)).await;
+
+    // Warm the redirect cache so that disabling has to invalidate it.
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get().uri(&format!("/{id}")).to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/v1/admin/links/{id}/disable"))
+        .insert_header(bearer(OPERATOR_TOKEN))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["id"], id.as_str());
+    assert_eq!(body["state"]["enabled"], false);
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get().uri(&format!("/{id}")).to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::GONE);
+    assert_eq!(test::read_body(resp).await, "URL disabled");
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/admin/links/{id}"))
+        .insert_header(bearer(VIEWER_TOKEN))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["state"]["enabled"], false);
+    assert!(body["state"]["disabled_at"].is_string());
+    assert_eq!(body["expired"], false);
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/links/{id}/restore"))
+            .insert_header(bearer(OPERATOR_TOKEN))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get().uri(&format!("/{id}")).to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/admin/links/missing/disable")
+            .insert_header(bearer(OPERATOR_TOKEN))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_admin_disable_normalizes_id() {
+    let repo = Arc::new(InMemoryRepository::new());
+    repo.create(
+        Url::parse("https://example.com/").unwrap(),
+        Some("docs"),
+        None,
+    )
+    .await
+    .unwrap();
+    let app = test::init_service(app(repo)).await;
+
+    // `d0cS` normalizes to `docs`, the ID the redirect looks up.
+    let req = test::TestRequest::post()
+        .uri("/api/v1/admin/links/d0cS/disable")
+        .insert_header(bearer(OPERATOR_TOKEN))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["id"], "docs");
+    assert_eq!(body["state"]["enabled"], false);
+
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/docs").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::GONE);
+}
+
+#[actix_web::test]
+async fn test_admin_bulk_disable_and_restore() {
+    let repo = Arc::new(InMemoryRepository::new());
+    for id in ["docs", "news"] {
+        repo.create(
+            Url::parse(&format!("https://example.com/{id}")).unwrap(),
+            Some(id),
+            None,
+        )
+        .await
+        .unwrap();
+    }
+    let app = test::init_service(app(repo)).await;
+
+    let bulk = |action: &str, token: &str, body: Value| {
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/links/{action}"))
+            .insert_header(bearer(token))
+            .set_json(body)
+            .to_request()
+    };
+
+    let resp = test::call_service(
+        &app,
+        bulk("disable", VIEWER_TOKEN, json!({ "ids": ["docs"] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let resp =
+        test::call_service(&app, bulk("disable", OPERATOR_TOKEN, json!({ "ids": [] }))).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = test::call_service(
+        &app,
+        bulk("disable", OPERATOR_TOKEN, json!({ "ids": ["docs", " "] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let body: Value = test::call_and_read_body_json(
+        &app,
+        bulk(
+            "disable",
+            OPERATOR_TOKEN,
+            json!({ "ids": ["docs", "d0cS", "news", "missing"], "reason": "spam wave" }),
+        ),
+    )
+    .await;
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0]["id"], "docs");
+    assert_eq!(items[0]["status"], "updated");
+    assert_eq!(items[0]["link"]["state"]["enabled"], false);
+    assert_eq!(items[1]["id"], "news");
+    assert_eq!(items[1]["status"], "updated");
+    assert_eq!(items[2]["id"], "missing");
+    assert_eq!(items[2]["status"], "not_found");
+    assert!(items[2]["link"].is_null());
+
+    for id in ["docs", "news"] {
+        let resp = test::call_service(
+            &app,
+            test::TestRequest::get().uri(&format!("/{id}")).to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::GONE);
+    }
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/admin/audit?actor=bob")
+        .insert_header(bearer(VIEWER_TOKEN))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert!(items.iter().all(|item| item["reason"] == "spam wave"));
+
+    let body: Value = test::call_and_read_body_json(
+        &app,
+        bulk("restore", OPERATOR_TOKEN, json!({ "ids": ["news"] })),
+    )
+    .await;
+    assert_eq!(body["items"][0]["link"]["state"]["enabled"], true);
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/news").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+}
+
+#[actix_web::test]
+async fn test_admin_update_destination() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let url = repo
+        .create(Url::parse("https://example.com/typo").unwrap(), None, None)
+        .await
+        .unwrap()
+        .into_url();
+    let id = url.id.0;
+    let app = test::init_service(app(repo)).await;
+
+    // Warm the redirect cache so that the edit has to invalidate it.
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get().uri(&format!("/{id}")).to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+
+    let patch = |token: &str, url: &str| {
+        test::TestRequest::patch()
+            .uri(&format!("/api/v1/admin/links/{id}"))
+            .insert_header(bearer(token))
+            .set_json(json!({ "url": url }))
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, patch(VIEWER_TOKEN, "https://example.com/fixed")).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let resp = test::call_service(&app, patch(OPERATOR_TOKEN, "ftp://example.com/")).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let body: Value =
+        test::call_and_read_body_json(&app, patch(OPERATOR_TOKEN, "https://example.com/fixed"))
+            .await;
+    assert_eq!(body["original_url"], "https://example.com/fixed");
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get().uri(&format!("/{id}")).to_request(),
+    )
+    .await;
+    assert_eq!(
+        resp.headers().get(header::LOCATION).unwrap(),
+        "https://example.com/fixed"
+    );
+
+    // Setting the current destination again is not a change.
+    let resp = test::call_service(&app, patch(OPERATOR_TOKEN, "https://example.com/fixed")).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/admin/links/{id}/history"))
+        .insert_header(bearer(VIEWER_TOKEN))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["previous_url"], "https://example.com/typo");
+    assert_eq!(items[0]["new_url"], "https://example.com/fixed");
+    assert_eq!(items[0]["changed_by"], "bob");
+
+    let resp = test::call_service(&app, {
+        test::TestRequest::patch()
+            .uri("/api/v1/admin/links/missing")
+            .insert_header(bearer(OPERATOR_TOKEN))
+            .set_json(json!({ "url": "https://example.com/" }))
+            .to_request()
+    })
+    .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri("/api/v1/admin/links/missing/history")
+            .insert_header(bearer(VIEWER_TOKEN))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_admin_delete_link() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let app = test::init_service(app(repo.clone())).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/shorten")
+        .set_json(json!({ "url": "https://example.com/", "custom_id": "docs" }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["id"], "docs");
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/docs").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+    let req = test::TestRequest::patch()
+        .uri("/api/v1/admin/links/docs")
+        .insert_header(bearer(OPERATOR_TOKEN))
+        .set_json(json!({ "url": "https://example.com/private" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let delete = |token: &str, uri: &str| {
+        test::TestRequest::delete()
+            .uri(uri)
+            .insert_header(bearer(token))
+            .to_request()
+    };
+    for uri in [
+        "/api/v1/admin/links/docs",
+        "/api/v1/admin/links/docs?confirm=other",
+    ] {
+        let resp = test::call_service(&app, delete(OPERATOR_TOKEN, uri)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+    let confirmed = "/api/v1/admin/links/docs?confirm=docs";
+    let resp = test::call_service(&app, delete(VIEWER_TOKEN, confirmed)).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let resp = test::call_service(&app, delete(OPERATOR_TOKEN, confirmed)).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert!(repo.get_state("docs").await.unwrap().is_none());
+    assert!(repo.get_create_meta("docs").await.unwrap().is_none());
+    assert!(repo.get_last_access("docs").await.unwrap().is_none());
+    let (logs, _) = repo
+        .list_access_logs_page("docs", &Default::default(), 10, None)
+        .await
+        .unwrap();
+    assert!(logs.is_empty());
+
+    // The audit trail stays, without the destinations it captured.
+    let req = test::TestRequest::get()
+        .uri("/api/v1/admin/audit?link_id=docs")
+        .insert_header(bearer(VIEWER_TOKEN))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["action"], "delete");
+    assert_eq!(items[1]["action"], "update_destination");
+    assert!(
+        items
+            .iter()
+            .all(|item| item["before"].is_null() && item["after"].is_null())
+    );
+
+    // The cached redirect is gone and the ID cannot be claimed again.
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/docs").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let req = test::TestRequest::post()
+        .uri("/api/v1/shorten")
+        .set_json(json!({ "url": "https://example.com/", "custom_id": "docs" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    // Deleting again only purges leftovers; unknown IDs are not found.
+    let resp = test::call_service(&app, delete(OPERATOR_TOKEN, confirmed)).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    let resp = test::call_service(
+        &app,
+        delete(
+            OPERATOR_TOKEN,
+            "/api/v1/admin/links/missing?confirm=missing",
+        ),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_repository_normalizes_ids_on_edit_and_delete() {
+    let repo = Arc::new(InMemoryRepository::new());
+    repo.create(
+        Url::parse("https://example.com/").unwrap(),
+        Some("docs"),
+        None,
+    )
+    .await
+    .unwrap();
+    let now = Utc::now();
+
+    // `d0cS` normalizes to `docs`.
+    let url = repo
+        .update_destination(
+            "d0cS",
+            Url::parse("https://example.com/b").unwrap(),
+            "bob",
+            now,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(url.id.0, "docs");
+    assert_eq!(
+        repo.list_destination_history("docs").await.unwrap().len(),
+        1
+    );
+
+    assert!(repo.delete("d0cS", "bob", now).await.unwrap());
+    assert!(repo.is_deleted("docs").await.unwrap());
+    assert!(repo.is_deleted("d0cS").await.unwrap());
+    assert!(
+        repo.find_by_id(ID::new("docs".to_string()))
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(
+        repo.list_destination_history("docs")
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[actix_web::test]
+async fn test_delete_is_stale_on_other_instances_until_cache_ttl() {
+    let repo = Arc::new(InMemoryRepository::new());
+    repo.create(
+        Url::parse("https://example.com/").unwrap(),
+        Some("docs"),
+        None,
+    )
+    .await
+    .unwrap();
+    let deleting = test::init_service(app(repo.clone())).await;
+    let other =
+        test::init_service(app_with_env(repo, &[("REDIRECT_CACHE_TTL_SECONDS", "1")])).await;
+
+    // Warm the cache of the instance that does not handle the delete.
+    let resp = test::call_service(&other, test::TestRequest::get().uri("/docs").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+
+    let req = test::TestRequest::delete()
+        .uri("/api/v1/admin/links/docs?confirm=docs")
+        .insert_header(bearer(OPERATOR_TOKEN))
+        .to_request();
+    let resp = test::call_service(&deleting, req).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    // Served from the cache without touching storage until the entry expires.
+    let resp = test::call_service(&other, test::TestRequest::get().uri("/docs").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let resp = test::call_service(&other, test::TestRequest::get().uri("/docs").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_admin_audit_log() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let mut ids = Vec::new();
+    for path in ["a", "b"] {
+        let url = repo
+            .create(
+                Url::parse(&format!("https://example.com/{path}")).unwrap(),
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+            .into_url();
+        ids.push(url.id.0);
+    }
+    let app = test::init_service(app(repo)).await;
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/links/{}/disable", ids[0]))
+            .insert_header(bearer(OPERATOR_TOKEN))
+            .insert_header(("x-request-id", "req-1"))
+            .set_json(json!({ "reason": "  phishing report  " }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/links/{}/disable", ids[1]))
+            .insert_header(bearer(OPERATOR_TOKEN))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/links/{}/restore", ids[0]))
+            .insert_header(bearer(OPERATOR_TOKEN))
+            .set_json(json!({ "reason": "x".repeat(501) }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/links/{}/restore", ids[0]))
+            .insert_header(bearer(OPERATOR_TOKEN))
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .set_payload("{")
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::patch()
+            .uri(&format!("/api/v1/admin/links/{}", ids[0]))
+            .insert_header(bearer(OPERATOR_TOKEN))
+            .set_json(json!({ "url": "https://example.com/c", "reason": "moved" }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let list = |query: &str| {
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/admin/audit{query}"))
+            .insert_header(bearer(VIEWER_TOKEN))
+            .to_request()
+    };
+
+    let body: Value = test::call_and_read_body_json(&app, list("")).await;
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 3);
+    assert!(body["next_page_state"].is_null());
+    assert_eq!(items[0]["action"], "update_destination");
+    assert_eq!(items[0]["reason"], "moved");
+    assert_eq!(items[0]["before"]["original_url"], "https://example.com/a");
+    assert_eq!(items[0]["after"]["original_url"], "https://example.com/c");
+
+    let body: Value =
+        test::call_and_read_body_json(&app, list(&format!("?link_id={}", ids[0]))).await;
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    let disable = &items[1];
+    assert_eq!(disable["action"], "disable");
+    assert_eq!(disable["actor"], "bob");
+    assert_eq!(disable["link_id"], ids[0].as_str());
+    assert_eq!(disable["reason"], "phishing report");
+    assert_eq!(disable["request_id"], "req-1");
+    assert_eq!(disable["before"]["enabled"], true);
+    assert_eq!(disable["after"]["enabled"], false);
+    assert!(disable["after"]["disabled_at"].is_string());
+
+    let body: Value = test::call_and_read_body_json(&app, list("?actor=bob&limit=2")).await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 2);
+    let page_state = body["next_page_state"].as_str().unwrap();
+    let body: Value = test::call_and_read_body_json(
+        &app,
+        list(&format!("?actor=bob&limit=2&page_state={page_state}")),
+    )
+    .await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 1);
+    assert_eq!(body["items"][0]["reason"], "phishing report");
+
+    let body: Value = test::call_and_read_body_json(&app, list("?actor=alice")).await;
+    assert!(body["items"].as_array().unwrap().is_empty());
+
+    let resp = test::call_service(&app, {
+        test::TestRequest::get()
+            .uri("/api/v1/admin/audit")
+            .to_request()
+    })
+    .await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_admin_list_links_pagination() {
+    let repo = Arc::new(InMemoryRepository::new());
+    for i in 0..3 {
+        repo.create(
+            Url::parse(&format!("https://example.com/{i}")).unwrap(),
+            None,
+            None,
+        )
+        .await
+        .unwrap()
+        .into_url();
+    }
+    let app = test::init_service(app(repo)).await;
+
+    let mut seen = Vec::new();
+    let mut page_state: Option<String> = None;
+    loop {
+        let uri = match &page_state {
+            Some(ps) => format!("/api/v1/admin/links?limit=2&page_state={ps}"),
+            None => "/api/v1/admin/links?limit=2".to_string(),
+        };
+        let req = test::TestRequest::get()
+            .uri(&uri)
+            .insert_header(bearer(VIEWER_TOKEN))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        for item in body["items"].as_array().unwrap() {
+            assert_eq!(item["enabled"], true);
+            assert_eq!(item["expired"], false);
+            assert_eq!(item["creator_metadata_expired"], false);
+            seen.push(item["original_url"].as_str().unwrap().to_string());
+        }
+        match body["next_page_state"].as_str() {
+            Some(ps) => page_state = Some(ps.to_string()),
+            None => break,
+        }
+    }
+
+    assert_eq!(
+        seen,
+        vec![
+            "https://example.com/2",
+            "https://example.com/1",
+            "https://example.com/0"
+        ]
+    );
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri("/api/v1/admin/links?page_state=!!")
+            .insert_header(bearer(VIEWER_TOKEN))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_admin_views_flag_aged_out_creator_metadata() {
+    let repo = Arc::new(InMemoryRepository::new().with_create_meta_retention(Duration::zero()));
+    let url = repo
+        .create(Url::parse("https://example.com/").unwrap(), None, None)
+        .await
+        .unwrap()
+        .into_url();
+    repo.save_create_meta_if_absent(&url.id.0, url.created_at, Some("192.0.2.1"), None, None)
+        .await
+        .unwrap();
+    let app = test::init_service(app(repo)).await;
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/admin/links")
+        .insert_header(bearer(VIEWER_TOKEN))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["items"][0]["creator_metadata_expired"], true);
+    assert!(body["items"][0]["creator_ip"].is_null());
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/admin/links/{}", url.id.0))
+        .insert_header(bearer(VIEWER_TOKEN))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["creator_metadata_expired"], true);
+}
+
+#[actix_web::test]
+async fn test_admin_list_access_logs() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let url = repo
+        .create(Url::parse("https://example.com/").unwrap(), None, None)
+        .await
+        .unwrap()
+        .into_url();
+    let id = url.id.0;
+    let app = test::init_service(app(repo)).await;
+
+    for _ in 0..2 {
+        let req = test::TestRequest::get()
+            .uri(&format!("/{id}"))
+            .insert_header(("x-request-id", "req-1"))
+            .to_request();
+        test::call_service(&app, req).await;
+    }
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/admin/links/{id}/accesses?limit=1"))
+        .insert_header(bearer(VIEWER_TOKEN))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["status_code"], 308);
+    assert_eq!(items[0]["request_id"], "req-1");
+
+    let page_state = body["next_page_state"].as_str().unwrap();
+    let req = test::TestRequest::get()
+        .uri(&format!(
+            "/api/v1/admin/links/{id}/accesses?limit=1&page_state={page_state}"
+        ))
+        .insert_header(bearer(VIEWER_TOKEN))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 1);
+    assert!(body["next_page_state"].is_null());
+
+    let req = test::TestRequest::get()
+        .uri(&format!(
+            "/api/v1/admin/links/{id}/accesses?status_code=404"
+        ))
+        .insert_header(bearer(VIEWER_TOKEN))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert!(body["items"].as_array().unwrap().is_empty());
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!(
+                "/api/v1/admin/links/{id}/accesses?from=2025-01-02T00:00:00Z&to=2025-01-01T00:00:00Z"
+            ))
+            .insert_header(bearer(VIEWER_TOKEN))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/admin/links")
+        .insert_header(bearer(VIEWER_TOKEN))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert!(body["items"][0]["last_access_at"].is_string());
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri("/api/v1/admin/links/unknown/accesses")
+            .insert_header(bearer(VIEWER_TOKEN))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_admin_list_access_logs_normalizes_id() {
+    let repo = Arc::new(InMemoryRepository::new());
+    repo.create(
+        Url::parse("https://example.com/").unwrap(),
+        Some("docs"),
+        None,
+    )
+    .await
+    .unwrap();
+    let app = test::init_service(app(repo)).await;
+
+    test::call_service(&app, test::TestRequest::get().uri("/docs").to_request()).await;
+
+    // `d0cS` normalizes to `docs`, the ID the redirect logged the access under.
+    let req = test::TestRequest::get()
+        .uri("/api/v1/admin/links/d0cS/accesses")
+        .insert_header(bearer(VIEWER_TOKEN))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 1);
+}
+
+#[actix_web::test]
+async fn test_shorten_rate_limited() {
+    let handler_config = HandlerConfig::init_from_hashmap(&HashMap::new()).unwrap();
+    let rate_limiter = RateLimiter::new(
+        Store::Local(LocalBuckets::default()),
+        Quota {
+            burst: 2,
+            per_minute: 1,
+        },
+        Quota {
+            burst: 0,
+            per_minute: 0,
+        },
+    );
+    let handler = Handler::new(Arc::new(InMemoryRepository::new()), handler_config)
+        .with_rate_limiter(rate_limiter);
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(handler))
+            .configure(routes::configure::<Arc<InMemoryRepository>>),
+    )
+    .await;
+
+    let shorten = |ip: &str| {
+        test::TestRequest::post()
+            .uri("/api/v1/shorten")
+            .peer_addr(format!("{ip}:40000").parse().unwrap())
+            .set_json(json!({ "url": "https://example.com/" }))
+            .to_request()
+    };
+
+    for _ in 0..2 {
+        let resp = test::call_service(&app, shorten("192.0.2.1")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+    let resp = test::call_service(&app, shorten("192.0.2.1")).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = resp
+        .headers()
+        .get(header::RETRY_AFTER)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=60).contains(&retry_after));
+
+    let resp = test::call_service(&app, shorten("192.0.2.2")).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_rate_limit_ignores_spoofed_forwarded_for() {
+    let handler_config = HandlerConfig::init_from_hashmap(&HashMap::new()).unwrap();
+    let rate_limiter = RateLimiter::new(
+        Store::Local(LocalBuckets::default()),
+        Quota {
+            burst: 1,
+            per_minute: 1,
+        },
+        Quota {
+            burst: 0,
+            per_minute: 0,
+        },
+    )
+    .with_trusted_proxies("10.0.0.1".parse().unwrap());
+    let handler = Handler::new(Arc::new(InMemoryRepository::new()), handler_config)
+        .with_rate_limiter(rate_limiter);
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(handler))
+            .configure(routes::configure::<Arc<InMemoryRepository>>),
+    )
+    .await;
+
+    let shorten = |peer: &str, forwarded_for: &str| {
+        test::TestRequest::post()
+            .uri("/api/v1/shorten")
+            .peer_addr(format!("{peer}:40000").parse().unwrap())
+            .insert_header(("x-forwarded-for", forwarded_for.to_string()))
+            .insert_header(("cf-connecting-ip", forwarded_for.to_string()))
+            .set_json(json!({ "url": "https://example.com/" }))
+            .to_request()
+    };
+
+    // A direct client cannot get a fresh bucket by changing the headers.
+    let resp = test::call_service(&app, shorten("192.0.2.1", "198.51.100.1")).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = test::call_service(&app, shorten("192.0.2.1", "198.51.100.2")).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // Behind the trusted proxy, the hop it appended is the client, not the
+    // one the client prepended.
+    let resp = test::call_service(&app, shorten("10.0.0.1", "198.51.100.3, 203.0.113.1")).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = test::call_service(&app, shorten("10.0.0.1", "198.51.100.4, 203.0.113.1")).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let resp = test::call_service(&app, shorten("10.0.0.1", "203.0.113.2")).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_metrics() {
+    let app = test::init_service(app(Arc::new(InMemoryRepository::new()))).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/shorten")
+        .set_json(json!({ "url": "https://example.com/metrics" }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let id = body["id"].as_str().unwrap().to_string();
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get().uri(&format!("/{id}")).to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+
+    let resp =
+        test::call_service(&app, test::TestRequest::get().uri("/metrics").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+
+    assert!(body.contains(r#"walnuk_redirects_total{status="308"}"#));
+    assert!(body.contains(r#"walnuk_creations_total{id_kind="generated"}"#));
+    // Routes are labelled by pattern, not by the requested ID.
+    assert!(body.contains(
+        r#"walnuk_http_request_duration_seconds_count{method="GET",route="/{id}",status="308"}"#
+    ));
+    assert!(!body.contains(&format!("route=\"/{id}\"")));
+}
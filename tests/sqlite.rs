@@ -0,0 +1,518 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use url::Url;
+use walnuk::{
+    domain::{
+        error::RepositoryError,
+        id::ID,
+        models::{
+            AccessEvent, AccessLogFilter, AuditAction, AuditEntry, AuditFilter, CreateOutcome,
+        },
+        repository::ShortenedURLRepository,
+    },
+    sqlite::{config::Config, db::DB},
+};
+
+fn open(name: &str) -> Arc<DB> {
+    let path = std::env::temp_dir().join(format!("walnuk-test-{}-{}.db", name, std::process::id()));
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+    Arc::new(
+        DB::open(Config {
+            path: path.display().to_string(),
+            busy_timeout_ms: 1000,
+        })
+        .unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_create_and_find() {
+    let db = open("create");
+    let expires_at = Utc::now() + Duration::hours(1);
+
+    let a = db
+        .create(Url::parse("https://example.com/a").unwrap(), None, None)
+        .await
+        .unwrap()
+        .into_url();
+    let b = db
+        .create(
+            Url::parse("https://example.com/b").unwrap(),
+            None,
+            Some(expires_at),
+        )
+        .await
+        .unwrap()
+        .into_url();
+    assert_eq!(a.id, ID::generate(1).unwrap());
+    assert_eq!(b.id, ID::generate(2).unwrap());
+
+    let found = db.find_by_id(b.id.clone()).await.unwrap().unwrap();
+    assert_eq!(found.original_url.as_str(), "https://example.com/b");
+    assert_eq!(
+        found.expires_at.unwrap().timestamp_millis(),
+        expires_at.timestamp_millis()
+    );
+    assert!(db.get_state(&b.id.0).await.unwrap().unwrap().enabled);
+
+    assert!(
+        db.find_by_id(ID("missing".to_string()))
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_custom_id_conflicts() {
+    let db = open("custom");
+    let url = Url::parse("https://example.com/first").unwrap();
+
+    let first = db.create(url.clone(), Some("docs"), None).await.unwrap();
+    assert!(matches!(first, CreateOutcome::Created(_)));
+
+    // Same destination again is idempotent.
+    let again = db.create(url, Some("d0cs"), None).await.unwrap();
+    let CreateOutcome::AlreadyExists(again) = again else {
+        panic!("expected the existing link");
+    };
+    assert_eq!(again.id.0, "docs");
+
+    let err = db
+        .create(
+            Url::parse("https://example.com/second").unwrap(),
+            Some("docs"),
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<RepositoryError>(),
+        Some(RepositoryError::IdTaken(id)) if id == "docs"
+    ));
+}
+
+#[tokio::test]
+async fn test_generated_ids_skip_taken_custom_ids() {
+    let db = open("skip");
+    let taken = ID::generate(1).unwrap();
+
+    db.create(
+        Url::parse("https://example.com/custom").unwrap(),
+        Some(&taken.0),
+        None,
+    )
+    .await
+    .unwrap();
+    let generated = db
+        .create(Url::parse("https://example.com/").unwrap(), None, None)
+        .await
+        .unwrap();
+
+    let CreateOutcome::Created(generated) = generated else {
+        panic!("expected a new link");
+    };
+    assert_eq!(generated.id, ID::generate(2).unwrap());
+    let custom = db.find_by_id(taken).await.unwrap().unwrap();
+    assert_eq!(custom.original_url.as_str(), "https://example.com/custom");
+}
+
+#[tokio::test]
+async fn test_list_by_created_at_page() {
+    let db = open("list");
+    for i in 0..5 {
+        db.create(
+            Url::parse(&format!("https://example.com/{i}")).unwrap(),
+            None,
+            None,
+        )
+        .await
+        .unwrap()
+        .into_url();
+        tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+    }
+
+    let mut seen = Vec::new();
+    let mut paging_state = None;
+    loop {
+        let (urls, next) = db.list_by_created_at_page(2, paging_state).await.unwrap();
+        seen.extend(urls.into_iter().map(|u| u.original_url.to_string()));
+        match next {
+            Some(next) => paging_state = Some(next),
+            None => break,
+        }
+    }
+
+    let expected: Vec<String> = (0..5)
+        .rev()
+        .map(|i| format!("https://example.com/{i}"))
+        .collect();
+    assert_eq!(seen, expected);
+}
+
+#[tokio::test]
+async fn test_update_destination_records_history() {
+    let db = open("destination");
+    let url = db
+        .create(Url::parse("https://example.com/a").unwrap(), None, None)
+        .await
+        .unwrap()
+        .into_url();
+    let id = url.id.0.as_str();
+    let first = Utc::now();
+
+    for (i, destination) in ["https://example.com/b", "https://example.com/c"]
+        .into_iter()
+        .enumerate()
+    {
+        let updated = db
+            .update_destination(
+                id,
+                Url::parse(destination).unwrap(),
+                "bob",
+                first + Duration::seconds(i as i64),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.original_url.as_str(), destination);
+        assert_eq!(
+            updated.created_at.timestamp_millis(),
+            url.created_at.timestamp_millis()
+        );
+    }
+    // Unchanged destinations and unknown links leave no history.
+    db.update_destination(
+        id,
+        Url::parse("https://example.com/c").unwrap(),
+        "bob",
+        first,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(
+        db.update_destination(
+            "missing",
+            Url::parse("https://example.com/").unwrap(),
+            "bob",
+            first
+        )
+        .await
+        .unwrap()
+        .is_none()
+    );
+
+    let found = db.find_by_id(url.id.clone()).await.unwrap().unwrap();
+    assert_eq!(found.original_url.as_str(), "https://example.com/c");
+    let (listed, _) = db.list_by_created_at_page(10, None).await.unwrap();
+    assert_eq!(listed[0].original_url.as_str(), "https://example.com/c");
+
+    let history = db.list_destination_history(id).await.unwrap();
+    let history = history
+        .iter()
+        .map(|c| {
+            (
+                c.previous_url.as_str(),
+                c.new_url.as_str(),
+                c.changed_by.as_str(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        history,
+        [
+            ("https://example.com/b", "https://example.com/c", "bob"),
+            ("https://example.com/a", "https://example.com/b", "bob"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_delete_leaves_tombstone() {
+    let db = open("delete");
+    let url = db
+        .create(
+            Url::parse("https://example.com/").unwrap(),
+            Some("docs"),
+            None,
+        )
+        .await
+        .unwrap()
+        .into_url();
+    let now = Utc::now();
+    db.save_create_meta_if_absent("docs", url.created_at, Some("203.0.113.1"), None, None)
+        .await
+        .unwrap();
+    db.log_access("docs", now, None, None, None, 308)
+        .await
+        .unwrap();
+    db.set_last_access("docs", now, 308).await.unwrap();
+    db.update_destination(
+        "docs",
+        Url::parse("https://example.com/b").unwrap(),
+        "bob",
+        now,
+    )
+    .await
+    .unwrap();
+    let mut entry = AuditEntry::new("bob", AuditAction::UpdateDestination, "docs", now);
+    entry.after = Some(serde_json::json!({ "original_url": "https://example.com/b" }));
+    db.record_audit(&entry).await.unwrap();
+
+    // `d0cS` normalizes to `docs`.
+    assert!(!db.is_deleted("d0cS").await.unwrap());
+    assert!(db.delete("d0cS", "bob", now).await.unwrap());
+    assert!(db.is_deleted("docs").await.unwrap());
+    assert!(db.is_deleted("d0cS").await.unwrap());
+    assert!(db.find_by_id(url.id.clone()).await.unwrap().is_none());
+    assert!(db.get_state("docs").await.unwrap().is_none());
+    assert!(db.get_create_meta("docs").await.unwrap().is_none());
+    assert!(db.get_last_access("docs").await.unwrap().is_none());
+    assert!(
+        db.list_destination_history("docs")
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    let (logs, _) = db
+        .list_access_logs_page("docs", &AccessLogFilter::default(), 10, None)
+        .await
+        .unwrap();
+    assert!(logs.is_empty());
+    let (listed, _) = db.list_by_created_at_page(10, None).await.unwrap();
+    assert!(listed.is_empty());
+    let (audit, _) = db
+        .list_audit_page(&AuditFilter::default(), 10, None)
+        .await
+        .unwrap();
+    assert_eq!(audit.len(), 1);
+    assert_eq!(audit[0].after, None);
+
+    let err = db
+        .create(
+            Url::parse("https://example.com/").unwrap(),
+            Some("docs"),
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<RepositoryError>(),
+        Some(RepositoryError::IdDeleted(id)) if id == "docs"
+    ));
+
+    assert!(db.delete("docs", "bob", now).await.unwrap());
+    assert!(!db.delete("missing", "bob", now).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_list_audit_page_filters() {
+    let db = open("audit");
+    let start = Utc::now();
+    let actions = [
+        ("bob", AuditAction::Disable, "docs"),
+        ("bob", AuditAction::Restore, "docs"),
+        ("carol", AuditAction::Disable, "blog"),
+        ("carol", AuditAction::Delete, "docs"),
+    ];
+    for (i, (actor, action, link_id)) in actions.into_iter().enumerate() {
+        let mut entry =
+            AuditEntry::new(actor, action, link_id, start + Duration::seconds(i as i64));
+        entry.reason = Some(format!("reason {i}"));
+        entry.before = Some(serde_json::json!({ "enabled": i % 2 == 0 }));
+        db.record_audit(&entry).await.unwrap();
+    }
+
+    let (page, paging_state) = db
+        .list_audit_page(&AuditFilter::default(), 3, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        page.iter().map(|e| e.action).collect::<Vec<_>>(),
+        [
+            AuditAction::Delete,
+            AuditAction::Disable,
+            AuditAction::Restore
+        ]
+    );
+    assert_eq!(
+        page[0].before,
+        Some(serde_json::json!({ "enabled": false }))
+    );
+    let (rest, paging_state) = db
+        .list_audit_page(&AuditFilter::default(), 3, paging_state)
+        .await
+        .unwrap();
+    assert_eq!(rest.len(), 1);
+    assert_eq!(rest[0].reason.as_deref(), Some("reason 0"));
+    assert!(paging_state.is_none());
+
+    let filter = AuditFilter {
+        link_id: Some("docs".to_string()),
+        actor: Some("bob".to_string()),
+    };
+    let (page, _) = db.list_audit_page(&filter, 10, None).await.unwrap();
+    assert_eq!(
+        page.iter().map(|e| e.action).collect::<Vec<_>>(),
+        [AuditAction::Restore, AuditAction::Disable]
+    );
+
+    let filter = AuditFilter {
+        actor: Some("carol".to_string()),
+        ..Default::default()
+    };
+    let (page, _) = db.list_audit_page(&filter, 10, None).await.unwrap();
+    assert_eq!(
+        page.iter().map(|e| e.link_id.as_str()).collect::<Vec<_>>(),
+        ["docs", "blog"]
+    );
+}
+
+#[tokio::test]
+async fn test_state_meta_and_logs() {
+    let db = open("state");
+    let url = db
+        .create(Url::parse("https://example.com/").unwrap(), None, None)
+        .await
+        .unwrap()
+        .into_url();
+    let id = url.id.0.as_str();
+    let now = Utc::now();
+
+    db.set_enabled(id, false, now).await.unwrap();
+    let state = db.get_state(id).await.unwrap().unwrap();
+    assert!(!state.enabled);
+    assert!(state.disabled_at.is_some());
+
+    db.save_create_meta_if_absent(id, url.created_at, Some("192.0.2.1"), None, None)
+        .await
+        .unwrap();
+    db.save_create_meta_if_absent(id, url.created_at, Some("192.0.2.2"), None, None)
+        .await
+        .unwrap();
+    let (_, ip, ua, _) = db.get_create_meta(id).await.unwrap().unwrap();
+    assert_eq!(ip, "192.0.2.1");
+    assert_eq!(ua, "");
+
+    for (offset, status) in [(0, 308), (1, 410)] {
+        db.log_access(
+            id,
+            now + Duration::seconds(offset),
+            None,
+            None,
+            None,
+            status,
+        )
+        .await
+        .unwrap();
+    }
+    let logs = db
+        .list_access_logs_page(id, &AccessLogFilter::default(), 10, None)
+        .await
+        .unwrap()
+        .0;
+    assert_eq!(logs.iter().map(|l| l.4).collect::<Vec<_>>(), vec![410, 308]);
+
+    db.set_last_access(id, now, 308).await.unwrap();
+    let (_, status) = db.get_last_access(id).await.unwrap().unwrap();
+    assert_eq!(status, 308);
+}
+
+#[tokio::test]
+async fn test_log_access_batch() {
+    let db = open("batch");
+    let now = Utc::now();
+    let event = |id: &str, offset: i64, status_code: i32, update_last_access: bool| AccessEvent {
+        id: id.to_string(),
+        ts: now + Duration::seconds(offset),
+        ip: Some("192.0.2.1".to_string()),
+        user_agent: None,
+        request_id: None,
+        status_code,
+        update_last_access,
+    };
+
+    db.log_access_batch(&[
+        event("a", 0, 308, true),
+        event("a", 1, 410, true),
+        event("missing", 0, 404, false),
+    ])
+    .await
+    .unwrap();
+
+    let logs = db
+        .list_access_logs_page("a", &AccessLogFilter::default(), 10, None)
+        .await
+        .unwrap()
+        .0;
+    assert_eq!(logs.iter().map(|l| l.4).collect::<Vec<_>>(), vec![410, 308]);
+    assert_eq!(logs[0].1, "192.0.2.1");
+    let (_, status) = db.get_last_access("a").await.unwrap().unwrap();
+    assert_eq!(status, 410);
+
+    assert_eq!(
+        db.list_access_logs_page("missing", &AccessLogFilter::default(), 10, None)
+            .await
+            .unwrap()
+            .0
+            .len(),
+        1
+    );
+    assert!(db.get_last_access("missing").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_list_access_logs_page_filters() {
+    let db = open("access_log_filters");
+    // SQLite keeps millisecond precision.
+    let now = DateTime::from_timestamp_millis(Utc::now().timestamp_millis()).unwrap();
+    let events = (0..5)
+        .map(|i| AccessEvent {
+            id: "a".to_string(),
+            ts: now + Duration::seconds(i),
+            ip: None,
+            user_agent: None,
+            request_id: None,
+            status_code: if i % 2 == 0 { 308 } else { 410 },
+            update_last_access: true,
+        })
+        .collect::<Vec<_>>();
+    db.log_access_batch(&events).await.unwrap();
+
+    let filter = AccessLogFilter {
+        from: Some(now + Duration::seconds(1)),
+        to: Some(now + Duration::seconds(4)),
+        status_code: None,
+    };
+    let (page, next) = db
+        .list_access_logs_page("a", &filter, 2, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        page.iter().map(|l| l.0).collect::<Vec<_>>(),
+        vec![events[3].ts, events[2].ts]
+    );
+    let (page, next) = db
+        .list_access_logs_page("a", &filter, 2, next)
+        .await
+        .unwrap();
+    assert_eq!(
+        page.iter().map(|l| l.0).collect::<Vec<_>>(),
+        vec![events[1].ts]
+    );
+    assert!(next.is_none());
+
+    let filter = AccessLogFilter {
+        status_code: Some(410),
+        ..Default::default()
+    };
+    let (page, _) = db
+        .list_access_logs_page("a", &filter, 10, None)
+        .await
+        .unwrap();
+    assert_eq!(page.len(), 2);
+}